@@ -0,0 +1,199 @@
+//! Fluent assertions on responses for tests
+//!
+//! This module is only available with the `testing` feature. Every
+//! assertion panics with a message describing the mismatch, so it reads
+//! like a failed `assert_eq!` in test output.
+//!
+//! ```rust,ignore
+//! use rust_fetch::assert::ResponseAssert;
+//!
+//! response
+//!     .assert()
+//!     .status(200)
+//!     .header("content-type", "application/json")
+//!     .json_pointer("/id", &serde_json::json!(1));
+//! ```
+
+use crate::types::{Response, ResponseBody};
+
+/// Adds [`ResponseAssert::assert`] to [`Response`]
+pub trait ResponseAssert {
+    /// Start a chain of assertions on this response
+    fn assert(&self) -> Assertions<'_>;
+}
+
+impl ResponseAssert for Response {
+    fn assert(&self) -> Assertions<'_> {
+        Assertions { response: self }
+    }
+}
+
+/// Chainable assertions on a borrowed [`Response`]
+#[derive(Debug, Clone, Copy)]
+pub struct Assertions<'a> {
+    response: &'a Response,
+}
+
+impl Assertions<'_> {
+    /// Assert the status code equals `expected`
+    ///
+    /// # Panics
+    ///
+    /// If the status differs.
+    #[track_caller]
+    pub fn status(&self, expected: u16) -> &Self {
+        assert!(
+            self.response.status == expected,
+            "expected status {}, got {} for {}",
+            expected,
+            self.response.status,
+            self.response.url
+        );
+        self
+    }
+
+    /// Assert the header `name` is present, whatever its value
+    ///
+    /// # Panics
+    ///
+    /// If the header is missing.
+    #[track_caller]
+    pub fn has_header(&self, name: &str) -> &Self {
+        assert!(
+            self.response.headers.contains(name),
+            "expected header `{}` to be present for {}",
+            name,
+            self.response.url
+        );
+        self
+    }
+
+    /// Assert the header `name` has exactly the value `expected`
+    ///
+    /// Header names are case-insensitive; for repeated headers any one of
+    /// the values may match.
+    ///
+    /// # Panics
+    ///
+    /// If the header is missing or none of its values is `expected`.
+    #[track_caller]
+    pub fn header(&self, name: &str, expected: &str) -> &Self {
+        match self.response.headers.get(name) {
+            Some(values) => assert!(
+                values.iter().any(|value| value == expected),
+                "expected header `{name}` to be {expected:?}, got {values:?}"
+            ),
+            None => panic!("expected header `{name}` to be {expected:?}, but it is missing"),
+        }
+        self
+    }
+
+    /// Assert the body, read as text, contains `needle`
+    ///
+    /// # Panics
+    ///
+    /// If the body doesn't contain `needle`.
+    #[track_caller]
+    pub fn body_contains(&self, needle: &str) -> &Self {
+        let body = match &self.response.body {
+            ResponseBody::Json(json) => json.to_string(),
+            ResponseBody::Text(text) => text.clone(),
+            ResponseBody::Binary(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            ResponseBody::Empty => String::new(),
+        };
+        assert!(body.contains(needle), "expected body to contain {needle:?}, got {body:?}");
+        self
+    }
+
+    /// Assert the JSON body has `expected` at `pointer` (RFC 6901, e.g.
+    /// `/items/0/id`)
+    ///
+    /// Text bodies are parsed as JSON first, so this also works when the
+    /// server didn't label the response as JSON.
+    ///
+    /// # Panics
+    ///
+    /// If the body is not JSON, or the value at `pointer` is missing or
+    /// differs from `expected`.
+    #[track_caller]
+    pub fn json_pointer(&self, pointer: &str, expected: &serde_json::Value) -> &Self {
+        let parsed;
+        let json = match &self.response.body {
+            ResponseBody::Json(json) => json,
+            ResponseBody::Text(text) => match serde_json::from_str(text) {
+                Ok(json) => {
+                    parsed = json;
+                    &parsed
+                }
+                Err(err) => panic!("expected a JSON body, but it failed to parse: {err}"),
+            },
+            other => panic!("expected a JSON body, got {other:?}"),
+        };
+        match json.pointer(pointer) {
+            Some(actual) => assert!(
+                actual == expected,
+                "expected {expected} at `{pointer}`, got {actual}"
+            ),
+            None => panic!("expected {expected} at `{pointer}`, but nothing is there"),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response() -> Response {
+        let mut response = Response {
+            status: 200,
+            body: ResponseBody::Json(json!({"id": 1, "items": [{"name": "a"}]})),
+            ..Response::default()
+        };
+        response.headers.insert("Content-Type", "application/json");
+        response
+    }
+
+    #[test]
+    fn test_passing_assertions_chain() {
+        response()
+            .assert()
+            .status(200)
+            .has_header("content-type")
+            .header("Content-Type", "application/json")
+            .body_contains("\"name\":\"a\"")
+            .json_pointer("/id", &json!(1))
+            .json_pointer("/items/0/name", &json!("a"));
+
+        let text = Response {
+            body: ResponseBody::Text(r#"{"ok":true}"#.to_string()),
+            ..Response::default()
+        };
+        text.assert().json_pointer("/ok", &json!(true));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected status 404, got 200")]
+    fn test_status_mismatch_panics() {
+        response().assert().status(404);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected header `etag` to be \"x\", but it is missing")]
+    fn test_missing_header_panics() {
+        response().assert().header("etag", "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected body to contain \"missing\"")]
+    fn test_body_contains_panics() {
+        response().assert().body_contains("missing");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 at `/id`, got 1")]
+    fn test_json_pointer_mismatch_panics() {
+        response().assert().status(200).json_pointer("/id", &json!(2));
+    }
+}
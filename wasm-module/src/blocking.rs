@@ -0,0 +1,205 @@
+//! Synchronous client for non-async callers (native only)
+//!
+//! This module is only available with the `blocking` feature and is never
+//! compiled for WASM, where blocking the main thread isn't possible. A
+//! [`BlockingClient`] wraps an ordinary [`Client`] together with a private
+//! single-threaded runtime and waits for each request on it, so retries,
+//! hooks and every other [`ClientBuilder`] option behave exactly as in the
+//! async client.
+//!
+//! ```rust,ignore
+//! use rust_fetch::blocking::BlockingClient;
+//!
+//! let client = BlockingClient::from_builder(Client::builder().base_url("https://api.example.com"))?;
+//! let user = client.get("/users/1")?;
+//! let created = client.post("/users").json(&new_user)?.send()?;
+//! ```
+//!
+//! Don't call it from inside an async runtime: blocking a runtime thread
+//! on another runtime panics.
+
+use crate::client::{Client, ClientBuilder, RequestBuilder};
+use crate::error::{Error, Result};
+use crate::types::{Headers, Method, Response};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An HTTP client whose requests block the calling thread
+///
+/// Cheap to clone; clones share the connection pool and runtime.
+#[derive(Clone)]
+pub struct BlockingClient {
+    inner: Client,
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+impl BlockingClient {
+    /// Create a blocking client with default configuration
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`BlockingClient::from_builder`].
+    pub fn new() -> Result<Self> {
+        Self::from_builder(Client::builder())
+    }
+
+    /// Create a blocking client from a configured [`ClientBuilder`]
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Network` if the runtime can't be started, or with the
+    /// error of [`ClientBuilder::build`].
+    pub fn from_builder(builder: ClientBuilder) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::network("Failed to start the blocking runtime", e))?;
+        Ok(Self {
+            inner: builder.build()?,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The async client requests are sent with
+    pub fn inner(&self) -> &Client {
+        &self.inner
+    }
+
+    /// Make a GET request
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`].
+    pub fn get(&self, url: impl AsRef<str>) -> Result<Response> {
+        self.request(Method::Get, url).send()
+    }
+
+    /// Make a HEAD request
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`].
+    pub fn head(&self, url: impl AsRef<str>) -> Result<Response> {
+        self.request(Method::Head, url).send()
+    }
+
+    /// Make a DELETE request
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`].
+    pub fn delete(&self, url: impl AsRef<str>) -> Result<Response> {
+        self.request(Method::Delete, url).send()
+    }
+
+    /// Make a POST request
+    pub fn post(&self, url: impl AsRef<str>) -> BlockingRequestBuilder {
+        self.request(Method::Post, url)
+    }
+
+    /// Make a PUT request
+    pub fn put(&self, url: impl AsRef<str>) -> BlockingRequestBuilder {
+        self.request(Method::Put, url)
+    }
+
+    /// Make a PATCH request
+    pub fn patch(&self, url: impl AsRef<str>) -> BlockingRequestBuilder {
+        self.request(Method::Patch, url)
+    }
+
+    /// Create a request builder for any method
+    pub fn request(&self, method: Method, url: impl AsRef<str>) -> BlockingRequestBuilder {
+        BlockingRequestBuilder {
+            inner: self.inner.request(method, url),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for BlockingClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingClient").finish_non_exhaustive()
+    }
+}
+
+/// Builder for a request sent with [`BlockingRequestBuilder::send`]
+///
+/// Offers the common options directly; [`BlockingRequestBuilder::with`]
+/// reaches the rest of the async [`RequestBuilder`] API.
+pub struct BlockingRequestBuilder {
+    inner: RequestBuilder,
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+impl BlockingRequestBuilder {
+    /// Set request header
+    #[must_use]
+    pub fn header(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(|request| request.header(name, value))
+    }
+
+    /// Set multiple headers
+    #[must_use]
+    pub fn headers(self, headers: Headers) -> Self {
+        self.with(|request| request.headers(headers))
+    }
+
+    /// Add a query parameter
+    #[must_use]
+    pub fn query(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(|request| request.query(key, value))
+    }
+
+    /// Set request body as JSON
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::json`].
+    pub fn json<T: serde::Serialize>(self, json: &T) -> Result<Self> {
+        let Self { inner, runtime } = self;
+        Ok(Self {
+            inner: inner.json(json)?,
+            runtime,
+        })
+    }
+
+    /// Set request body as text
+    #[must_use]
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.with(|request| request.text(text))
+    }
+
+    /// Set request body as raw bytes
+    #[must_use]
+    pub fn bytes(self, bytes: Vec<u8>) -> Self {
+        self.with(|request| request.bytes(bytes))
+    }
+
+    /// Set request timeout
+    #[must_use]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.with(|request| request.timeout(timeout))
+    }
+
+    /// Configure the underlying async [`RequestBuilder`]
+    ///
+    /// ```rust,ignore
+    /// client.request(Method::Get, url).with(|request| request.bearer_auth(token)).send()?;
+    /// ```
+    #[must_use]
+    pub fn with(self, configure: impl FnOnce(RequestBuilder) -> RequestBuilder) -> Self {
+        Self {
+            inner: configure(self.inner),
+            runtime: self.runtime,
+        }
+    }
+
+    /// Send the request and wait for the response
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`].
+    pub fn send(self) -> Result<Response> {
+        self.runtime.block_on(self.inner.send())
+    }
+}
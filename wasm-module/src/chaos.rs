@@ -0,0 +1,164 @@
+//! Fault injection for resilience testing
+//!
+//! This module is only available with the `testing` feature, so chaos can
+//! never be switched on in a production build by accident.
+
+use crate::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Configuration for injected latency and failures
+///
+/// Every request attempt first waits for the configured latency, then fails
+/// with the configured error with probability `failure_rate`, otherwise
+/// fails with one of the configured status codes with probability
+/// `status_rate`, and otherwise proceeds normally. Decisions come from a
+/// seeded RNG, so a given seed always produces the same sequence.
+#[derive(Clone)]
+pub struct ChaosConfig {
+    latency: Duration,
+    failure_rate: f64,
+    failure: Option<Arc<dyn Fn() -> Error + Send + Sync>>,
+    status_rate: f64,
+    statuses: Vec<u16>,
+    seed: u64,
+}
+
+impl ChaosConfig {
+    /// Create a configuration that injects nothing
+    pub fn new() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            failure_rate: 0.0,
+            failure: None,
+            status_rate: 0.0,
+            statuses: Vec::new(),
+            seed: 0,
+        }
+    }
+
+    /// Add artificial latency before every attempt
+    #[must_use]
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Fail a fraction (`0.0..=1.0`) of attempts with the error produced by `error`
+    #[must_use]
+    pub fn fail(mut self, rate: f64, error: impl Fn() -> Error + Send + Sync + 'static) -> Self {
+        self.failure_rate = rate.clamp(0.0, 1.0);
+        self.failure = Some(Arc::new(error));
+        self
+    }
+
+    /// Answer a fraction (`0.0..=1.0`) of attempts with an HTTP error using
+    /// a status code picked uniformly from `statuses`
+    #[must_use]
+    pub fn status(mut self, rate: f64, statuses: Vec<u16>) -> Self {
+        self.status_rate = rate.clamp(0.0, 1.0);
+        self.statuses = statuses;
+        self
+    }
+
+    /// Seed the random number generator
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ChaosConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaosConfig")
+            .field("latency", &self.latency)
+            .field("failure_rate", &self.failure_rate)
+            .field("status_rate", &self.status_rate)
+            .field("statuses", &self.statuses)
+            .field("seed", &self.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Chaos state shared by all clones of a client
+pub(crate) struct Chaos {
+    config: ChaosConfig,
+    rng: Mutex<u64>,
+}
+
+impl Chaos {
+    /// Create chaos state from a configuration
+    pub(crate) fn new(config: ChaosConfig) -> Self {
+        let rng = Mutex::new(config.seed);
+        Self { config, rng }
+    }
+
+    /// Decide the latency and outcome for the next attempt
+    pub(crate) fn next(&self) -> (Duration, Option<Error>) {
+        let mut rng = self.rng.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if let Some(failure) = &self.config.failure {
+            if next_unit(&mut rng) < self.config.failure_rate {
+                return (self.config.latency, Some(failure()));
+            }
+        }
+
+        if !self.config.statuses.is_empty() && next_unit(&mut rng) < self.config.status_rate {
+            let len = self.config.statuses.len() as u64;
+            let index = usize::try_from(splitmix64(&mut rng) % len).unwrap_or(0);
+            let status = self.config.statuses[index];
+            return (
+                self.config.latency,
+                Some(Error::http(status, None)),
+            );
+        }
+
+        (self.config.latency, None)
+    }
+}
+
+/// Advance a `SplitMix64` generator
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Draw a uniform value in `[0, 1)`
+fn next_unit(state: &mut u64) -> f64 {
+    let high = u32::try_from(splitmix64(state) >> 32).unwrap_or(u32::MAX);
+    f64::from(high) / 4_294_967_296.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_outcomes() {
+        let config = ChaosConfig::new().status(0.5, vec![500, 503]).seed(42);
+        let outcomes = |chaos: Chaos| {
+            (0..32)
+                .map(|_| match chaos.next().1 {
+                    Some(Error::Http { status, .. }) => status,
+                    _ => 200,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let first = outcomes(Chaos::new(config.clone()));
+        assert_eq!(first, outcomes(Chaos::new(config)));
+        assert!(first.contains(&200));
+        assert!(first.iter().any(|s| *s != 200));
+    }
+}
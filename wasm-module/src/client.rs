@@ -4,28 +4,95 @@
 //! retries, interceptors, and various configuration options.
 
 use crate::{
-    error::{Error, Result},
-    types::{Body, Headers, Method, RequestConfig, Response, ResponseBody, ResponseFormat, RetryConfig},
+    auth::{AuthScheme, OAuthConfig, TokenFuture, TokenSource},
+    error::{Error, FailedAttempts, Result},
+    time::{Clock, Deadline, SystemClock},
+    types::{
+        AttemptRecord, Body, BodyFactory, ErrorBodyEnd, Fallback, FetchPriority, FormEncoding, Headers, LoadBalancePolicy, Method, PatchOp, ProgressCallback, RequestConfig, RequestContext, RequestMetrics, Response, SerializeNulls, Upserted,
+        ResponseBody, ResponseFormat, RetryConfig, DEFAULT_REQUEST_ID_HEADERS,
+    },
 };
-use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::types::{MultipartBody, PartSource, ResponseStream, StreamBody, StreamPart};
+#[cfg(target_arch = "wasm32")]
+use crate::types::TimingBreakdown;
+use serde::Serialize;
+use sha2::Digest;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use futures_util::future::{AbortHandle, Abortable};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::future_to_promise;
 
+#[cfg(all(target_arch = "wasm32", feature = "native-fetch"))]
+mod native_fetch;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod connect_timing;
+
 /// HTTP client for making requests
 #[derive(Clone)]
 pub struct Client {
-    inner: reqwest::Client,
+    inner: Arc<Transport>,
     config: Arc<ClientConfig>,
 }
 
+/// Callback producing a fresh bearer token after a `401`
+type TokenRefresh = Arc<dyn Fn() -> TokenFuture + Send + Sync>;
+
+/// Hook run on the final headers of every attempt
+type BeforeSend = Arc<dyn Fn(&Method, &str, &mut Headers) + Send + Sync>;
+
+/// Hook observing an attempt together with its request's context
+type AttemptHook = Arc<dyn Fn(&AttemptRecord, &RequestContext) + Send + Sync>;
+
 /// Client configuration
-#[derive(Clone)]
+// Mirrors the independent switches of `ClientBuilder`
+#[allow(clippy::struct_excessive_bools)]
 struct ClientConfig {
     default_headers: Headers,
     timeout: Duration,
     retry_config: Option<RetryConfig>,
     base_url: Option<String>,
+    base_urls: Vec<String>,
+    load_balance: LoadBalancePolicy,
+    rotate_base_on_retry: bool,
+    next_base: AtomicUsize,
+    strict_auto: bool,
+    error_on_status: bool,
+    strict_headers: bool,
+    honor_keep_alive_hints: bool,
+    request_id_headers: Vec<String>,
+    transfer_quota: Option<u64>,
+    bytes_transferred: AtomicU64,
+    sensitive_headers: Vec<String>,
+    before_send: Option<BeforeSend>,
+    on_retry: Option<AttemptHook>,
+    on_complete: Option<AttemptHook>,
+    timing_breakdown: bool,
+    request_metrics: bool,
+    accept_charset: Option<String>,
+    oauth: Option<Arc<TokenSource>>,
+    on_unauthorized: Option<TokenRefresh>,
+    /// Tokens from `on_unauthorized`, by the origin that rejected the old one
+    refreshed_tokens: RwLock<HashMap<String, String>>,
+    error_body_limit: Option<(usize, ErrorBodyEnd)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: Option<tokio::runtime::Handle>,
+    #[cfg(not(target_arch = "wasm32"))]
+    cookie_jar: Option<Arc<reqwest::cookie::Jar>>,
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    pinned_certificates: Vec<String>,
+    in_flight: Mutex<HashMap<String, Vec<(u64, AbortHandle)>>>,
+    next_request_id: AtomicU64,
+    #[cfg(feature = "testing")]
+    chaos: Option<Arc<crate::chaos::Chaos>>,
+    #[cfg(feature = "testing")]
+    mock: Option<crate::mock::MockClient>,
+    clock: Arc<dyn Clock>,
 }
 
 impl Client {
@@ -64,75 +131,687 @@ impl Client {
         self.request(Method::Patch, url)
     }
     
+    /// GET many URLs concurrently and collect the JSON results by key
+    ///
+    /// Each `(key, url)` pair is fetched with the client's usual headers,
+    /// base URL and retries, and its body deserialized into `T`. At most
+    /// [`BATCH_CONCURRENCY`] requests run at once. Every key appears in the
+    /// result with its own outcome, so one failure doesn't hide the rest;
+    /// if a key is repeated, the last response to finish wins.
+    pub async fn get_typed_map<K, T>(&self, items: Vec<(K, String)>) -> HashMap<K, Result<T>>
+    where
+        K: Eq + std::hash::Hash,
+        T: serde::de::DeserializeOwned,
+    {
+        use futures_util::StreamExt;
+        
+        futures_util::stream::iter(items)
+            .map(|(key, url)| async move {
+                let result = self.get(url).await.and_then(Response::into_json);
+                (key, result)
+            })
+            .buffer_unordered(BATCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+    
+    /// Download `url` and check its body against a SHA-256 digest
+    ///
+    /// The digest is computed chunk by chunk as the body streams in, so the
+    /// body is buffered only once. `expected_sha256` is hex, in either case.
+    /// Returns the body on a match and `Error::ChecksumMismatch` otherwise;
+    /// Retries are not applied.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::ChecksumMismatch` if the digest differs, with
+    /// `Error::Http` on a non-2xx status, and with `Error::Network` or
+    /// `Error::Timeout` if the transfer fails.
+    pub async fn download_verified(
+        &self,
+        url: impl AsRef<str>,
+        expected_sha256: &str,
+    ) -> Result<Vec<u8>> {
+        use futures_util::StreamExt;
+        
+        let expected = expected_sha256.trim().to_ascii_lowercase();
+        if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(Error::InvalidInput {
+                parameter: "expected_sha256".to_string(),
+                reason: "Expected 64 hex characters".to_string(),
+            });
+        }
+        
+        let config = self.request_config(Method::Get);
+        let response = self.send_request_on_runtime(self.resolve(url.as_ref()), config).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = retry_after_header(&response, self.config.clock.system_time());
+            return Err(Error::Http {
+                status: status.as_u16(),
+                status_text: crate::error::status_text(status.as_u16()),
+                body: response.text().await.ok(),
+                retry_after,
+            });
+        }
+        
+        let mut hasher = sha2::Sha256::new();
+        let mut body = Vec::with_capacity(
+            response
+                .content_length()
+                .and_then(|len| usize::try_from(len).ok())
+                .unwrap_or(0),
+        );
+        let mut chunks = response.bytes_stream();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            self.record_transfer(chunk.len());
+            hasher.update(&chunk);
+            body.extend_from_slice(&chunk);
+        }
+        
+        let actual = hex_encode(&hasher.finalize());
+        if actual != expected {
+            return Err(Error::ChecksumMismatch { expected, actual });
+        }
+        Ok(body)
+    }
+    
+    /// Open a connection to `url`'s host ahead of the first real request
+    ///
+    /// Sends a `HEAD` request and leaves its connection in the pool, so the
+    /// next request to the same host skips the TCP and TLS handshakes. Any
+    /// status counts as success since only the connection matters, and
+    /// redirects are not followed. No-op on WASM, where the browser manages
+    /// connections.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the network or timeout error of the `HEAD` request.
+    pub async fn warm_up(&self, url: impl AsRef<str>) -> Result<()> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let config = RequestConfig {
+                follow_redirects: false,
+                ..self.request_config(Method::Head)
+            };
+            self.send_request_on_runtime(self.resolve(url.as_ref()), config).await?;
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = url;
+        Ok(())
+    }
+    
+    /// Make a TRACE request, for diagnosing what intermediaries do to a
+    /// request
+    ///
+    /// The server echoes the request it received in the response body. Since
+    /// that echo can expose credentials to anything that sees the response
+    /// (including scripts, in the classic cross-site tracing attack), the
+    /// client's sensitive headers (see [`ClientBuilder::sensitive_headers`])
+    /// are stripped before sending, after default headers, OAuth tokens and
+    /// the `before_send` hook have been applied. Opt out per request with
+    /// [`RequestBuilder::allow_trace_sensitive`]. Many servers disable TRACE
+    /// and answer `405`.
+    pub fn trace(&self, url: impl AsRef<str>) -> RequestBuilder {
+        self.request(Method::Trace, url)
+    }
+    
+    /// POST a JSON body and fetch the resource it created
+    ///
+    /// Only a `201 Created` with a `Location` header is followed: the
+    /// location is resolved against the POST URL and fetched with a `GET`,
+    /// whose response is returned. Any other successful response is
+    /// returned as-is.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`], for the POST or the follow-up
+    /// GET.
+    pub async fn post_and_follow<B: Serialize>(
+        &self,
+        url: impl AsRef<str>,
+        body: &B,
+    ) -> Result<Response> {
+        let created = self.post(url).json(body)?.send().await?;
+        match created.created_location() {
+            Some(location) => self.execute(location, self.request_config(Method::Get)).await,
+            None => Ok(created),
+        }
+    }
+    
+    /// PUT a JSON body to create or replace the resource at `url`
+    ///
+    /// Reports whether the resource was created (`201`) or updated (any
+    /// other success, typically `200` or `204`). The response body is
+    /// deserialized into `T` when there is one; a `204 No Content` or an
+    /// empty body gives `None`.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`], or with `Error::Parse` if a
+    /// non-empty body doesn't match `T`.
+    pub async fn put_upsert<B, T>(&self, url: impl AsRef<str>, body: &B) -> Result<Upserted<T>>
+    where
+        B: Serialize,
+        T: serde::de::DeserializeOwned,
+    {
+        let response = self.put(url).json(body)?.send().await?;
+        let created = response.was_created();
+        let empty = response.status == 204
+            || matches!(response.body, ResponseBody::Empty)
+            || response.bytes().is_some_and(<[u8]>::is_empty);
+        let value = if empty { None } else { Some(response.into_json()?) };
+        Ok(Upserted { value, created })
+    }
+    
+    /// Stream the items of an offset/limit paginated JSON API
+    ///
+    /// Requests `url` with `offset` and `limit` query parameters (replacing
+    /// any already present), starting at offset 0. `extract` locates the
+    /// items array in each page's JSON, and every item is deserialized into
+    /// `T`. Paging stops after the first page with fewer than `limit`
+    /// items, so the final partial (or empty) page ends the stream. Pages
+    /// are fetched lazily as the stream is polled. A failed request or an
+    /// item that doesn't match `T` is yielded as an error and ends the
+    /// stream.
+    pub fn paginate_offset<T, F>(
+        &self,
+        url: impl AsRef<str>,
+        limit: usize,
+        extract: F,
+    ) -> impl futures_util::Stream<Item = Result<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        F: Fn(&serde_json::Value) -> Vec<serde_json::Value>,
+    {
+        use futures_util::StreamExt;
+        
+        let client = self.clone();
+        let url = self.resolve(url.as_ref());
+        let extract = Arc::new(extract);
+        let state = (0usize, std::collections::VecDeque::new(), limit == 0);
+        let invalid = (limit == 0).then(|| Error::InvalidInput {
+            parameter: "limit".to_string(),
+            reason: "Page size must be greater than zero".to_string(),
+        });
+        
+        let pages = futures_util::stream::unfold(state, move |(offset, mut items, done)| {
+            let client = client.clone();
+            let url = url.clone();
+            let extract = extract.clone();
+            async move {
+                if let Some(item) = items.pop_front() {
+                    let item = serde_json::from_value(item).map_err(Error::from);
+                    return Some((item, (offset, items, done)));
+                }
+                if done {
+                    return None;
+                }
+                
+                let page_url = with_query(
+                    &url,
+                    &[("offset", offset.to_string()), ("limit", limit.to_string())],
+                );
+                let mut config = client.request_config(Method::Get);
+                config.response_format = ResponseFormat::Json;
+                let page = match client.execute(page_url, config).await {
+                    Ok(response) => response,
+                    Err(err) => return Some((Err(err), (offset, items, true))),
+                };
+                let page = extract(page.json().unwrap_or(&serde_json::Value::Null));
+                let done = page.len() < limit;
+                let offset = offset + page.len();
+                items.extend(page);
+                
+                let item = items.pop_front()?;
+                let item = serde_json::from_value(item).map_err(Error::from);
+                Some((item, (offset, items, done)))
+            }
+        });
+        futures_util::stream::iter(invalid.map(Err)).chain(pages)
+    }
+    
+    /// Stream the items of a cursor paginated JSON API
+    ///
+    /// The first page is requested from `url` as-is; each following page
+    /// passes the cursor returned by `extract_cursor` as the `cursor_param`
+    /// query parameter (replacing any already present). `extract_items`
+    /// locates the items array in each page's JSON, and every item is
+    /// deserialized into `T`. Paging stops once `extract_cursor` returns
+    /// `None` or a cursor already used, or a page has no items, so a server
+    /// handing out the same cursor can't keep the stream going. As with
+    /// [`Client::paginate_offset`], pages are fetched lazily and the first
+    /// error ends the stream.
+    pub fn paginate_cursor<T, I, C>(
+        &self,
+        url: impl AsRef<str>,
+        cursor_param: &str,
+        extract_items: I,
+        extract_cursor: C,
+    ) -> impl futures_util::Stream<Item = Result<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        I: Fn(&serde_json::Value) -> Vec<serde_json::Value>,
+        C: Fn(&serde_json::Value) -> Option<String>,
+    {
+        let client = self.clone();
+        let url = self.resolve(url.as_ref());
+        let cursor_param = cursor_param.to_string();
+        let extract = Arc::new((extract_items, extract_cursor));
+        let state = (None::<String>, std::collections::HashSet::new(), std::collections::VecDeque::new(), false);
+        
+        futures_util::stream::unfold(state, move |(mut cursor, mut seen, mut items, mut done)| {
+            let client = client.clone();
+            let url = url.clone();
+            let cursor_param = cursor_param.clone();
+            let extract = extract.clone();
+            async move {
+                loop {
+                    if let Some(item) = items.pop_front() {
+                        let item = serde_json::from_value(item).map_err(Error::from);
+                        return Some((item, (cursor, seen, items, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+                    
+                    let page_url = match &cursor {
+                        Some(cursor) => with_query(&url, &[(cursor_param.as_str(), cursor.clone())]),
+                        None => url.clone(),
+                    };
+                    let mut config = client.request_config(Method::Get);
+                    config.response_format = ResponseFormat::Json;
+                    let page = match client.execute(page_url, config).await {
+                        Ok(response) => response,
+                        Err(err) => return Some((Err(err), (cursor, seen, items, true))),
+                    };
+                    let page = page.json().unwrap_or(&serde_json::Value::Null);
+                    let (extract_items, extract_cursor) = &*extract;
+                    let page_items = extract_items(page);
+                    let empty = page_items.is_empty();
+                    items.extend(page_items);
+                    cursor = extract_cursor(page);
+                    done = empty || !cursor.as_ref().is_some_and(|next| seen.insert(next.clone()));
+                }
+            }
+        })
+    }
+    
     /// Create a request builder
+    ///
+    /// `url` is resolved against the client's base URL; an absolute URL
+    /// bypasses it. Use [`RequestBuilder::base_url`] to resolve against a
+    /// different base for this request only.
     pub fn request(&self, method: Method, url: impl AsRef<str>) -> RequestBuilder {
-        let url = if let Some(base) = &self.config.base_url {
-            format!("{}{}", base.trim_end_matches('/'), url.as_ref())
+        RequestBuilder {
+            client: self.clone(),
+            config: self.request_config(method),
+            url: self.resolve(url.as_ref()),
+            path: url.as_ref().to_string(),
+            retry_invalid: false,
+        }
+    }
+    
+    /// Fresh request config carrying the client's default headers and timeout
+    fn request_config(&self, method: Method) -> RequestConfig {
+        RequestConfig {
+            method,
+            headers: self.config.default_headers.clone(),
+            timeout: Some(self.config.timeout),
+            ..RequestConfig::default()
+        }
+    }
+    
+    /// Prefix `url` with the configured base URL, if any
+    ///
+    /// With several base URLs, one is picked per call according to the
+    /// load-balancing policy.
+    fn resolve(&self, url: &str) -> String {
+        let base = if self.config.base_urls.is_empty() {
+            self.config.base_url.as_deref()
         } else {
-            url.as_ref().to_string()
+            let index = match self.config.load_balance {
+                LoadBalancePolicy::RoundRobin => {
+                    self.config.next_base.fetch_add(1, Ordering::Relaxed)
+                }
+                LoadBalancePolicy::Random => random_index(),
+            };
+            Some(self.config.base_urls[index % self.config.base_urls.len()].as_str())
+        };
+        match base {
+            Some(base) => join_base(base, url),
+            None => url.to_string(),
+        }
+    }
+    
+    /// Move a URL built from one of the base URLs onto a different one
+    ///
+    /// Returns `None` if the URL wasn't resolved against a configured base
+    /// URL or there is no other base to move to.
+    fn rebase(&self, url: &str) -> Option<String> {
+        let bases = &self.config.base_urls;
+        if bases.len() < 2 {
+            return None;
+        }
+        let (current, path) = bases
+            .iter()
+            .enumerate()
+            .find_map(|(i, base)| strip_base(base, url).map(|path| (i, path)))?;
+        let offset = match self.config.load_balance {
+            LoadBalancePolicy::RoundRobin => 1,
+            LoadBalancePolicy::Random => 1 + random_index() % (bases.len() - 1),
+        };
+        Some(join_base(&bases[(current + offset) % bases.len()], path))
+    }
+    
+    /// Follow a single redirect manually
+    ///
+    /// Resolves the response's `Location` header against its URL and re-issues
+    /// `original` against the new target, applying the same method rules as
+    /// automatic redirect handling:
+    ///
+    /// - `301`, `302`, `303`: the request becomes a bodyless `GET` (a `HEAD`
+    ///   stays a `HEAD`)
+    /// - `307`, `308`: the original method and body are preserved
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidInput` if the response is not one of these
+    /// statuses or has no valid `Location` header, and otherwise like
+    /// [`RequestBuilder::send`].
+    pub async fn follow(&self, response: &Response, original: &RequestBuilder) -> Result<Response> {
+        let invalid = |reason: &str| Error::InvalidInput {
+            parameter: "response".to_string(),
+            reason: reason.to_string(),
         };
         
-        RequestBuilder {
-            client: self.clone(),
-            config: RequestConfig {
-                method,
-                headers: self.config.default_headers.clone(),
-                body: None,
-                timeout: Some(self.config.timeout),
-                follow_redirects: true,
-                max_redirects: 10,
-                response_format: ResponseFormat::Auto,
-            },
-            url,
+        let location = response
+            .location()
+            .ok_or_else(|| invalid("response has no Location header"))?;
+        let target = reqwest::Url::parse(&response.url)
+            .and_then(|base| base.join(location))
+            .map_err(|_| invalid("Location header is not a valid URL"))?;
+        
+        let mut config = original.config.clone();
+        if !redirect_request(&mut config, response.status) {
+            return Err(invalid("response is not a redirect"));
+        }
+        
+        self.execute(target.to_string(), config).await
+    }
+    
+    /// Total request and response body bytes transferred by this client
+    pub fn bytes_transferred(&self) -> u64 {
+        self.config.bytes_transferred.load(Ordering::Relaxed)
+    }
+    
+    /// Add body bytes to the transfer counter
+    fn record_transfer(&self, bytes: usize) {
+        let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+        self.config.bytes_transferred.fetch_add(bytes, Ordering::Relaxed);
+    }
+    
+    /// Execute a request built from a standalone [`RequestConfig`]
+    ///
+    /// The URL is resolved against the config's `base_url`, or else the
+    /// client's base URL, and client default
+    /// headers are added unless the config already sets them; the request
+    /// then goes through the same retry path as [`RequestBuilder::send`].
+    /// Because `RequestConfig` is serializable, request specs can be
+    /// persisted (e.g. in a job queue) and replayed this way.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`].
+    pub async fn execute_config(
+        &self,
+        url: impl AsRef<str>,
+        mut config: RequestConfig,
+    ) -> Result<Response> {
+        for (name, values) in self.config.default_headers.iter() {
+            if !config.headers.contains(name) {
+                for value in values {
+                    config.headers.insert(name.clone(), value.clone());
+                }
+            }
         }
+        let url = match &config.base_url {
+            Some(base) => join_base(base, url.as_ref()),
+            None => self.resolve(url.as_ref()),
+        };
+        self.execute(url, config).await
     }
     
     /// Execute a request with the given configuration
+    ///
+    /// When the request finally fails, the configured fallback (if any) gets
+    /// a chance to turn the error into a response.
     async fn execute(&self, url: String, config: RequestConfig) -> Result<Response> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(handle) = &self.config.runtime {
+            let client = self.clone();
+            return handle
+                .spawn(async move { client.execute_here(url, config).await })
+                .await
+                .unwrap_or_else(|e| Err(Error::network("Request task failed", e)));
+        }
+        
+        self.execute_here(url, config).await
+    }
+    
+    /// Execute a request on the current runtime, applying the fallback
+    async fn execute_here(&self, url: String, config: RequestConfig) -> Result<Response> {
+        let fallback = config.fallback.clone();
+        match self.execute_with(url, config, Ok, |_| false, None).await {
+            Err(err) => match fallback.and_then(|fallback| fallback.recover(&err)) {
+                Some(response) => Ok(response),
+                None => Err(err),
+            },
+            result => result,
+        }
+    }
+    
+    /// Execute a request, mapping each successful response with `map`
+    ///
+    /// Tagged requests are registered for [`Client::cancel_tag`] while they
+    /// run, including retries, and fail with `Error::Cancelled` if aborted.
+    async fn execute_with<T, M, R>(
+        &self,
+        url: String,
+        config: RequestConfig,
+        map: M,
+        retry_if: R,
+        records: Option<&mut Vec<AttemptRecord>>,
+    ) -> Result<T>
+    where
+        M: Fn(Response) -> Result<T>,
+        R: Fn(&Error) -> bool,
+    {
+        let Some(tag) = config.tag.clone() else {
+            return self.run_attempts(url, config, map, retry_if, records).await;
+        };
+        
+        let (handle, registration) = AbortHandle::new_pair();
+        let _guard = self.register_tagged(tag, handle);
+        let attempts = self.run_attempts(url, config, map, retry_if, records);
+        Abortable::new(attempts, registration)
+            .await
+            .unwrap_or(Err(Error::Cancelled))
+    }
+    
+    /// Abort every in-flight request tagged with `tag`
+    ///
+    /// Aborted requests fail with `Error::Cancelled`, wherever they are in
+    /// their attempt/retry cycle. Requests with other tags, or started
+    /// after this call, are unaffected. Returns how many were aborted.
+    pub fn cancel_tag(&self, tag: &str) -> usize {
+        let mut in_flight = self.config.in_flight.lock().unwrap_or_else(PoisonError::into_inner);
+        let handles = in_flight.remove(tag).unwrap_or_default();
+        for (_, handle) in &handles {
+            handle.abort();
+        }
+        handles.len()
+    }
+    
+    /// Cookies the cookie store would send to `url`, as `(name, value)`
+    ///
+    /// Empty unless [`ClientBuilder::cookie_store`] is enabled, and always
+    /// empty on WASM, where cookies belong to the browser.
+    pub fn cookies(&self, url: impl AsRef<str>) -> Vec<(String, String)> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use reqwest::cookie::CookieStore;
+            
+            let Some(jar) = &self.config.cookie_jar else {
+                return Vec::new();
+            };
+            let Ok(url) = reqwest::Url::parse(&self.resolve(url.as_ref())) else {
+                return Vec::new();
+            };
+            let Some(header) = jar.cookies(&url) else {
+                return Vec::new();
+            };
+            header
+                .to_str()
+                .unwrap_or("")
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = url;
+            Vec::new()
+        }
+    }
+    
+    /// Number of in-flight requests tagged with `tag`
+    pub fn in_flight(&self, tag: &str) -> usize {
+        let in_flight = self.config.in_flight.lock().unwrap_or_else(PoisonError::into_inner);
+        in_flight.get(tag).map_or(0, Vec::len)
+    }
+    
+    /// Track a tagged request until the returned guard is dropped
+    fn register_tagged(&self, tag: String, handle: AbortHandle) -> TagGuard<'_> {
+        let id = self.config.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let mut in_flight = self.config.in_flight.lock().unwrap_or_else(PoisonError::into_inner);
+        in_flight.entry(tag.clone()).or_default().push((id, handle));
+        TagGuard {
+            client: self,
+            tag,
+            id,
+        }
+    }
+    
+    /// Run the attempt/retry loop, mapping each successful response with `map`
+    ///
+    /// Errors from `map` go through the same retry decision as transport
+    /// errors; `retry_if` can mark errors retryable beyond the defaults.
+    /// When `records` is given, the outcome of every attempt is appended.
+    async fn run_attempts<T, M, R>(
+        &self,
+        mut url: String,
+        config: RequestConfig,
+        map: M,
+        retry_if: R,
+        mut records: Option<&mut Vec<AttemptRecord>>,
+    ) -> Result<T>
+    where
+        M: Fn(Response) -> Result<T>,
+        R: Fn(&Error) -> bool,
+    {
         let retry_config = self.config.retry_config.clone();
+        let clock = &*self.config.clock;
+        if config.deadline.is_some_and(|deadline| deadline.remaining_on(clock).is_zero()) {
+            return Err(Error::Timeout { duration_ms: 0 });
+        }
         
+        let request_started = self.config.request_metrics.then(|| Stopwatch::start(clock));
         let mut attempt = 0;
+        let mut backoff_total = Duration::ZERO;
+        // `on_unauthorized` runs at most once per request, retries included
+        let mut refreshed = false;
         let mut last_error;
+        let mut last_record;
+        // A success whose body asked for a retry, returned if none follows
+        let mut held_back;
         
         loop {
-            match self.execute_once(url.clone(), config.clone()).await {
-                Ok(response) => return Ok(response),
+            let started = Stopwatch::start(clock);
+            let result = self.attempt(url.clone(), within_deadline(&config, clock), &mut refreshed).await;
+            let result = result.map(|mut response| {
+                response.metrics = request_started.as_ref().map(|request_started| RequestMetrics {
+                    attempts: attempt + 1,
+                    total: request_started.elapsed(clock),
+                    last_attempt: started.elapsed(clock),
+                    backoff_total,
+                });
+                response
+            });
+            // A success whose body asks for a retry goes through the retry decision
+            let (result, held) = retry_on_body(result, retry_config.as_ref(), attempt, clock);
+            held_back = held;
+            let status = result.as_ref().ok().map(|response| response.status);
+            let result = result.and_then(&map);
+            
+            let error = result.as_ref().err();
+            last_record = AttemptRecord {
+                attempt: attempt + 1,
+                method: config.method,
+                url: url.clone(),
+                status: status.or(match error {
+                    Some(Error::Http { status, .. }) => Some(*status),
+                    _ => None,
+                }),
+                error: error.map(Error::kind),
+                elapsed: started.elapsed(clock),
+                tag: config.tag.clone(),
+            };
+            if let Some(records) = records.as_deref_mut() {
+                records.push(last_record.clone());
+            }
+            
+            match result {
+                Ok(value) => {
+                    self.complete(&last_record, &config.context);
+                    return Ok(value);
+                }
                 Err(err) => {
                     last_error = err;
                     
                     if let Some(retry) = &retry_config {
-                        if attempt >= retry.max_retries {
+                        // Not exhausted: this error is returned as-is
+                        if !retry.should_retry(&last_error) && !retry_if(&last_error) {
                             break;
                         }
                         
-                        if !last_error.is_retryable() {
+                        let delay = retry_delay(&last_error, attempt + 1, retry);
+                        // Out of attempts, or no point waiting for a retry
+                        // the deadline won't allow
+                        if attempt >= retry.max_retries
+                            || config.deadline.is_some_and(|deadline| deadline.remaining_on(clock) <= delay)
+                        {
+                            if attempt > 0 {
+                                last_error = Error::RetriesExhausted {
+                                    attempts: attempt + 1,
+                                    last: Box::new(last_error),
+                                };
+                            }
                             break;
                         }
                         
-                        attempt += 1;
-                        let delay = calculate_retry_delay(attempt, retry);
-                        
-                        #[cfg(not(target_arch = "wasm32"))]
-                        {
-                            tokio::time::sleep(delay).await;
+                        if let Some(hook) = &self.config.on_retry {
+                            hook(&last_record, &config.context);
                         }
-                        
-                        #[cfg(target_arch = "wasm32")]
-                        {
-                            let delay_ms = delay.as_millis() as i32;
-                            wasm_bindgen_futures::JsFuture::from(
-                                js_sys::Promise::new(&mut |resolve, _| {
-                                    web_sys::window()
-                                        .unwrap()
-                                        .set_timeout_with_callback_and_timeout_and_arguments_0(
-                                            &resolve,
-                                            delay_ms,
-                                        )
-                                        .unwrap();
-                                }),
-                            )
-                            .await
-                            .unwrap();
+                        attempt += 1;
+                        sleep(delay).await;
+                        backoff_total += delay;
+                        if self.config.rotate_base_on_retry && config.base_url.is_none() {
+                            if let Some(next) = self.rebase(&url) {
+                                url = next;
+                            }
                         }
                     } else {
                         break;
@@ -141,272 +820,3330 @@ impl Client {
             }
         }
         
-        Err(last_error)
+        if held_back.is_some() {
+            last_record.error = None;
+        }
+        self.complete(&last_record, &config.context);
+        match held_back {
+            Some(response) => map(response),
+            None => Err(last_error),
+        }
     }
     
-    /// Execute a single request attempt
-    async fn execute_once(&self, url: String, config: RequestConfig) -> Result<Response> {
-        let mut request = self.inner.request(config.method.to_reqwest(), &url);
+    /// Report a request's final attempt to the `on_complete` hook
+    fn complete(&self, record: &AttemptRecord, context: &RequestContext) {
+        if let Some(hook) = &self.config.on_complete {
+            hook(record, context);
+        }
+    }
+    
+    /// Run a single attempt, applying injected chaos when configured
+    ///
+    /// A `401` refreshes the token and re-sends the attempt unless an earlier
+    /// attempt of the request already did, as recorded in `refreshed`.
+    async fn attempt(&self, url: String, config: RequestConfig, refreshed: &mut bool) -> Result<Response> {
+        #[cfg(feature = "testing")]
+        if let Some(chaos) = &self.config.chaos {
+            let (latency, failure) = chaos.next();
+            if !latency.is_zero() {
+                sleep(latency).await;
+            }
+            if let Some(error) = failure {
+                return Err(error);
+            }
+        }
         
-        // Set headers
-        for (name, values) in config.headers.iter() {
-            for value in values {
-                request = request.header(name.as_str(), value.as_str());
+        let Some(refresh) = self.config.on_unauthorized.as_ref().filter(|_| !*refreshed) else {
+            return self.execute_once(url, config).await;
+        };
+        // A request's own credentials are never replaced
+        let Some(origin) = origin(&url).filter(|_| !self.own_authorization(&config)) else {
+            return self.execute_once(url, config).await;
+        };
+        match self.execute_once(url.clone(), config.clone()).await {
+            Err(Error::Http { status: 401, .. }) => {
+                *refreshed = true;
+                let token = refresh().await?;
+                self.config
+                    .refreshed_tokens
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(origin, token);
+                self.execute_once(url, config).await
             }
+            result => result,
         }
+    }
+    
+    /// Whether `config` carries an `Authorization` header other than the
+    /// client default
+    fn own_authorization(&self, config: &RequestConfig) -> bool {
+        config.headers.get("authorization").is_some_and(|value| {
+            Some(value) != self.config.default_headers.get("authorization")
+        })
+    }
+    
+    /// Read at most `limit` bytes of a body from the chosen end, lossily
+    /// decoded as UTF-8
+    ///
+    /// A read error ends the capture with whatever arrived so far.
+    async fn read_capped(
+        &self,
+        response: reqwest::Response,
+        limit: usize,
+        keep: ErrorBodyEnd,
+    ) -> String {
+        use futures_util::StreamExt;
         
-        // Set body
-        if let Some(body) = config.body {
-            let content_type = body.content_type();
-            request = request.header("content-type", content_type);
-            request = request.body(body.to_bytes()?);
+        let mut kept = std::collections::VecDeque::new();
+        let mut chunks = response.bytes_stream();
+        while let Some(Ok(chunk)) = chunks.next().await {
+            self.record_transfer(chunk.len());
+            match keep {
+                ErrorBodyEnd::Head => {
+                    let room = limit - kept.len();
+                    kept.extend(&chunk[..chunk.len().min(room)]);
+                    if kept.len() == limit {
+                        break;
+                    }
+                }
+                ErrorBodyEnd::Tail => {
+                    let tail = &chunk[chunk.len().saturating_sub(limit)..];
+                    let overflow = (kept.len() + tail.len()).saturating_sub(limit);
+                    kept.drain(..overflow);
+                    kept.extend(tail);
+                }
+            }
         }
         
-        // Set timeout
+        let text = String::from_utf8_lossy(kept.make_contiguous()).into_owned();
+        // A cut may split a multi-byte character at the truncated edge
+        match keep {
+            ErrorBodyEnd::Head => text.trim_end_matches('\u{FFFD}').to_string(),
+            ErrorBodyEnd::Tail => text.trim_start_matches('\u{FFFD}').to_string(),
+        }
+    }
+    
+    /// Throw away a response body the caller doesn't want
+    ///
+    /// On native, a small body is drained so the connection can go back to
+    /// the pool; larger or unknown-length bodies are dropped unread, which
+    /// closes the connection instead of downloading data nobody will look
+    /// at. On WASM the body is simply never awaited.
+    async fn discard_body(&self, response: reqwest::Response) {
         #[cfg(not(target_arch = "wasm32"))]
-        if let Some(timeout) = config.timeout {
-            request = request.timeout(timeout);
+        if response.content_length().is_some_and(|len| len <= DISCARD_DRAIN_LIMIT) {
+            let mut response = response;
+            while let Ok(Some(chunk)) = response.chunk().await {
+                self.record_transfer(chunk.len());
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        drop(response);
+    }
+    
+    /// Read and decode a response body based on format preference and content type
+    async fn read_body(
+        &self,
+        response: reqwest::Response,
+        format: ResponseFormat,
+        content_type: &str,
+        config: &RequestConfig,
+    ) -> Result<ResponseBody> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(progress) = &config.on_progress {
+            let bytes = self.read_with_progress(response, progress).await?;
+            return self.decode_body(&bytes, format, content_type, config.expect_json);
         }
         
-        // Execute request
-        let response = request.send().await?;
+        let bytes = response.bytes().await
+            .map_err(|e| Error::parse("Failed to read response body", e))?;
+        self.record_transfer(bytes.len());
+        self.decode_body(&bytes, format, content_type, config.expect_json)
+    }
+    
+    /// Read a whole body chunk by chunk, reporting progress after each one
+    ///
+    /// An empty body is reported once, as zero bytes.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn read_with_progress(
+        &self,
+        mut response: reqwest::Response,
+        progress: &ProgressCallback,
+    ) -> Result<Vec<u8>> {
+        let total = response.content_length();
+        let mut bytes = Vec::with_capacity(total.and_then(|n| usize::try_from(n).ok()).unwrap_or(0));
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::parse("Failed to read response body", e))?
+        {
+            self.record_transfer(chunk.len());
+            bytes.extend_from_slice(&chunk);
+            progress.report(bytes.len() as u64, total);
+        }
+        if bytes.is_empty() {
+            progress.report(0, total);
+        }
+        Ok(bytes)
+    }
+    
+    /// Decode a fully read body based on format preference and content type
+    fn decode_body(
+        &self,
+        bytes: &[u8],
+        format: ResponseFormat,
+        content_type: &str,
+        expect_json: bool,
+    ) -> Result<ResponseBody> {
+        let body = match format {
+            ResponseFormat::Json => {
+                let json: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| {
+                    if expect_json {
+                        Error::parse(
+                            format!(
+                                "Failed to parse JSON response (Content-Type: {:?}): {}",
+                                content_type,
+                                body_snippet(bytes)
+                            ),
+                            e,
+                        )
+                    } else {
+                        Error::parse("Failed to parse JSON response", e)
+                    }
+                })?;
+                ResponseBody::Json(json)
+            }
+            ResponseFormat::Text => ResponseBody::Text(decode_text(bytes, content_type)),
+            ResponseFormat::Binary => ResponseBody::Binary(bytes.to_vec()),
+            ResponseFormat::Auto => {
+                if content_type.contains("application/json") {
+                    match serde_json::from_slice::<serde_json::Value>(bytes) {
+                        Ok(json) => ResponseBody::Json(json),
+                        Err(e) if self.config.strict_auto => {
+                            return Err(Error::parse("Failed to parse JSON response", e));
+                        }
+                        Err(_) => {
+                            // Fallback to text if JSON parsing fails
+                            match String::from_utf8(bytes.to_vec()) {
+                                Ok(text) => ResponseBody::Text(text),
+                                Err(_) => ResponseBody::Binary(bytes.to_vec()),
+                            }
+                        }
+                    }
+                } else if content_type.contains("text/") || content_type.contains("xml") {
+                    ResponseBody::Text(decode_text(bytes, content_type))
+                } else {
+                    ResponseBody::Binary(bytes.to_vec())
+                }
+            }
+        };
+        Ok(body)
+    }
+    
+    /// Execute a single request attempt over the configured transport
+    async fn execute_once(&self, url: String, config: RequestConfig) -> Result<Response> {
+        #[cfg(feature = "testing")]
+        if let Some(mock) = &self.config.mock {
+            return self.mock_once(mock, &url, &config).await;
+        }
+        #[cfg(all(target_arch = "wasm32", feature = "native-fetch"))]
+        {
+            self.fetch_once(url, config).await
+        }
+        #[cfg(not(all(target_arch = "wasm32", feature = "native-fetch")))]
+        {
+            self.reqwest_once(url, config).await
+        }
+    }
+    
+    /// Whether a non-2xx `status` is returned as a response rather than
+    /// an `Error::Http`
+    pub(super) fn accepts(&self, config: &RequestConfig, status: u16) -> bool {
+        !self.config.error_on_status || config.success_statuses.iter().any(|range| range.contains(&status))
+    }
+    
+    /// Answer one request from a mock without touching the network
+    #[cfg(feature = "testing")]
+    async fn mock_once(
+        &self,
+        mock: &crate::mock::MockClient,
+        url: &str,
+        config: &RequestConfig,
+    ) -> Result<Response> {
+        let url = merge_query(url, &config.query, &config.query_replaced);
+        let config = self.prepare_send(&url, config).await?;
+        let body = config
+            .body
+            .as_ref()
+            .filter(|body| !body.is_stream())
+            .map(|body| body.encode(config.form_encoding))
+            .transpose()?;
+        let response = mock.respond(crate::mock::MockRequest {
+            method: config.method,
+            url,
+            headers: config.headers.clone(),
+            body,
+        })?;
+        let accepted = self.accepts(&config, response.status);
+        finish(response, accepted, &config, self.config.clock.system_time())
+    }
+    
+    /// Send one request through reqwest and read the response
+    #[cfg_attr(all(target_arch = "wasm32", feature = "native-fetch"), allow(dead_code))]
+    async fn reqwest_once(&self, url: String, config: RequestConfig) -> Result<Response> {
+        let clock = &*self.config.clock;
+        let started = Stopwatch::start(clock);
+        #[cfg(not(target_arch = "wasm32"))]
+        let phases = self
+            .config
+            .timing_breakdown
+            .then(|| Arc::new(connect_timing::PhaseRecorder::default()));
+        #[cfg(not(target_arch = "wasm32"))]
+        let sent = connect_timing::scoped(phases.as_ref(), self.send_request_counted(&url, &config)).await;
+        #[cfg(target_arch = "wasm32")]
+        let sent = self.send_request_counted(&url, &config).await;
+        let (response, redirects) = sent?;
+        let head = started.elapsed(clock);
         
         // Parse response
         let status = response.status().as_u16();
-        let status_text = response.status().canonical_reason().unwrap_or("Unknown").to_string();
+        let status_text = crate::error::status_text(status);
         let url = response.url().to_string();
+        let accepted = self.accepts(&config, status);
+        #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+        let peer_certificate = crate::tls::leaf_der(&response).map(<[u8]>::to_vec);
+        #[cfg(not(all(feature = "tls", not(target_arch = "wasm32"))))]
+        let peer_certificate = None;
         
-        // Parse headers
-        let mut headers = Headers::new();
-        for (name, value) in response.headers() {
-            if let Ok(value_str) = value.to_str() {
-                headers.insert(name.to_string(), value_str);
+        // Capture a bounded error body without buffering all of it
+        if let Some((limit, keep)) = self.config.error_body_limit {
+            if !response.status().is_success() && !accepted {
+                let retry_after = retry_after_header(&response, self.config.clock.system_time());
+                let body = self.read_capped(response, limit, keep).await;
+                return Err(Error::Http {
+                    status,
+                    status_text,
+                    body: Some(body),
+                    retry_after,
+                });
+            }
+        }
+        
+        let headers = response_headers(&response);
+        if self.config.strict_headers {
+            check_content_length(&headers)?;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(rotation) = &self.inner.rotation {
+            if self.config.honor_keep_alive_hints {
+                if let Some(hint) = crate::types::KeepAlive::from_headers(&headers).and_then(|hint| hint.timeout) {
+                    rotation.honor_keep_alive(&url, hint);
+                }
             }
         }
         
         // Parse body based on format preference and content type
         let content_type = headers.get_first("content-type").unwrap_or("");
-        let body = match config.response_format {
-            ResponseFormat::Json => {
-                let json: serde_json::Value = response.json().await
-                    .map_err(|e| Error::parse("Failed to parse JSON response", e))?;
-                ResponseBody::Json(json)
-            }
-            ResponseFormat::Text => {
-                let text = response.text().await
-                    .map_err(|e| Error::parse("Failed to read text response", e))?;
-                ResponseBody::Text(text)
+        let format = choose_format(&config, content_type);
+        let discard = config.discard_body && (response.status().is_success() || accepted);
+        let read = async {
+            if discard {
+                self.discard_body(response).await;
+                Ok(ResponseBody::Empty)
+            } else {
+                self.read_body(response, format, content_type, &config).await
             }
-            ResponseFormat::Binary => {
-                let bytes = response.bytes().await
-                    .map_err(|e| Error::parse("Failed to read binary response", e))?;
-                ResponseBody::Binary(bytes.to_vec())
+        };
+        
+        #[cfg(not(target_arch = "wasm32"))]
+        let body = match config.read_timeout {
+            Some(limit) => tokio::time::timeout(limit, read)
+                .await
+                .map_err(|_| Error::Timeout {
+                    duration_ms: crate::error::duration_ms(limit),
+                })??,
+            None => read.await?,
+        };
+        
+        #[cfg(target_arch = "wasm32")]
+        let body = read.await?;
+        
+        #[cfg(not(target_arch = "wasm32"))]
+        let timing = phases.map(|phases| phases.breakdown(head, started.elapsed(clock)));
+        #[cfg(target_arch = "wasm32")]
+        let timing = if self.config.timing_breakdown {
+            let total = started.elapsed(clock);
+            resource_timing(&url).or(Some(TimingBreakdown {
+                wait: head,
+                download: total.saturating_sub(head),
+                total,
+                ..TimingBreakdown::default()
+            }))
+        } else {
+            None
+        };
+        
+        let response = Response {
+            status,
+            status_text,
+            headers,
+            body,
+            url,
+            request_id_headers: self.config.request_id_headers.clone(),
+            timing,
+            metrics: None,
+            redirects,
+            peer_certificate,
+        };
+        
+        finish(response, accepted, &config, self.config.clock.system_time())
+    }
+    
+    /// Send a request and return the raw response without reading the body
+    async fn send_request(&self, url: &str, config: &RequestConfig) -> Result<reqwest::Response> {
+        Ok(self.send_request_counted(url, config).await?.0)
+    }
+    
+    /// [`Client::send_request`] on the runtime from
+    /// [`ClientBuilder::runtime_handle`], if any
+    ///
+    /// For the streaming and connection paths that bypass `execute`. The
+    /// connection is driven by the runtime that opened it, so the returned
+    /// body keeps streaming from there wherever it is polled.
+    async fn send_request_on_runtime(&self, url: String, config: RequestConfig) -> Result<reqwest::Response> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(handle) = &self.config.runtime {
+            let client = self.clone();
+            return handle
+                .spawn(async move { client.send_request(&url, &config).await })
+                .await
+                .unwrap_or_else(|e| Err(Error::network("Request task failed", e)));
+        }
+        
+        self.send_request(&url, &config).await
+    }
+    
+    /// Send a request and return the raw response, without reading the
+    /// body, along with the number of redirects followed
+    ///
+    /// Redirects are followed here rather than by reqwest so that sensitive
+    /// headers can be stripped when the target is on another host, and
+    /// exceeding `max_redirects` fails with a clear "Too many redirects"
+    /// `Error::Network`. A redirect back to a URL already requested with the
+    /// same method fails straight away as a redirect loop.
+    async fn send_request_counted(
+        &self,
+        url: &str,
+        config: &RequestConfig,
+    ) -> Result<(reqwest::Response, usize)> {
+        let url = &merge_query(url, &config.query, &config.query_replaced);
+        let prepared = self.prepare_send(url, config).await?;
+        let config = &*prepared;
+        
+        // One timeout covers the whole redirect chain, not each hop
+        let clock = &*self.config.clock;
+        let deadline = config
+            .timeout
+            .map(|timeout| (timeout, Deadline::after_on(clock, timeout)));
+        let mut response = self.send_single(url, config).await?;
+        if !config.follow_redirects {
+            #[cfg(target_arch = "wasm32")]
+            reject_browser_redirect(url, &response)?;
+            return Ok((response, 0));
+        }
+        
+        let mut redirected: Option<RequestConfig> = None;
+        let mut hops = 0;
+        // A request repeating an earlier one would redirect the same way again
+        let mut visited = vec![(config.method, response.url().clone())];
+        loop {
+            let status = response.status().as_u16();
+            let Some(location) = response
+                .headers()
+                .get("location")
+                .and_then(|value| value.to_str().ok())
+            else {
+                break;
+            };
+            let previous = response.url().clone();
+            let target = previous
+                .join(location)
+                .map_err(|e| Error::network("Invalid redirect location", e))?;
+            
+            let next = redirected.get_or_insert_with(|| config.clone());
+            if !redirect_request(next, status) {
+                break;
+            }
+            if visited.contains(&(next.method, target.clone())) {
+                return Err(Error::Network {
+                    message: format!("Redirect loop detected at {target}"),
+                    source: None,
+                });
+            }
+            visited.push((next.method, target.clone()));
+            if hops >= config.max_redirects {
+                return Err(Error::Network {
+                    message: format!("Too many redirects (limit {})", config.max_redirects),
+                    source: None,
+                });
+            }
+            hops += 1;
+            
+            if target.host_str() != previous.host_str()
+                || target.port_or_known_default() != previous.port_or_known_default()
+            {
+                for name in &self.config.sensitive_headers {
+                    next.headers.remove(name);
+                }
+            }
+            // Timeouts are reported as the configured one, not what was left
+            let timed_out = |timeout| Error::Timeout {
+                duration_ms: crate::error::duration_ms(timeout),
+            };
+            if let Some((timeout, deadline)) = &deadline {
+                let remaining = deadline.remaining_on(clock);
+                if remaining.is_zero() {
+                    return Err(timed_out(*timeout));
+                }
+                next.timeout = Some(remaining);
+            }
+            response = self
+                .send_single(target.as_str(), next)
+                .await
+                .map_err(|err| match (err, &deadline) {
+                    (Error::Timeout { .. }, Some((timeout, _))) => timed_out(*timeout),
+                    (err, _) => err,
+                })?;
+        }
+        
+        Ok((response, usize::try_from(hops).unwrap_or(usize::MAX)))
+    }
+    
+    /// Check the transfer quota and apply client-wide additions to a request
+    ///
+    /// Materializes factory bodies, adds `Accept` (from the response
+    /// format), `Accept-Charset` and bearer tokens, runs the `before_send` hook and strips sensitive headers
+    /// from `TRACE` requests. Borrows `config` unchanged when none of that
+    /// applies.
+    async fn prepare_send<'a>(
+        &self,
+        url: &str,
+        config: &'a RequestConfig,
+    ) -> Result<Cow<'a, RequestConfig>> {
+        if let Some(limit) = self.config.transfer_quota {
+            let used = self.bytes_transferred();
+            if used >= limit {
+                return Err(Error::QuotaExceeded { limit, used });
+            }
+        }
+        
+        let refreshed_token = if self.own_authorization(config) {
+            None
+        } else {
+            let tokens = self.config.refreshed_tokens.read().unwrap_or_else(PoisonError::into_inner);
+            origin(url).and_then(|origin| tokens.get(&origin).cloned())
+        };
+        if config.body_factory.is_some()
+            || !config.headers.contains("accept")
+            || self.config.before_send.is_some()
+            || self.config.oauth.is_some()
+            || self.config.accept_charset.is_some()
+            || refreshed_token.is_some()
+            || (config.method == Method::Trace && !config.allow_trace_sensitive)
+        {
+            let mut config = config.clone();
+            if let Some(factory) = &config.body_factory {
+                config.body = Some(factory.make());
+            }
+            if !config.headers.contains("accept") {
+                config.headers.set("Accept", config.response_format.accept());
+            }
+            if let Some(charset) = &self.config.accept_charset {
+                if !config.headers.contains("accept-charset") {
+                    config.headers.set("Accept-Charset", charset.clone());
+                }
+            }
+            if let Some(source) = &self.config.oauth {
+                if !config.headers.contains("authorization") {
+                    let token = source.token().await?;
+                    config.headers.set("Authorization", format!("Bearer {token}"));
+                }
+            }
+            if let Some(token) = &refreshed_token {
+                config.headers.set("Authorization", format!("Bearer {token}"));
+            }
+            if let Some(hook) = &self.config.before_send {
+                hook(&config.method, url, &mut config.headers);
+            }
+            if config.method == Method::Trace && !config.allow_trace_sensitive {
+                for name in &self.config.sensitive_headers {
+                    config.headers.remove(name);
+                }
+            }
+            Ok(Cow::Owned(config))
+        } else {
+            Ok(Cow::Borrowed(config))
+        }
+    }
+    
+    /// Send exactly one HTTP request without following redirects
+    async fn send_single(&self, url: &str, config: &RequestConfig) -> Result<reqwest::Response> {
+        // Plain HTTP has no certificate to check the pin against
+        #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+        if !self.config.pinned_certificates.is_empty()
+            && !url.get(..8).is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"))
+        {
+            crate::tls::check_pin(None, &self.config.pinned_certificates)?;
+        }
+        let transport = self.inner.client(url);
+        let mut request = transport.request(config.method.to_reqwest(), url);
+        
+        // Keep the connection out of the pool; browsers manage this themselves
+        #[cfg(not(target_arch = "wasm32"))]
+        if config.connection_close {
+            request = request.header("connection", "close");
+        }
+        
+        // Set headers
+        for (name, values) in config.headers.iter() {
+            for value in values {
+                request = request.header(name.as_str(), value.as_str());
+            }
+        }
+        
+        // Set body, keeping an explicitly provided content type
+        if let Some(body) = &config.body {
+            #[allow(unused_mut)]
+            let mut typed = config.headers.contains("content-type");
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Body::Multipart(multipart) = body {
+                let parts = multipart.take().ok_or_else(|| Error::InvalidInput {
+                    parameter: "body".to_string(),
+                    reason: "Multipart body was already consumed by an earlier attempt".to_string(),
+                })?;
+                // Sets `Content-Type` with the form's boundary
+                request = request.multipart(multipart_form(parts).await?);
+                typed = true;
+            }
+            if !typed {
+                request = request.header("content-type", body.content_type());
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Body::Stream(stream) = body {
+                let stream = stream.take().ok_or_else(|| Error::InvalidInput {
+                    parameter: "body".to_string(),
+                    reason: "Streaming body was already consumed by an earlier attempt".to_string(),
+                })?;
+                request = request.body(stream);
+            }
+            if !body.is_stream() {
+                let bytes = body.encode(config.form_encoding)?;
+                self.record_transfer(bytes.len());
+                request = request.body(bytes);
+            }
+        }
+        
+        // Set timeout
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = config.timeout {
+            request = request.timeout(timeout);
+        }
+        
+        #[cfg(feature = "testing")]
+        if let Some(mock) = &self.config.mock {
+            return mock_raw(mock, &config.method, request);
+        }
+        
+        // Execute request, reporting the timeout actually applied
+        request.send().await.map_err(|e| {
+            #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+            if crate::tls::is_pin_mismatch(&e) {
+                return Error::Network {
+                    message: "certificate pin mismatch".to_string(),
+                    source: Some(Box::new(e)),
+                };
+            }
+            Error::from_reqwest(e, config.timeout)
+        })
+    }
+}
+
+/// Builder for creating HTTP clients
+// Each flag is an independent switch set by its own builder method
+#[allow(clippy::struct_excessive_bools)]
+pub struct ClientBuilder {
+    headers: Headers,
+    timeout: Duration,
+    retry_config: Option<RetryConfig>,
+    base_url: Option<String>,
+    base_urls: Vec<String>,
+    load_balance: LoadBalancePolicy,
+    rotate_base_on_retry: bool,
+    strict_auto: bool,
+    error_on_status: bool,
+    strict_headers: bool,
+    request_id_headers: Vec<String>,
+    transfer_quota: Option<u64>,
+    sensitive_headers: Vec<String>,
+    before_send: Option<BeforeSend>,
+    on_retry: Option<AttemptHook>,
+    on_complete: Option<AttemptHook>,
+    request_metrics: bool,
+    oauth: Option<OAuthConfig>,
+    on_unauthorized: Option<TokenRefresh>,
+    error_body_limit: Option<(usize, ErrorBodyEnd)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: Option<tokio::runtime::Handle>,
+    transport: TransportSettings,
+    preserve_number_precision: bool,
+    accept_charset: Option<String>,
+    max_connection_age: Option<Duration>,
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    pinned_certificates: Vec<String>,
+    honor_keep_alive_hints: bool,
+    #[cfg(feature = "testing")]
+    chaos: Option<crate::chaos::ChaosConfig>,
+    #[cfg(feature = "testing")]
+    mock: Option<crate::mock::MockClient>,
+    clock: Arc<dyn Clock>,
+}
+
+/// How much earlier than a server's `Keep-Alive: timeout` hint idle
+/// connections are dropped, so they're never reused at the last moment
+#[cfg(not(target_arch = "wasm32"))]
+const KEEP_ALIVE_MARGIN: Duration = Duration::from_secs(1);
+
+/// The reqwest client requests are sent with
+///
+/// Shared by a [`Client`], its clones and its OAuth token source, so a
+/// pool replaced by [`PoolRotation`] is replaced for all of them.
+pub(crate) struct Transport {
+    client: reqwest::Client,
+    #[cfg(not(target_arch = "wasm32"))]
+    rotation: Option<PoolRotation>,
+}
+
+impl Transport {
+    /// The reqwest client to send a request to `url` with
+    pub(crate) fn client(&self, url: &str) -> reqwest::Client {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(rotation) = &self.rotation {
+            return rotation.client(url);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = url;
+        self.client.clone()
+    }
+}
+
+/// reqwest's pool idle timeout when none is configured
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Replaces reqwest clients, and with them their connection pools, once a
+/// pool reaches a maximum age or a server hints at a shorter idle timeout
+///
+/// Every pooled connection is younger than its pool, so no connection is
+/// reused past `max_age`. Origins that hint a shorter idle timeout get a
+/// pool of their own, leaving everyone else's connections alone. Requests
+/// still running on a replaced client finish normally; its connections
+/// close once the last of them completes.
+#[cfg(not(target_arch = "wasm32"))]
+struct PoolRotation {
+    max_age: Option<Duration>,
+    /// Settings the client was first built with
+    transport: TransportSettings,
+    /// Pool for origins without a `Keep-Alive` hint of their own
+    shared: Mutex<Pool>,
+    /// Pools for origins that hinted a shorter idle timeout, by origin
+    hinted: Mutex<HashMap<String, Pool>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Pool {
+    client: reqwest::Client,
+    built: std::time::Instant,
+    idle_timeout: Option<Duration>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PoolRotation {
+    fn new(max_age: Option<Duration>, transport: TransportSettings, client: reqwest::Client) -> Self {
+        let idle_timeout = transport.pool_idle_timeout;
+        Self {
+            max_age,
+            transport,
+            shared: Mutex::new(Pool {
+                client,
+                built: std::time::Instant::now(),
+                idle_timeout,
+            }),
+            hinted: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// A fresh client with the original settings and `pool_idle_timeout`
+    fn rebuild(&self, pool_idle_timeout: Option<Duration>) -> Result<reqwest::Client> {
+        build_reqwest_client(&TransportSettings {
+            pool_idle_timeout,
+            ..self.transport.clone()
+        })
+    }
+    
+    /// Replace `pool` if it has reached `max_age`
+    fn renew(&self, pool: &mut Pool) {
+        if self.max_age.is_some_and(|max_age| pool.built.elapsed() >= max_age) {
+            // Settings were validated by the first build, so this can't
+            // really fail; keep the old pool if it somehow does
+            if let Ok(fresh) = self.rebuild(pool.idle_timeout) {
+                pool.client = fresh;
+                pool.built = std::time::Instant::now();
+            }
+        }
+    }
+    
+    /// The client to send a request to `url` with, renewed first if its
+    /// pool is too old
+    fn client(&self, url: &str) -> reqwest::Client {
+        if let Some(origin) = origin(url) {
+            let mut hinted = self.hinted.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(pool) = hinted.get_mut(&origin) {
+                self.renew(pool);
+                return pool.client.clone();
+            }
+        }
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        self.renew(&mut shared);
+        shared.client.clone()
+    }
+    
+    /// Give the origin of `url` a pool that drops idle connections before
+    /// the server closes them after `hint`, unless its current pool
+    /// already does so sooner
+    fn honor_keep_alive(&self, url: &str, hint: Duration) {
+        let idle = hint
+            .checked_sub(KEEP_ALIVE_MARGIN)
+            .filter(|idle| !idle.is_zero())
+            .unwrap_or(hint / 2);
+        let Some(origin) = origin(url) else {
+            return;
+        };
+        let mut hinted = self.hinted.lock().unwrap_or_else(PoisonError::into_inner);
+        let current = match hinted.get(&origin) {
+            Some(pool) => pool.idle_timeout,
+            None => self.shared.lock().unwrap_or_else(PoisonError::into_inner).idle_timeout,
+        };
+        if current.unwrap_or(DEFAULT_POOL_IDLE_TIMEOUT) <= idle {
+            return;
+        }
+        if let Ok(client) = self.rebuild(Some(idle)) {
+            let pool = Pool {
+                client,
+                built: std::time::Instant::now(),
+                idle_timeout: Some(idle),
+            };
+            hinted.insert(origin, pool);
+        }
+    }
+}
+
+/// Settings of the underlying reqwest client
+///
+/// Kept in one place so a client rebuilt by [`PoolRotation`] is configured
+/// exactly like the original; anything passed to the reqwest builder
+/// belongs here.
+#[derive(Debug, Clone, Default)]
+struct TransportSettings {
+    preserve_header_case: bool,
+    connect_timeout: Option<Duration>,
+    /// Time connection phases for [`crate::types::TimingBreakdown`]
+    timing_breakdown: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    cookie_jar: Option<Arc<reqwest::cookie::Jar>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_idle_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: ProxySettings,
+    /// Normalized fingerprints, filled in by `build`
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    pinned_certificates: Vec<String>,
+}
+
+/// Proxy settings applied when building the native client
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default)]
+struct ProxySettings {
+    http: Option<String>,
+    https: Option<String>,
+    no_proxy: Option<String>,
+}
+
+impl ClientBuilder {
+    /// Create a new client builder
+    pub fn new() -> Self {
+        Self {
+            headers: Headers::new(),
+            timeout: Duration::from_secs(30),
+            retry_config: None,
+            base_url: None,
+            base_urls: Vec::new(),
+            load_balance: LoadBalancePolicy::default(),
+            rotate_base_on_retry: false,
+            strict_auto: false,
+            error_on_status: true,
+            strict_headers: false,
+            request_id_headers: DEFAULT_REQUEST_ID_HEADERS
+                .iter()
+                .map(|name| (*name).to_string())
+                .collect(),
+            transfer_quota: None,
+            sensitive_headers: DEFAULT_SENSITIVE_HEADERS
+                .iter()
+                .map(|name| (*name).to_string())
+                .collect(),
+            before_send: None,
+            on_retry: None,
+            on_complete: None,
+            request_metrics: false,
+            oauth: None,
+            on_unauthorized: None,
+            error_body_limit: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: None,
+            transport: TransportSettings::default(),
+            preserve_number_precision: false,
+            accept_charset: None,
+            max_connection_age: None,
+            #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+            pinned_certificates: Vec::new(),
+            honor_keep_alive_hints: false,
+            #[cfg(feature = "testing")]
+            chaos: None,
+            #[cfg(feature = "testing")]
+            mock: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+    
+    /// Set default header
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+    
+    /// Send an API key in the header `header_name` with every request
+    ///
+    /// Replaces any default value for that header rather than adding a
+    /// second one; [`RequestBuilder::api_key`] overrides it per request.
+    #[must_use]
+    pub fn api_key(mut self, header_name: impl Into<String>, key: impl Into<String>) -> Self {
+        self.headers.set(header_name, key);
+        self
+    }
+    
+    /// Send an API key in the `X-Api-Key` header with every request
+    #[must_use]
+    pub fn x_api_key(self, key: impl Into<String>) -> Self {
+        self.api_key(API_KEY_HEADER, key)
+    }
+    
+    /// Send `Authorization: Bearer <token>` with every request
+    ///
+    /// Replaces any default `Authorization` header;
+    /// [`RequestBuilder::bearer_auth`] overrides it per request.
+    #[must_use]
+    pub fn bearer_auth(mut self, token: impl Into<String>) -> Self {
+        let scheme = AuthScheme::Bearer { token: token.into() };
+        self.headers.set("Authorization", scheme.to_header_value());
+        self
+    }
+    
+    /// Send HTTP Basic credentials (RFC 7617) with every request
+    ///
+    /// A missing password encodes as `username:`. Replaces any default
+    /// `Authorization` header; [`RequestBuilder::basic_auth`] overrides it
+    /// per request.
+    #[must_use]
+    pub fn basic_auth(mut self, username: impl Into<String>, password: Option<String>) -> Self {
+        let scheme = AuthScheme::Basic {
+            username: username.into(),
+            password: password.unwrap_or_default(),
+        };
+        self.headers.set("Authorization", scheme.to_header_value());
+        self
+    }
+    
+    /// Set default headers
+    pub fn default_headers(mut self, headers: Headers) -> Self {
+        self.headers = headers;
+        self
+    }
+    
+    /// Set request timeout, covering any redirects followed
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Enable retries with default configuration
+    pub fn with_retries(mut self) -> Self {
+        self.retry_config = Some(RetryConfig::default());
+        self
+    }
+    
+    /// Set retry configuration
+    pub fn retry_config(mut self, config: RetryConfig) -> Self {
+        self.retry_config = Some(config);
+        self
+    }
+    
+    /// Set base URL for all requests
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+    
+    /// Spread requests across several base URLs (e.g. API mirrors)
+    ///
+    /// Each request picks one base according to
+    /// [`ClientBuilder::load_balance`] (round-robin by default). When this
+    /// list is non-empty it takes precedence over [`ClientBuilder::base_url`],
+    /// which is then ignored.
+    #[must_use]
+    pub fn base_urls(mut self, urls: Vec<String>) -> Self {
+        self.base_urls = urls;
+        self
+    }
+    
+    /// Set how requests are distributed across [`ClientBuilder::base_urls`]
+    #[must_use]
+    pub fn load_balance(mut self, policy: LoadBalancePolicy) -> Self {
+        self.load_balance = policy;
+        self
+    }
+    
+    /// Move each retry to a different base URL from
+    /// [`ClientBuilder::base_urls`] instead of retrying the same one
+    /// (disabled by default)
+    #[must_use]
+    pub fn rotate_base_on_retry(mut self, enabled: bool) -> Self {
+        self.rotate_base_on_retry = enabled;
+        self
+    }
+    
+    /// Fail with a parse error when `ResponseFormat::Auto` sees a JSON
+    /// `Content-Type` but the body is not valid JSON, instead of silently
+    /// falling back to text or binary
+    #[must_use]
+    pub fn strict_auto(mut self, strict: bool) -> Self {
+        self.strict_auto = strict;
+        self
+    }
+    
+    /// Fail non-2xx responses with `Error::Http` (enabled by default)
+    ///
+    /// When disabled, every status is returned as `Ok(Response)` with its
+    /// body decoded as usual, so structured error payloads (a `422` with
+    /// JSON details, say) can be read; check with
+    /// [`Response::error_for_status`] where an error is wanted. Since no
+    /// status is an error any more, statuses are never retried either.
+    #[must_use]
+    pub fn error_on_status(mut self, enabled: bool) -> Self {
+        self.error_on_status = enabled;
+        self
+    }
+    
+    /// Fail with a parse error when a response carries several
+    /// `Content-Length` values that disagree (disabled by default)
+    ///
+    /// Conflicting lengths are a classic request-smuggling signal: a proxy
+    /// and the origin may frame the body differently. Repeated identical
+    /// values are allowed.
+    #[must_use]
+    pub fn strict_headers(mut self, strict: bool) -> Self {
+        self.strict_headers = strict;
+        self
+    }
+    
+    /// Set the response headers checked for a server-assigned request ID
+    ///
+    /// Replaces the default list (`X-Request-Id`, `X-Amzn-RequestId`, ...)
+    /// used by [`Response::server_request_id`]. Names are matched
+    /// case-insensitively, first match wins.
+    #[must_use]
+    pub fn server_request_id_headers(mut self, headers: Vec<String>) -> Self {
+        self.request_id_headers = headers;
+        self
+    }
+    
+    /// Cap the total request and response body bytes this client may transfer
+    ///
+    /// Usage is shared by all clones of the client. Once the quota is used
+    /// up, further requests fail with `Error::QuotaExceeded` before anything
+    /// is sent; the request that crosses the limit still completes.
+    #[must_use]
+    pub fn transfer_quota(mut self, bytes: u64) -> Self {
+        self.transfer_quota = Some(bytes);
+        self
+    }
+    
+    /// Set the headers removed when a redirect leads to a different host
+    ///
+    /// Defaults to `Authorization`, `Cookie`, `Proxy-Authorization` and
+    /// `WWW-Authenticate`, matching reqwest and browsers. Redirects to the
+    /// same host and port keep all headers. On WASM the browser follows
+    /// redirects and applies its own rules instead. The same headers are
+    /// stripped from [`Client::trace`] requests.
+    #[must_use]
+    pub fn sensitive_headers(mut self, headers: Vec<String>) -> Self {
+        self.sensitive_headers = headers;
+        self
+    }
+    
+    /// Run a hook on the final request headers before every attempt
+    ///
+    /// The hook sees the method and URL and may edit the headers after
+    /// default and per-request headers have been merged, e.g. to add a
+    /// timestamp. It runs again on each retry. For anything more involved,
+    /// wrap the client instead.
+    #[must_use]
+    pub fn before_send<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Method, &str, &mut Headers) + Send + Sync + 'static,
+    {
+        self.before_send = Some(Arc::new(hook));
+        self
+    }
+    
+    /// Call `hook` with each failed attempt that is about to be retried
+    ///
+    /// Runs before the backoff delay, with the request's
+    /// [`RequestContext`] so events can be correlated with the caller.
+    #[must_use]
+    pub fn on_retry<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&AttemptRecord, &RequestContext) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+    
+    /// Call `hook` once per request with the record of its final attempt
+    ///
+    /// Runs whether the request succeeded or failed for good, with the
+    /// request's [`RequestContext`]; `attempt` in the record is the total
+    /// number of attempts made. Not called for requests that are cancelled
+    /// or whose future is dropped before finishing.
+    #[must_use]
+    pub fn on_complete<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&AttemptRecord, &RequestContext) + Send + Sync + 'static,
+    {
+        self.on_complete = Some(Arc::new(hook));
+        self
+    }
+    
+    /// Attach a per-phase latency breakdown to every response
+    ///
+    /// See [`crate::types::TimingBreakdown`] for the phases available on each platform.
+    /// On native, host names are then looked up by the client's own timed
+    /// resolver, which uses the system resolver like reqwest's default.
+    #[must_use]
+    pub fn timing_breakdown(mut self, enabled: bool) -> Self {
+        self.transport.timing_breakdown = enabled;
+        self
+    }
+    
+    /// Attach attempt count and retry-inclusive timing to every response
+    ///
+    /// See [`RequestMetrics`]. Off by default so requests that don't need
+    /// it skip the bookkeeping.
+    #[must_use]
+    pub fn request_metrics(mut self, enabled: bool) -> Self {
+        self.request_metrics = enabled;
+        self
+    }
+    
+    /// Refresh the bearer token through `refresh` when a request gets a `401`
+    ///
+    /// Only a `401 Unauthorized` response triggers the callback. The token
+    /// it returns is stored for the origin (scheme, host and port) that
+    /// sent the `401`, and the request is re-sent once with
+    /// `Authorization: Bearer <token>`; if that also fails, its error is
+    /// returned, so a bad callback can't cause a loop. The callback runs at
+    /// most once per request, even when a retry hits another `401`. From
+    /// then on the stored token replaces the default `Authorization` header
+    /// of requests to that origin only, until another `401` refreshes it
+    /// again.
+    /// Requests that set their own `Authorization` header are never
+    /// refreshed or altered. Concurrent requests that hit a `401` at the
+    /// same time each invoke the callback. An error from the callback is
+    /// returned as the request's error.
+    #[must_use]
+    pub fn on_unauthorized<F>(mut self, refresh: F) -> Self
+    where
+        F: Fn() -> TokenFuture + Send + Sync + 'static,
+    {
+        self.on_unauthorized = Some(Arc::new(refresh));
+        self
+    }
+    
+    /// Run requests on a specific tokio runtime (native only)
+    ///
+    /// Each `send` spawns the request, including retries and their backoff
+    /// sleeps, onto `handle` and awaits the result, so connection handling
+    /// and timers live on that runtime rather than the caller's. Because of
+    /// this, synchronous code can drive a request with a plain blocking
+    /// executor such as `futures::executor::block_on`, as long as it is not
+    /// called from one of `handle`'s own worker threads, which would
+    /// deadlock a current-thread runtime. The streaming methods, `warm_up`,
+    /// `download_verified` and OAuth token fetches send on `handle` too;
+    /// their connections stay on it, so a streamed body keeps arriving
+    /// through `handle` while the caller polls it.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn runtime_handle(mut self, handle: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(handle);
+        self
+    }
+    
+    /// Cap how much of an error response body is kept in `Error::Http`
+    ///
+    /// Error bodies are streamed and at most `limit` bytes are kept, from
+    /// the start or the end of the body depending on `keep`, so a huge
+    /// error page never has to be buffered in full. With
+    /// [`ErrorBodyEnd::Head`] reading stops once `limit` bytes have arrived.
+    /// Without this setting the whole error body is read.
+    #[must_use]
+    pub fn error_body_limit(mut self, limit: usize, keep: ErrorBodyEnd) -> Self {
+        self.error_body_limit = Some((limit, keep));
+        self
+    }
+    
+    /// Authenticate every request with an `OAuth2` client-credentials token
+    ///
+    /// The token is requested from `config.token_url` on first use, cached
+    /// for its `expires_in` lifetime, and refreshed once it is within
+    /// `config.refresh_margin` of expiring. Concurrent requests share one
+    /// fetch. Each attempt carries `Authorization: Bearer <token>` unless
+    /// the request already sets an `Authorization` header. Token-endpoint
+    /// failures surface as `Error::Auth`.
+    #[must_use]
+    pub fn oauth2_client_credentials(mut self, config: OAuthConfig) -> Self {
+        self.oauth = Some(config);
+        self
+    }
+    
+    /// Send header names in conventional `Title-Case` (e.g. `Content-Type`)
+    /// instead of lowercase, for legacy servers that match header names
+    /// case-sensitively
+    ///
+    /// Only affects HTTP/1 connections on native targets: HTTP/2 requires
+    /// lowercase names and browsers control casing on WASM. Lookups through
+    /// [`Headers`] stay case-insensitive either way.
+    #[must_use]
+    pub fn preserve_header_case(mut self, preserve: bool) -> Self {
+        self.transport.preserve_header_case = preserve;
+        self
+    }
+    
+    /// Limit how long establishing a connection (DNS, TCP and TLS) may
+    /// take, independently of the overall request timeout
+    ///
+    /// Lets requests fail fast on unreachable hosts while still allowing a
+    /// generous [`ClientBuilder::timeout`] for slow responses. Exceeding it
+    /// produces `Error::Timeout`. Native only; ignored on WASM, where the
+    /// browser does not expose the connection phase.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.transport.connect_timeout = Some(timeout);
+        self
+    }
+    
+    /// Require that JSON numbers are kept exactly as received
+    ///
+    /// By default `serde_json` stores numbers as `u64`, `i64` or `f64`, so
+    /// integers beyond 64 bits (e.g. 20-digit IDs) and long decimals are
+    /// rounded. With the `arbitrary-precision` feature, numbers in
+    /// `ResponseBody::Json` keep their original digits and convert
+    /// losslessly when deserialized into wide types or strings.
+    ///
+    /// The representation is chosen when `serde_json` is compiled, so the
+    /// feature applies to every client and every other `serde_json` user in
+    /// the build, and this option only checks it is on: `build` fails with
+    /// `Error::InvalidInput` without it. Numbers then cost a heap-allocated
+    /// string each, making parsing slower, and `Number` values compare by
+    /// their text (`1.0 != 1.00`). On WASM, converting such values to
+    /// JavaScript yields objects instead of numbers.
+    #[must_use]
+    pub fn preserve_number_precision(mut self, preserve: bool) -> Self {
+        self.preserve_number_precision = preserve;
+        self
+    }
+    
+    /// Stop reusing pooled connections once they are `age` old
+    ///
+    /// Useful behind load balancers that rotate backends, where a
+    /// long-lived keep-alive connection would stay pinned to one backend.
+    /// reqwest has no per-connection age limit, so the whole connection
+    /// pool is replaced every `age`; requests in flight at that moment
+    /// finish on their old connections. No-op on WASM, where the browser
+    /// manages connections.
+    #[must_use]
+    pub fn max_connection_age(mut self, age: Duration) -> Self {
+        self.max_connection_age = Some(age);
+        self
+    }
+    
+    /// Drop idle pooled connections before servers close them, going by
+    /// their `Keep-Alive: timeout=N` response hints (disabled by default)
+    ///
+    /// A best-effort native optimization against sending on a connection
+    /// the server is about to close. reqwest has a single idle timeout per
+    /// pool, so a host whose hint, less a small margin, is shorter than its
+    /// current idle timeout (reqwest's default of 90 seconds at first) gets
+    /// a pool of its own; other hosts keep theirs. No-op on WASM, where the
+    /// browser manages connections.
+    #[must_use]
+    pub fn honor_keep_alive_hints(mut self, enabled: bool) -> Self {
+        self.honor_keep_alive_hints = enabled;
+        self
+    }
+    
+    /// Keep cookies set by responses and send them on later requests from
+    /// this client, like a browser session (disabled by default)
+    ///
+    /// Cookies are matched by domain, path and expiry, and live as long as
+    /// the client and its clones; inspect them with [`Client::cookies`].
+    /// No-op on WASM, where the browser manages cookies itself.
+    #[must_use]
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.transport.cookie_jar = enabled.then(Arc::default);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = enabled;
+        self
+    }
+    
+    /// Only accept servers whose leaf certificate has this SHA-256
+    /// fingerprint (native only, requires the `tls` feature)
+    ///
+    /// Takes hex with or without `:` separators, as printed by
+    /// `openssl x509 -noout -fingerprint -sha256`. Call again to allow
+    /// several certificates, e.g. the current and the next one during a
+    /// rotation. The pin is checked during the TLS handshake, so a server
+    /// presenting any other certificate, or a plain HTTP URL, fails with an
+    /// `Error::Network` "certificate pin mismatch" before anything is
+    /// sent. A pinned certificate replaces CA validation, so self-signed
+    /// certificates can be pinned too; it must still be unexpired. OAuth
+    /// token requests are pinned as well. An invalid fingerprint fails
+    /// `build` with `Error::InvalidInput`.
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    #[must_use]
+    pub fn pin_certificate(mut self, sha256_fingerprint: impl Into<String>) -> Self {
+        self.pinned_certificates.push(sha256_fingerprint.into());
+        self
+    }
+    
+    /// Send `Accept-Charset: <charset>` with every request, for legacy
+    /// servers that only return UTF-8 (or another charset) when asked
+    ///
+    /// A request that sets its own `Accept-Charset` header sends that one
+    /// instead. Responses are decoded by the charset
+    /// they declare in `Content-Type`, so together these request and read a
+    /// specific encoding. `build` fails with `Error::InvalidInput` if
+    /// `charset` is not a known encoding label.
+    #[must_use]
+    pub fn accept_charset(mut self, charset: impl Into<String>) -> Self {
+        self.accept_charset = Some(charset.into());
+        self
+    }
+    
+    /// Route plain HTTP requests through the given proxy (native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn http_proxy(mut self, url: impl Into<String>) -> Self {
+        self.transport.proxy.http = Some(url.into());
+        self
+    }
+    
+    /// Route HTTPS requests through the given proxy (native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn https_proxy(mut self, url: impl Into<String>) -> Self {
+        self.transport.proxy.https = Some(url.into());
+        self
+    }
+    
+    /// Bypass the configured proxies for a comma-separated list of hosts,
+    /// domains, and IP ranges, using the `NO_PROXY` syntax (native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn no_proxy(mut self, hosts: impl Into<String>) -> Self {
+        self.transport.proxy.no_proxy = Some(hosts.into());
+        self
+    }
+    
+    /// Apply configuration from environment variables (native only)
+    ///
+    /// The following variables are consulted; unset or empty variables leave
+    /// the corresponding setting untouched:
+    ///
+    /// - `HTTP_PROXY` / `http_proxy`: proxy for plain HTTP requests
+    /// - `HTTPS_PROXY` / `https_proxy`: proxy for HTTPS requests
+    /// - `NO_PROXY` / `no_proxy`: hosts that bypass the proxies
+    /// - `RUST_FETCH_TIMEOUT_MS`: request timeout in milliseconds (ignored
+    ///   if it is not a valid integer)
+    ///
+    /// When `REQUEST_METHOD` is set, i.e. inside a CGI script, `HTTP_PROXY`
+    /// is ignored: CGI passes the request's `Proxy` header in that variable
+    /// ("httpoxy"). Only the lowercase `http_proxy` is read then.
+    ///
+    /// Invalid proxy URLs are reported as errors by [`ClientBuilder::build`].
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn from_env(self) -> Self {
+        self.from_env_with(|name| std::env::var(name).ok())
+    }
+    
+    /// Apply configuration from variables looked up with `var`, as
+    /// [`ClientBuilder::from_env`] does from the process environment
+    /// (native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn from_env_with(mut self, var: impl Fn(&str) -> Option<String>) -> Self {
+        let non_empty = |name: &str| var(name).filter(|v| !v.is_empty());
+        // Falls back to the lowercase name
+        let lookup = |name: &str| non_empty(name).or_else(|| non_empty(&name.to_lowercase()));
+        let http_proxy = if var("REQUEST_METHOD").is_some() {
+            non_empty("http_proxy")
+        } else {
+            lookup("HTTP_PROXY")
+        };
+        if let Some(url) = http_proxy {
+            self = self.http_proxy(url);
+        }
+        if let Some(url) = lookup("HTTPS_PROXY") {
+            self = self.https_proxy(url);
+        }
+        if let Some(hosts) = lookup("NO_PROXY") {
+            self = self.no_proxy(hosts);
+        }
+        if let Some(ms) = lookup("RUST_FETCH_TIMEOUT_MS").and_then(|v| v.parse().ok()) {
+            self = self.timeout(Duration::from_millis(ms));
+        }
+        self
+    }
+    
+    /// Inject latency and failures into every request attempt
+    ///
+    /// Only available with the `testing` feature.
+    #[cfg(feature = "testing")]
+    #[must_use]
+    pub fn chaos(mut self, config: crate::chaos::ChaosConfig) -> Self {
+        self.chaos = Some(config);
+        self
+    }
+    
+    /// Read the current time from `clock` instead of the system clock
+    ///
+    /// Deadlines, attempt timing, OAuth token expiry and `Retry-After`
+    /// dates follow it, so a [`crate::time::TestClock`] makes them testable
+    /// without waiting. Sleeps between retries still take real time.
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+    
+    /// Answer every request from `mock` instead of the network
+    ///
+    /// Retries, hooks and status handling still apply; see
+    /// [`crate::mock`]. The streaming methods, `warm_up` (a `HEAD`
+    /// request) and `download_verified` are answered from the mock too,
+    /// except streaming on WASM, which fails instead. Only available with
+    /// the `testing` feature.
+    #[cfg(feature = "testing")]
+    #[must_use]
+    pub fn mock(mut self, mock: &crate::mock::MockClient) -> Self {
+        self.mock = Some(mock.clone());
+        self
+    }
+    
+    /// Build the client
+    pub fn build(self) -> Result<Client> {
+        if self.preserve_number_precision && !cfg!(feature = "arbitrary-precision") {
+            return Err(Error::InvalidInput {
+                parameter: "preserve_number_precision".to_string(),
+                reason: "Requires the `arbitrary-precision` feature".to_string(),
+            });
+        }
+        if let Some(charset) = &self.accept_charset {
+            if encoding_rs::Encoding::for_label(charset.trim().as_bytes()).is_none() {
+                return Err(Error::InvalidInput {
+                    parameter: "accept_charset".to_string(),
+                    reason: format!("Unknown character encoding: {charset}"),
+                });
+            }
+        }
+        let transport = self.transport_settings()?;
+        let client = build_reqwest_client(&transport)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let rotation = (self.max_connection_age.is_some() || self.honor_keep_alive_hints)
+            .then(|| PoolRotation::new(self.max_connection_age, transport.clone(), client.clone()));
+        let inner = Arc::new(Transport {
+            client,
+            #[cfg(not(target_arch = "wasm32"))]
+            rotation,
+        });
+        Ok(self.assemble(inner, transport))
+    }
+    
+    /// Settings to build the transport from, with certificate pins
+    /// normalized
+    fn transport_settings(&self) -> Result<TransportSettings> {
+        #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+        {
+            let pinned_certificates = self
+                .pinned_certificates
+                .iter()
+                .map(|pin| {
+                    crate::tls::normalize_fingerprint(pin).ok_or_else(|| Error::InvalidInput {
+                        parameter: "pin_certificate".to_string(),
+                        reason: format!("Not a SHA-256 fingerprint: {pin}"),
+                    })
+                })
+                .collect::<Result<_>>()?;
+            Ok(TransportSettings {
+                pinned_certificates,
+                ..self.transport.clone()
+            })
+        }
+        #[cfg(not(all(feature = "tls", not(target_arch = "wasm32"))))]
+        Ok(self.transport.clone())
+    }
+    
+    /// Create the client around `inner`, a transport built from `transport`
+    fn assemble(self, inner: Arc<Transport>, transport: TransportSettings) -> Client {
+        let oauth = self.oauth.map(|config| {
+            let mut builder = Client::builder().timeout(self.timeout);
+            builder.clock = self.clock.clone();
+            // Token requests share this client's pools and pins
+            let client = builder.assemble(inner.clone(), TransportSettings::default());
+            Arc::new(TokenSource::new(config, client, self.clock.clone()))
+        });
+        
+        Client {
+            inner,
+            config: Arc::new(ClientConfig {
+                default_headers: self.headers,
+                timeout: self.timeout,
+                retry_config: self.retry_config,
+                base_url: self.base_url,
+                base_urls: self.base_urls,
+                load_balance: self.load_balance,
+                rotate_base_on_retry: self.rotate_base_on_retry,
+                next_base: AtomicUsize::new(0),
+                strict_auto: self.strict_auto,
+                error_on_status: self.error_on_status,
+                strict_headers: self.strict_headers,
+                honor_keep_alive_hints: self.honor_keep_alive_hints,
+                request_id_headers: self.request_id_headers,
+                transfer_quota: self.transfer_quota,
+                bytes_transferred: AtomicU64::new(0),
+                sensitive_headers: self.sensitive_headers,
+                before_send: self.before_send,
+                on_retry: self.on_retry,
+                on_complete: self.on_complete,
+                timing_breakdown: transport.timing_breakdown,
+                request_metrics: self.request_metrics,
+                accept_charset: self.accept_charset,
+                oauth,
+                on_unauthorized: self.on_unauthorized,
+                refreshed_tokens: RwLock::new(HashMap::new()),
+                error_body_limit: self.error_body_limit,
+                #[cfg(not(target_arch = "wasm32"))]
+                runtime: self.runtime,
+                #[cfg(not(target_arch = "wasm32"))]
+                cookie_jar: transport.cookie_jar,
+                #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+                pinned_certificates: transport.pinned_certificates,
+                in_flight: Mutex::new(HashMap::new()),
+                next_request_id: AtomicU64::new(0),
+                #[cfg(feature = "testing")]
+                chaos: self.chaos.map(|config| Arc::new(crate::chaos::Chaos::new(config))),
+                #[cfg(feature = "testing")]
+                mock: self.mock,
+                clock: self.clock,
+            }),
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Request builder for configuring individual requests
+#[derive(Clone)]
+pub struct RequestBuilder {
+    client: Client,
+    config: RequestConfig,
+    url: String,
+    /// The URL as passed to `Client::request`, before base URL resolution
+    path: String,
+    retry_invalid: bool,
+}
+
+impl RequestBuilder {
+    /// Resolve this request's URL against `base` instead of the client's
+    /// base URL
+    ///
+    /// For the odd request to another host without building a second
+    /// client. Passing an absolute URL to [`Client::request`] works too;
+    /// this makes the intent explicit and keeps the path relative.
+    ///
+    /// Retries stay on this base even with
+    /// [`ClientBuilder::rotate_base_on_retry`].
+    #[must_use]
+    pub fn base_url(mut self, base: impl Into<String>) -> Self {
+        let base = base.into();
+        self.url = join_base(&base, &self.path);
+        self.config.base_url = Some(base);
+        self
+    }
+    
+    /// Set request header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.headers.insert(name, value);
+        self
+    }
+    
+    /// Send an API key in the header `header_name`
+    ///
+    /// Replaces the client's default value for that header, if any, so the
+    /// request carries exactly one key.
+    #[must_use]
+    pub fn api_key(mut self, header_name: impl Into<String>, key: impl Into<String>) -> Self {
+        self.config.headers.set(header_name, key);
+        self
+    }
+    
+    /// Send an API key in the `X-Api-Key` header
+    #[must_use]
+    pub fn x_api_key(self, key: impl Into<String>) -> Self {
+        self.api_key(API_KEY_HEADER, key)
+    }
+    
+    /// Send `Authorization: Bearer <token>`, replacing the client's default
+    #[must_use]
+    pub fn bearer_auth(mut self, token: impl Into<String>) -> Self {
+        let scheme = AuthScheme::Bearer { token: token.into() };
+        self.config.headers.set("Authorization", scheme.to_header_value());
+        self
+    }
+    
+    /// Send HTTP Basic credentials (RFC 7617), replacing the client's
+    /// default `Authorization` header
+    ///
+    /// A missing password encodes as `username:`.
+    #[must_use]
+    pub fn basic_auth(mut self, username: impl Into<String>, password: Option<String>) -> Self {
+        let scheme = AuthScheme::Basic {
+            username: username.into(),
+            password: password.unwrap_or_default(),
+        };
+        self.config.headers.set("Authorization", scheme.to_header_value());
+        self
+    }
+    
+    /// Append a query parameter
+    ///
+    /// Parameters already in the URL are kept and come first; builder
+    /// parameters follow in the order they were added. Adding a key that
+    /// the URL (or an earlier call) already has keeps both values; use
+    /// [`RequestBuilder::query_replace`] to override instead.
+    #[must_use]
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.query.push((key.into(), value.into()));
+        self
+    }
+    
+    /// Set a query parameter, replacing every existing value for `key`
+    ///
+    /// Removes `key` from the URL's own parameters and from earlier
+    /// builder parameters, then appends `key=value` after the remaining
+    /// URL parameters. Later [`RequestBuilder::query`] calls for the same
+    /// key append again.
+    #[must_use]
+    pub fn query_replace(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.config.query.retain(|(name, _)| *name != key);
+        if !self.config.query_replaced.contains(&key) {
+            self.config.query_replaced.push(key.clone());
+        }
+        self.config.query.push((key, value.into()));
+        self
+    }
+    
+    /// Append query parameters from any serializable struct or map
+    ///
+    /// Same as [`RequestBuilder::query_serde_with`] with
+    /// [`SerializeNulls::Omit`].
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::query_serde_with`].
+    pub fn query_serde<T: serde::Serialize>(self, data: &T) -> Result<Self> {
+        self.query_serde_with(data, SerializeNulls::Omit)
+    }
+    
+    /// Append query parameters from any serializable struct or map,
+    /// choosing how `None` fields are written
+    ///
+    /// Fields may be scalars or sequences of scalars; sequences repeat the
+    /// key for every element. Parameters are appended like
+    /// [`RequestBuilder::query`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidInput` if `data` contains nested objects or
+    /// doesn't serialize to a map.
+    pub fn query_serde_with<T: serde::Serialize>(
+        mut self,
+        data: &T,
+        nulls: SerializeNulls,
+    ) -> Result<Self> {
+        for (name, value) in serde_fields(data, "query", nulls)? {
+            match value {
+                serde_json::Value::Array(items) => {
+                    for item in &items {
+                        if let Some(item) = crate::types::form_scalar(item) {
+                            self.config.query.push((name.clone(), item));
+                        }
+                    }
+                }
+                value => {
+                    if let Some(value) = crate::types::form_scalar(&value) {
+                        self.config.query.push((name, value));
+                    }
+                }
+            }
+        }
+        Ok(self)
+    }
+    
+    /// Set multiple headers
+    pub fn headers(mut self, headers: Headers) -> Self {
+        for (name, values) in headers.iter() {
+            for value in values {
+                self.config.headers.insert(name.clone(), value.clone());
+            }
+        }
+        self
+    }
+    
+    /// Set request body as JSON
+    pub fn json<T: serde::Serialize>(mut self, json: &T) -> Result<Self> {
+        let value = serde_json::to_value(json)?;
+        self.config.body = Some(Body::Json(value));
+        Ok(self)
+    }
+    
+    /// Set request body as a JSON Merge Patch (RFC 7396) document, sent
+    /// with `Content-Type: application/merge-patch+json`
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if `patch` can't be serialized to JSON.
+    pub fn merge_patch<T: serde::Serialize>(self, patch: &T) -> Result<Self> {
+        let mut builder = self.json(patch)?;
+        builder.config.headers.set("content-type", "application/merge-patch+json");
+        Ok(builder)
+    }
+    
+    /// Set request body as a JSON Patch (RFC 6902) document, sent with
+    /// `Content-Type: application/json-patch+json`
+    #[must_use]
+    pub fn json_patch(mut self, ops: &[PatchOp]) -> Self {
+        self.config.body = Some(Body::Json(serde_json::json!(ops)));
+        self.config.headers.set("content-type", "application/json-patch+json");
+        self
+    }
+    
+    /// Stream a JSON array body, serializing one item at a time (native only)
+    ///
+    /// Sends `[`, then each item as JSON separated by `,`, then `]`, so the
+    /// full document is never held in memory. Sets `Content-Type:
+    /// application/json`. The items are consumed by the first attempt: a
+    /// retry or a 307/308 redirect of a streamed request fails with
+    /// `Error::InvalidInput`, so don't combine this with retries. A
+    /// serialization error aborts the upload mid-stream and surfaces as
+    /// `Error::Network`.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn json_stream<T, I>(mut self, items: I) -> Self
+    where
+        T: Serialize,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + Sync + 'static,
+    {
+        let client = self.client.clone();
+        let chunks = JsonArrayChunks {
+            items: items.into_iter(),
+            started: false,
+            done: false,
+        }
+        .inspect(move |chunk| {
+            if let Ok(bytes) = chunk {
+                client.record_transfer(bytes.len());
+            }
+        });
+        let body = reqwest::Body::wrap_stream(futures_util::stream::iter(chunks));
+        self.config.headers.set("Content-Type", "application/json");
+        self.config.body = Some(Body::Stream(StreamBody::new(body)));
+        self
+    }
+    
+    /// Stream a `multipart/form-data` body built from `parts` (native only)
+    ///
+    /// Text parts are sent from memory; file and reader parts are read in
+    /// chunks as the upload proceeds, so memory use stays flat however
+    /// large the files are. Files are opened when the request is sent, and
+    /// a file that can't be opened fails with `Error::InvalidInput`. The
+    /// `Content-Type` header, including the boundary, is set automatically.
+    /// When every part has a known length (text and file parts do) a
+    /// `Content-Length` is sent, otherwise the body is chunked.
+    ///
+    /// Like [`RequestBuilder::json_stream`], the parts are consumed by the
+    /// first attempt: a retry or a 307/308 redirect fails with
+    /// `Error::InvalidInput`, so don't combine this with retries.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn multipart_stream(mut self, parts: Vec<StreamPart>) -> Self {
+        self.config.body = Some(Body::Multipart(MultipartBody::new(parts)));
+        self
+    }
+    
+    /// Set request body as text
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.config.body = Some(Body::Text(text.into()));
+        self
+    }
+    
+    /// Set request body as bytes
+    pub fn bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.config.body = Some(Body::Binary(bytes));
+        self
+    }
+    
+    /// Produce a fresh body for every attempt instead of a fixed one
+    ///
+    /// The factory is called once per attempt, including retries, so bodies
+    /// that can't simply be replayed (e.g. re-read from a file) can still be
+    /// retried. Takes precedence over any body set with the other methods.
+    #[must_use]
+    pub fn body_factory<F>(mut self, factory: F) -> Self
+    where
+        F: Fn() -> Body + Send + Sync + 'static,
+    {
+        self.config.body_factory = Some(BodyFactory::new(factory));
+        self
+    }
+    
+    /// Set request body as form data
+    pub fn form(mut self, data: std::collections::HashMap<String, String>) -> Self {
+        self.config.body = Some(Body::Form(data));
+        self
+    }
+    
+    /// Set request body as form data from any serializable struct or map
+    ///
+    /// Fields may be scalars or sequences of scalars; `None` fields are
+    /// omitted. Sequences are encoded according to
+    /// [`RequestBuilder::form_encoding`].
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::form_serde_with`].
+    pub fn form_serde<T: serde::Serialize>(self, data: &T) -> Result<Self> {
+        self.form_serde_with(data, SerializeNulls::Omit)
+    }
+    
+    /// Set request body as form data from any serializable struct or map,
+    /// choosing how `None` fields are written
+    ///
+    /// See [`RequestBuilder::form_serde`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidInput` if `data` contains nested objects or
+    /// doesn't serialize to a map.
+    pub fn form_serde_with<T: serde::Serialize>(
+        mut self,
+        data: &T,
+        nulls: SerializeNulls,
+    ) -> Result<Self> {
+        let fields = serde_fields(data, "form", nulls)?;
+        self.config.body = Some(Body::FormFields(fields));
+        Ok(self)
+    }
+    
+    /// Choose how sequence-valued form fields are encoded (default `Repeated`)
+    #[must_use]
+    pub fn form_encoding(mut self, encoding: FormEncoding) -> Self {
+        self.config.form_encoding = encoding;
+        self
+    }
+    
+    /// Send `Expect: 100-continue` so the server can reject the request
+    /// before the body is uploaded
+    ///
+    /// Useful for large uploads where authentication or size checks may
+    /// fail. The interim `100 Continue` response is consumed internally and
+    /// never returned. Native only (browsers refuse to send this header),
+    /// and servers are free to ignore it.
+    #[must_use]
+    pub fn expect_continue(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.config.headers.set("Expect", "100-continue");
+        } else {
+            self.config.headers.remove("expect");
+        }
+        self
+    }
+    
+    /// Treat additional statuses as success instead of `Error::Http`
+    ///
+    /// Responses in `statuses` are returned as `Ok(Response)`, e.g. `304..=304`
+    /// for conditional requests. 2xx statuses always count as success; call
+    /// repeatedly to accept several ranges.
+    #[must_use]
+    pub fn success_statuses(mut self, statuses: impl std::ops::RangeBounds<u16>) -> Self {
+        use std::ops::Bound;
+        
+        let start = match statuses.start_bound() {
+            Bound::Included(start) => Some(*start),
+            Bound::Excluded(start) => start.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match statuses.end_bound() {
+            Bound::Included(end) => Some(*end),
+            Bound::Excluded(end) => end.checked_sub(1),
+            Bound::Unbounded => Some(u16::MAX),
+        };
+        if let (Some(start), Some(end)) = (start, end) {
+            self.config.success_statuses.push(start..=end);
+        }
+        self
+    }
+    
+    /// Set request timeout, covering any redirects followed
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+    
+    /// Give the whole request, retries included, `budget` from now to finish
+    ///
+    /// Each attempt's timeout is shortened so it ends by the deadline, and
+    /// no retry is started if its backoff would run past it. If the
+    /// deadline has already passed when the request is sent, it fails with
+    /// `Error::Timeout` without being sent. Combined with
+    /// [`RequestBuilder::deadline_at`], the earlier deadline wins.
+    #[must_use]
+    pub fn deadline(mut self, budget: Duration) -> Self {
+        let deadline = Deadline::after_on(&*self.client.config.clock, budget);
+        self.config.deadline = Some(Deadline::earliest(self.config.deadline, deadline));
+        self
+    }
+    
+    /// Like [`RequestBuilder::deadline`], but at an absolute `instant`
+    ///
+    /// Avoids the drift of converting a parent operation's deadline into a
+    /// duration before the request is sent.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn deadline_at(mut self, instant: std::time::Instant) -> Self {
+        let deadline = Deadline::at(instant);
+        self.config.deadline = Some(Deadline::earliest(self.config.deadline, deadline));
+        self
+    }
+    
+    /// Like [`RequestBuilder::deadline`], but at an absolute time in
+    /// milliseconds on the `performance.now()` clock
+    #[cfg(target_arch = "wasm32")]
+    pub fn deadline_at_ms(mut self, ms: f64) -> Self {
+        let deadline = Deadline::at_ms(ms);
+        self.config.deadline = Some(Deadline::earliest(self.config.deadline, deadline));
+        self
+    }
+    
+    /// Negotiate the response format from an ordered preference list
+    ///
+    /// Sends a weighted `Accept` header built from the list (the first
+    /// format gets `q=1`, each following one `0.1` less), then parses the
+    /// response with the first listed format matching the returned
+    /// `Content-Type`, falling back to `ResponseFormat::Auto` when none
+    /// matches. Takes precedence over [`RequestBuilder::response_format`].
+    #[must_use]
+    pub fn prefer(mut self, formats: &[ResponseFormat]) -> Self {
+        let accept = formats
+            .iter()
+            .take(10)
+            .enumerate()
+            .map(|(i, format)| match i {
+                0 => format.accept().to_string(),
+                _ => format!("{};q=0.{}", format.accept(), 10 - i),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !accept.is_empty() {
+            self.config.headers.set("accept", accept);
+        }
+        self.config.preferred_formats = formats.to_vec();
+        self
+    }
+    
+    /// Follow redirects for this request (on by default)
+    ///
+    /// When disabled, a `3xx` response is returned as-is, so it surfaces as
+    /// `Error::Http` unless accepted via success statuses. On WASM the
+    /// browser follows redirects before the client sees them; a request
+    /// that ended up at a different URL fails with `Error::Network`
+    /// instead.
+    #[must_use]
+    pub fn follow_redirects(mut self, follow: bool) -> Self {
+        self.config.follow_redirects = follow;
+        self
+    }
+    
+    /// Fail with `Error::Network` after following more than `max`
+    /// redirects (default 10)
+    ///
+    /// Ignored on WASM, where the browser applies its own limit.
+    #[must_use]
+    pub fn max_redirects(mut self, max: u32) -> Self {
+        self.config.max_redirects = max;
+        self
+    }
+    
+    /// Limit how long reading the response body may take once the response
+    /// headers have arrived, independently of the overall request timeout
+    ///
+    /// Useful for long-polling, where the server may hold the request open
+    /// for a long time but must deliver the body promptly once it starts.
+    /// Exceeding the limit produces `Error::Timeout`. Native only; ignored
+    /// on WASM, where the browser does not expose the body-reading phase.
+    #[must_use]
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+    
+    /// Skip reading the response body, for fire-and-forget requests such
+    /// as telemetry beacons
+    ///
+    /// The status is still checked and the returned [`Response`] keeps its
+    /// status and headers, but its body is [`ResponseBody::Empty`]. Error
+    /// responses are read as usual so `Error::Http` can carry the body.
+    /// On native, bodies up to 64 KiB are drained so the connection stays
+    /// pooled; larger ones are dropped and the connection closed.
+    #[must_use]
+    pub fn discard_body(mut self) -> Self {
+        self.config.discard_body = true;
+        self
+    }
+    
+    /// Set response format preference
+    ///
+    /// Also sent as the `Accept` header (`application/json`, `text/*` or
+    /// `*/*`) unless the request sets its own.
+    pub fn response_format(mut self, format: ResponseFormat) -> Self {
+        self.config.response_format = format;
+        self
+    }
+    
+    /// Retry when [`RequestBuilder::send_validated`] fails to deserialize or
+    /// validate the response, as if it were a transient error
+    ///
+    /// Only takes effect when the client has retries enabled.
+    #[must_use]
+    pub fn retry_on_invalid(mut self, retry: bool) -> Self {
+        self.retry_invalid = retry;
+        self
+    }
+    
+    /// Build a deterministic canonical representation of the request for
+    /// HMAC signing
+    ///
+    /// The result is six lines joined by `\n` (no trailing newline):
+    ///
+    /// 1. The uppercase method
+    /// 2. The URL path, each segment percent-encoded per RFC 3986 (only
+    ///    `A-Z a-z 0-9 - . _ ~` are left unescaped; `/` separates segments)
+    /// 3. The query string: pairs encoded like path segments, sorted by name
+    ///    then value, joined as `name=value` with `&`
+    /// 4. The signed headers as `name:value`, with lowercase names sorted
+    ///    alphabetically, values trimmed, and multiple values joined by `,`
+    ///    (a missing header has an empty value), separated by `;`
+    /// 5. The signed header names, lowercase, sorted, and separated by `;`
+    /// 6. The lowercase hex SHA-256 of the request body (of the empty string
+    ///    when there is no body)
+    ///
+    /// The canonical string does not depend on header insertion order or on
+    /// the casing of header names.
+    pub fn canonical_string(&self, signed_headers: &[&str]) -> String {
+        let url = merge_query(&self.url, &self.config.query, &self.config.query_replaced);
+        let (path, query) = match reqwest::Url::parse(&url) {
+            Ok(url) => {
+                let mut pairs: Vec<(String, String)> = url
+                    .query_pairs()
+                    .map(|(k, v)| (rfc3986_encode(&k), rfc3986_encode(&v)))
+                    .collect();
+                pairs.sort();
+                let query = pairs
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join("&");
+                (url.path().to_string(), query)
+            }
+            Err(_) => (self.url.clone(), String::new()),
+        };
+        let path = path
+            .split('/')
+            .map(|segment| rfc3986_encode(&percent_encoding::percent_decode_str(segment).decode_utf8_lossy()))
+            .collect::<Vec<_>>()
+            .join("/");
+        
+        let mut names: Vec<String> = signed_headers.iter().map(|name| name.to_lowercase()).collect();
+        names.sort();
+        names.dedup();
+        let headers = names
+            .iter()
+            .map(|name| {
+                let value = match self.config.headers.get(name) {
+                    Some(values) => values.iter().map(|v| v.trim()).collect::<Vec<_>>().join(","),
+                    None if name == "content-type" => self
+                        .config
+                        .body
+                        .as_ref()
+                        .map(|body| body.content_type().to_string())
+                        .unwrap_or_default(),
+                    None => String::new(),
+                };
+                format!("{name}:{value}")
+            })
+            .collect::<Vec<_>>()
+            .join(";");
+        
+        let body = self
+            .config
+            .body
+            .as_ref()
+            .and_then(|body| body.encode(self.config.form_encoding).ok())
+            .unwrap_or_default();
+        let digest = hex_encode(&sha2::Sha256::digest(&body));
+        
+        [
+            self.config.method.as_str().to_string(),
+            path,
+            query,
+            headers,
+            names.join(";"),
+            digest,
+        ]
+        .join("\n")
+    }
+    
+    /// Render the request as an equivalent `curl` command, for bug reports
+    ///
+    /// Includes the method, the URL with its query parameters, the headers
+    /// (sorted by name) and the body, each single-quoted for a POSIX shell.
+    /// Values of the client's sensitive headers (see
+    /// [`ClientBuilder::sensitive_headers`]) are replaced by `[REDACTED]`;
+    /// use [`RequestBuilder::to_curl_unredacted`] to keep them. Additions
+    /// made at send time, such as OAuth tokens or the `before_send` hook,
+    /// are not included, and neither are streaming bodies or bodies that
+    /// aren't valid UTF-8.
+    pub fn to_curl(&self) -> String {
+        self.curl(true)
+    }
+    
+    /// Like [`RequestBuilder::to_curl`], but with sensitive header values
+    /// left in place
+    pub fn to_curl_unredacted(&self) -> String {
+        self.curl(false)
+    }
+    
+    fn curl(&self, redact: bool) -> String {
+        let url = merge_query(&self.url, &self.config.query, &self.config.query_replaced);
+        let mut args = vec![
+            "curl".to_string(),
+            "-X".to_string(),
+            self.config.method.as_str().to_string(),
+            shell_quote(&url),
+        ];
+        
+        let mut headers: Vec<(&String, &Vec<String>)> = self.config.headers.iter().collect();
+        headers.sort();
+        let mut lines: Vec<String> = Vec::new();
+        for (name, values) in headers {
+            let sensitive = redact
+                && self
+                    .client
+                    .config
+                    .sensitive_headers
+                    .iter()
+                    .any(|sensitive| sensitive.eq_ignore_ascii_case(name));
+            for value in values {
+                let value = if sensitive { "[REDACTED]" } else { value.as_str() };
+                lines.push(format!("{name}: {value}"));
+            }
+        }
+        let body = self.config.body.as_ref().filter(|body| !body.is_stream());
+        if let Some(body) = body {
+            if !self.config.headers.contains("content-type") {
+                lines.push(format!("content-type: {}", body.content_type()));
+            }
+        }
+        for line in lines {
+            args.push("-H".to_string());
+            args.push(shell_quote(&line));
+        }
+        
+        let data = body
+            .and_then(|body| body.encode(self.config.form_encoding).ok())
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+        if let Some(data) = data {
+            args.push("--data-raw".to_string());
+            args.push(shell_quote(&data));
+        }
+        args.join(" ")
+    }
+    
+    /// Require a JSON response body
+    ///
+    /// Forces [`ResponseFormat::Json`] regardless of `Content-Type`. Unlike
+    /// the `Auto` format, a body that is not valid JSON never degrades to
+    /// text: it fails with `Error::Parse` whose message includes the
+    /// response `Content-Type` and the first 200 bytes of the body, so it is
+    /// easy to see what the server actually sent (an HTML error page, say).
+    #[must_use]
+    pub fn expect_json(mut self) -> Self {
+        self.config.response_format = ResponseFormat::Json;
+        self.config.preferred_formats.clear();
+        self.config.expect_json = true;
+        self
+    }
+    
+    /// Close the connection after this request instead of returning it to
+    /// the pool, by sending `Connection: close`
+    ///
+    /// The next request opens a fresh connection, which helps when a load
+    /// balancer should pick a new backend. No-op on WASM.
+    #[must_use]
+    pub fn connection_close(mut self, close: bool) -> Self {
+        self.config.connection_close = close;
+        self
+    }
+    
+    /// Keep sensitive headers on a [`Client::trace`] request
+    ///
+    /// Only for servers you control: the echoed body will then contain
+    /// credentials such as the `Authorization` header.
+    #[must_use]
+    pub fn allow_trace_sensitive(mut self, allow: bool) -> Self {
+        self.config.allow_trace_sensitive = allow;
+        self
+    }
+    
+    /// Attach a request-scoped value, such as a user ID or trace span, for
+    /// the client's `on_retry` and `on_complete` hooks
+    ///
+    /// Values are keyed by type, so setting a second value of the same type
+    /// replaces the first. Read them back with [`RequestContext::get`].
+    #[must_use]
+    pub fn context<T: std::any::Any + Send + Sync>(mut self, value: T) -> Self {
+        self.config.context.insert(value);
+        self
+    }
+    
+    /// Tag the request so its group can be cancelled with
+    /// [`Client::cancel_tag`] or counted with [`Client::in_flight`]
+    ///
+    /// The tag is also reported in [`AttemptRecord::tag`], so `on_retry` and
+    /// `on_complete` hooks can measure each group.
+    #[must_use]
+    pub fn tag(mut self, key: impl Into<String>) -> Self {
+        self.config.tag = Some(key.into());
+        self
+    }
+    
+    /// Set the browser fetch priority hint for this request
+    ///
+    /// Lets a browser app schedule critical API calls ahead of prefetches.
+    /// The hint is recorded on the request config and ignored on native
+    /// targets. Browser support varies: Chromium-based browsers honor it,
+    /// Safari and Firefox only in recent releases, and older browsers
+    /// silently ignore the option. Note that reqwest's WASM backend builds
+    /// the fetch `RequestInit` itself and offers no hook for extra options,
+    /// so the hint only reaches the browser with the `native-fetch`
+    /// feature, whose transport builds its own `RequestInit` via
+    /// `FetchPriority::apply` (WASM only).
+    #[must_use]
+    pub fn priority(mut self, priority: FetchPriority) -> Self {
+        self.config.priority = Some(priority);
+        self
+    }
+    
+    /// Fail with `Error::Parse` unless a successful response has the given
+    /// media type; see [`Response::ensure_content_type`]
+    #[must_use]
+    pub fn require_content_type(mut self, expected: &str) -> Self {
+        self.config.required_content_type = Some(expected.to_string());
+        self
+    }
+    
+    /// Report download progress as `(bytes_so_far, total)` (native only)
+    ///
+    /// Called after every body chunk, including the last, so the final call
+    /// has the full body size; `total` is the `Content-Length`, or `None`
+    /// when the server didn't send one. Works with both
+    /// [`RequestBuilder::send`] and [`RequestBuilder::send_stream`]; each
+    /// retry starts again from zero. The callback runs inline on the task
+    /// reading the body, so keep it cheap (update a counter or send to a
+    /// channel) rather than blocking.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(u64, Option<u64>) + Send + 'static,
+    {
+        self.config.on_progress = Some(ProgressCallback::new(callback));
+        self
+    }
+    
+    /// Return a substitute response instead of an error when the request
+    /// ultimately fails
+    ///
+    /// The fallback runs once, after retries are exhausted, and only sees
+    /// the final error (e.g. `Error::RetriesExhausted`), not the errors of
+    /// earlier attempts. Returning `Some(response)` makes `send` succeed with
+    /// that response, which allows serving cached or default data while a
+    /// service is down; returning `None` propagates the error.
+    #[must_use]
+    pub fn fallback<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Error) -> Option<Response> + Send + Sync + 'static,
+    {
+        self.config.fallback = Some(Fallback::new(f));
+        self
+    }
+    
+    /// Execute the request
+    pub async fn send(self) -> Result<Response> {
+        self.client.execute(self.url, self.config).await
+    }
+    
+    /// Execute the request on the current runtime, ignoring
+    /// [`ClientBuilder::runtime_handle`]
+    ///
+    /// For requests made while another request is already running on that
+    /// runtime, such as OAuth token fetches.
+    pub(crate) async fn send_here(self) -> Result<Response> {
+        self.client.execute_here(self.url, self.config).await
+    }
+    
+    /// Execute the request, deserialize the JSON body into `T`, and run a
+    /// validation hook on the result
+    ///
+    /// See [`RequestBuilder::retry_on_invalid`] to retry such failures.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`RequestBuilder::send`], with `Error::Parse` if the body
+    /// doesn't match `T`, and with `Error::Parse` carrying the validator's
+    /// message if validation fails.
+    pub async fn send_validated<T, F>(self, validate: F) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+        F: Fn(&T) -> std::result::Result<(), String>,
+    {
+        let retry_invalid = self.retry_invalid;
+        self.client
+            .execute_with(
+                self.url,
+                self.config,
+                |response| {
+                    let value = response.into_json::<T>()?;
+                    validate(&value).map_err(|message| Error::Parse {
+                        message,
+                        source: None,
+                    })?;
+                    Ok(value)
+                },
+                |err| retry_invalid && matches!(err, Error::Parse { .. }),
+                None,
+            )
+            .await
+    }
+    
+    /// Execute the request and hand the raw response body to a custom
+    /// deserializer, for wire formats other than JSON
+    ///
+    /// The body is read as bytes regardless of `Content-Type` and passed to
+    /// `deserialize`, e.g. a closure wrapping `rmp_serde::from_slice`. It
+    /// sends `Accept: */*`; set a specific one with
+    /// [`RequestBuilder::header`] if the server negotiates on it.
+    ///
+    /// # Errors
+    ///
+    /// Non-2xx responses fail with `Error::Http` before the deserializer
+    /// runs. Errors returned by the deserializer are passed through, and
+    /// `Error::Parse` ones are retried when
+    /// [`RequestBuilder::retry_on_invalid`] is set.
+    pub async fn send_as<T, D>(mut self, deserialize: D) -> Result<T>
+    where
+        D: Fn(&[u8]) -> Result<T>,
+    {
+        let retry_invalid = self.retry_invalid;
+        self.config.response_format = ResponseFormat::Binary;
+        self.config.preferred_formats.clear();
+        self.client
+            .execute_with(
+                self.url,
+                self.config,
+                |response| deserialize(response.bytes().unwrap_or_default()),
+                |err| retry_invalid && matches!(err, Error::Parse { .. }),
+                None,
+            )
+            .await
+    }
+    
+    /// Execute the request and also return a record of every attempt made
+    ///
+    /// Useful for debugging flaky endpoints: the records show each retry's
+    /// status or error kind and how long it took.
+    ///
+    /// # Errors
+    ///
+    /// If every attempt fails, the final error is returned together with the
+    /// records as [`FailedAttempts`].
+    pub async fn send_with_attempts(
+        self,
+    ) -> std::result::Result<(Response, Vec<AttemptRecord>), FailedAttempts> {
+        let mut records = Vec::new();
+        let result = self
+            .client
+            .execute_with(self.url, self.config, Ok, |_| false, Some(&mut records))
+            .await;
+        match result {
+            Ok(response) => Ok((response, records)),
+            Err(error) => Err(FailedAttempts { error, attempts: records }),
+        }
+    }
+    
+    /// Execute the request and stream the body as text lines as they arrive
+    ///
+    /// Lines end at `\n` or `\r\n` (the terminator is stripped), partial
+    /// lines are buffered across chunks, and a final unterminated line is
+    /// yielded when the body ends. Each line is decoded using the `charset`
+    /// of the response `Content-Type`. Retries are not applied, and the
+    /// request timeout still bounds the whole stream, so raise it for
+    /// long-lived log tails.
+    ///
+    /// # Errors
+    ///
+    /// A non-2xx status fails with `Error::Http` before any line is yielded;
+    /// other failures are those of [`RequestBuilder::send`].
+    pub async fn send_lines(self) -> Result<impl futures_util::Stream<Item = Result<String>>> {
+        use futures_util::StreamExt;
+        
+        let response = self.client.send_request_on_runtime(self.url, self.config).await?;
+        if !response.status().is_success() {
+            return Err(stream_status_error(response, self.client.config.clock.system_time()).await);
+        }
+        
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let client = self.client;
+        let chunks = Box::pin(response.bytes_stream());
+        
+        Ok(futures_util::stream::unfold(
+            (chunks, Vec::new(), false),
+            move |(mut chunks, mut buffer, mut done)| {
+                let client = client.clone();
+                let content_type = content_type.clone();
+                async move {
+                    loop {
+                        if let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                            let mut line: Vec<u8> = buffer.drain(..=end).collect();
+                            line.pop();
+                            if line.last() == Some(&b'\r') {
+                                line.pop();
+                            }
+                            let line = decode_text(&line, &content_type);
+                            return Some((Ok(line), (chunks, buffer, done)));
+                        }
+                        if done {
+                            if buffer.is_empty() {
+                                return None;
+                            }
+                            let mut line = std::mem::take(&mut buffer);
+                            if line.last() == Some(&b'\r') {
+                                line.pop();
+                            }
+                            let line = decode_text(&line, &content_type);
+                            return Some((Ok(line), (chunks, buffer, done)));
+                        }
+                        match chunks.next().await {
+                            Some(Ok(bytes)) => {
+                                client.record_transfer(bytes.len());
+                                buffer.extend_from_slice(&bytes);
+                            }
+                            Some(Err(e)) => {
+                                buffer.clear();
+                                return Some((Err(Error::from(e)), (chunks, buffer, true)));
+                            }
+                            None => done = true,
+                        }
+                    }
+                }
+            },
+        ))
+    }
+    
+    /// Execute the request and stream the body as raw chunks as they arrive
+    ///
+    /// For large downloads that shouldn't be buffered in memory. Status and
+    /// headers are available on the returned [`ResponseStream`] before any of
+    /// the body is read. The body is yielded as received: the
+    /// `ResponseFormat`, charset and JSON handling of
+    /// [`RequestBuilder::send`] are skipped. Retries are not applied, and the
+    /// request timeout still bounds the whole stream. Native only.
+    ///
+    /// # Errors
+    ///
+    /// A non-2xx status fails with `Error::Http` before any chunk is yielded;
+    /// other failures are those of [`RequestBuilder::send`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn send_stream(self) -> Result<ResponseStream> {
+        use futures_util::StreamExt;
+        
+        let progress = self.config.on_progress.clone();
+        let response = self.client.send_request_on_runtime(self.url, self.config).await?;
+        if !response.status().is_success() {
+            return Err(stream_status_error(response, self.client.config.clock.system_time()).await);
+        }
+        
+        let headers = response_headers(&response);
+        let status = response.status();
+        let url = response.url().to_string();
+        let total = response.content_length();
+        let client = self.client;
+        let mut downloaded = 0u64;
+        let chunks = response.bytes_stream().map(move |chunk| {
+            let chunk = chunk.map_err(|e| Error::parse("Failed to read response body", e))?;
+            client.record_transfer(chunk.len());
+            downloaded += chunk.len() as u64;
+            if let Some(progress) = &progress {
+                progress.report(downloaded, total);
+            }
+            Ok(chunk)
+        });
+        
+        Ok(ResponseStream {
+            status: status.as_u16(),
+            status_text: crate::error::status_text(status.as_u16()),
+            headers,
+            url,
+            chunks: Box::pin(chunks),
+        })
+    }
+    
+    /// Stream a request body up while streaming the response body down
+    /// (native only)
+    ///
+    /// For streaming RPC protocols where the server answers chunks of an
+    /// upload as they arrive. The request is sent with `request_stream` as
+    /// its body, and the response body is returned as soon as the status
+    /// and headers are in, while the upload continues in the background.
+    ///
+    /// Both directions can only stay open together on HTTP/2, which reqwest
+    /// negotiates for HTTPS servers that support it. A plain-HTTP server is
+    /// spoken to in HTTP/1.1, where this only works if the server answers
+    /// before reading the whole request, as the echo in the tests does;
+    /// proxies in between may buffer either direction. The request timeout
+    /// bounds the whole exchange.
+    ///
+    /// # Errors
+    ///
+    /// Retries and 307/308 redirects fail with `Error::InvalidInput`, since
+    /// the request body can't be replayed. A non-2xx status fails with
+    /// `Error::Http` before any chunk is yielded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn send_duplex<S>(
+        mut self,
+        request_stream: S,
+    ) -> Result<impl futures_util::Stream<Item = Result<Vec<u8>>>>
+    where
+        S: futures_util::Stream<Item = Result<Vec<u8>>> + Send + Sync + 'static,
+    {
+        use futures_util::{StreamExt, TryStreamExt};
+        
+        let client = self.client.clone();
+        let upload = request_stream.inspect_ok(move |chunk| client.record_transfer(chunk.len()));
+        self.config.body = Some(Body::Stream(StreamBody::new(reqwest::Body::wrap_stream(upload))));
+        let response = self.send_stream().await?;
+        Ok(response.map(|chunk| chunk.map(Vec::from)))
+    }
+}
+
+/// Answer a request built for reqwest from a mock, for the paths that
+/// read the raw response rather than going through `execute_once`
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+fn mock_raw(mock: &crate::mock::MockClient, method: &Method, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let request = request.build().map_err(Error::from)?;
+    let mut headers = Headers::new();
+    for (name, value) in request.headers() {
+        if let Ok(value) = value.to_str() {
+            headers.insert(name.to_string(), value);
+        }
+    }
+    let response = mock.respond(crate::mock::MockRequest {
+        method: *method,
+        url: request.url().to_string(),
+        headers,
+        body: request.body().and_then(reqwest::Body::as_bytes).map(<[u8]>::to_vec),
+    })?;
+    crate::mock::into_reqwest(response)
+}
+
+/// Mocked raw responses can't be built on WASM, where reqwest wraps the
+/// browser's own response type
+#[cfg(all(feature = "testing", target_arch = "wasm32"))]
+fn mock_raw(_: &crate::mock::MockClient, method: &Method, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let url = request.build().map(|request| request.url().to_string()).unwrap_or_default();
+    Err(Error::Network {
+        message: format!("Streaming requests can't be mocked on WASM: {} {}", method.as_str(), url),
+        source: None,
+    })
+}
+
+/// Error for a streaming request that got a non-2xx status
+async fn stream_status_error(response: reqwest::Response, now: std::time::SystemTime) -> Error {
+    let status = response.status();
+    let retry_after = retry_after_header(&response, now);
+    Error::Http {
+        status: status.as_u16(),
+        status_text: crate::error::status_text(status.as_u16()),
+        body: response.text().await.ok(),
+        retry_after,
+    }
+}
+
+/// Maximum number of requests [`Client::get_typed_map`] runs at once
+pub const BATCH_CONCURRENCY: usize = 8;
+
+/// Largest body [`RequestBuilder::discard_body`] still drains to keep the
+/// connection reusable
+#[cfg(not(target_arch = "wasm32"))]
+const DISCARD_DRAIN_LIMIT: u64 = 64 * 1024;
+
+/// Header used by [`ClientBuilder::x_api_key`] and [`RequestBuilder::x_api_key`]
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// The scheme, host and port of `url`, e.g. `https://api.example.com:8443`
+fn origin(url: &str) -> Option<String> {
+    let origin = reqwest::Url::parse(url).ok()?.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
+
+/// Headers stripped from cross-host redirects unless configured otherwise
+const DEFAULT_SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "www-authenticate",
+];
+
+/// Single-quote `value` for a POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Fail if the browser followed a redirect the request asked not to follow
+///
+/// The fetch API always follows redirects and only reports the final URL,
+/// so refuse the result rather than silently return a page from elsewhere.
+#[cfg(target_arch = "wasm32")]
+fn reject_browser_redirect(url: &str, response: &reqwest::Response) -> Result<()> {
+    let Ok(mut requested) = reqwest::Url::parse(url) else {
+        return Ok(());
+    };
+    requested.set_fragment(None);
+    if *response.url() == requested {
+        return Ok(());
+    }
+    Err(Error::Network {
+        message: format!(
+            "Redirected to {} although redirects are disabled",
+            response.url()
+        ),
+        source: None,
+    })
+}
+
+/// Rewrite a request for following a redirect with the given status
+///
+/// `301`, `302` and `303` turn the request into a bodyless `GET` (a `HEAD`
+/// stays a `HEAD`); `307` and `308` keep the method and body. Returns
+/// `false` if `status` is not a followable redirect.
+fn redirect_request(config: &mut RequestConfig, status: u16) -> bool {
+    match status {
+        301..=303 => {
+            if config.method != Method::Head {
+                config.method = Method::Get;
+            }
+            config.body = None;
+            config.body_factory = None;
+            config.headers.remove("content-type");
+            config.headers.remove("content-length");
+            true
+        }
+        307 | 308 => true,
+        _ => false,
+    }
+}
+
+/// Build a reqwest client with platform-specific configuration
+fn build_reqwest_client(config: &TransportSettings) -> Result<reqwest::Client> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // Redirects are followed by `Client::send_request`
+        let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        
+        if config.preserve_header_case {
+            builder = builder.http1_title_case_headers();
+        }
+        if config.timing_breakdown {
+            builder = builder.dns_resolver(Arc::new(connect_timing::TimedResolver));
+        }
+        #[cfg(feature = "tls")]
+        {
+            builder = builder.tls_info(true);
+            if !config.pinned_certificates.is_empty() {
+                let tls = crate::tls::pinned_config(config.pinned_certificates.clone());
+                builder = builder.use_preconfigured_tls(tls).https_only(true);
+            }
+        }
+        if let Some(jar) = &config.cookie_jar {
+            builder = builder.cookie_provider(jar.clone());
+        }
+        if let Some(idle) = config.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        
+        let no_proxy = config
+            .proxy
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string);
+        if let Some(url) = &config.proxy.http {
+            let proxy = reqwest::Proxy::http(url).map_err(|e| Error::InvalidInput {
+                parameter: "http_proxy".to_string(),
+                reason: e.to_string(),
+            })?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+        }
+        if let Some(url) = &config.proxy.https {
+            let proxy = reqwest::Proxy::https(url).map_err(|e| Error::InvalidInput {
+                parameter: "https_proxy".to_string(),
+                reason: e.to_string(),
+            })?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy));
+        }
+        
+        builder
+            .build()
+            .map_err(|e| Error::network("Failed to create HTTP client", e))
+    }
+    
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = config;
+        reqwest::Client::builder()
+            .build()
+            .map_err(|e| Error::network("Failed to create HTTP client", e))
+    }
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters
+fn rfc3986_encode(value: &str) -> String {
+    const UNRESERVED: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+        .remove(b'-')
+        .remove(b'.')
+        .remove(b'_')
+        .remove(b'~');
+    percent_encoding::utf8_percent_encode(value, UNRESERVED).to_string()
+}
+
+/// Build a reqwest form from streamed parts, opening any files
+#[cfg(not(target_arch = "wasm32"))]
+async fn multipart_form(parts: Vec<StreamPart>) -> Result<reqwest::multipart::Form> {
+    use reqwest::multipart::Part;
+    use tokio_util::io::ReaderStream;
+    
+    let mut form = reqwest::multipart::Form::new();
+    for part in parts {
+        let mut body = match part.source {
+            PartSource::Text(text) => Part::text(text),
+            PartSource::File(path) => {
+                let opened = async {
+                    let file = tokio::fs::File::open(&path).await?;
+                    let length = file.metadata().await?.len();
+                    Ok::<_, std::io::Error>((file, length))
+                };
+                let (file, length) = opened.await.map_err(|e| Error::InvalidInput {
+                    parameter: "multipart".to_string(),
+                    reason: format!("Failed to open {}: {}", path.display(), e),
+                })?;
+                let stream = reqwest::Body::wrap_stream(ReaderStream::new(file));
+                Part::stream_with_length(stream, length)
             }
-            ResponseFormat::Auto => {
-                if content_type.contains("application/json") {
-                    let bytes = response.bytes().await
-                        .map_err(|e| Error::parse("Failed to read response bytes", e))?;
-                    match serde_json::from_slice::<serde_json::Value>(&bytes) {
-                        Ok(json) => ResponseBody::Json(json),
-                        Err(_) => {
-                            // Fallback to text if JSON parsing fails
-                            match String::from_utf8(bytes.to_vec()) {
-                                Ok(text) => ResponseBody::Text(text),
-                                Err(_) => ResponseBody::Binary(bytes.to_vec()),
-                            }
-                        }
-                    }
-                } else if content_type.contains("text/") || content_type.contains("xml") {
-                    let text = response.text().await
-                        .map_err(|e| Error::parse("Failed to read text response", e))?;
-                    ResponseBody::Text(text)
-                } else {
-                    let bytes = response.bytes().await
-                        .map_err(|e| Error::parse("Failed to read binary response", e))?;
-                    ResponseBody::Binary(bytes.to_vec())
-                }
+            PartSource::Reader(reader) => {
+                Part::stream(reqwest::Body::wrap_stream(ReaderStream::new(reader)))
             }
         };
-        
-        let response = Response {
-            status,
-            status_text,
-            headers,
-            body,
-            url,
-        };
-        
-        // Check for HTTP errors
-        if !response.is_success() {
-            return Err(Error::Http {
-                status: response.status,
-                status_text: response.status_text.clone(),
-                body: response.text().map(|s| s.to_string()),
-            });
+        if let Some(file_name) = part.file_name {
+            body = body.file_name(file_name);
         }
-        
-        Ok(response)
+        if let Some(content_type) = &part.content_type {
+            body = body.mime_str(content_type).map_err(|e| Error::InvalidInput {
+                parameter: "content_type".to_string(),
+                reason: e.to_string(),
+            })?;
+        }
+        form = form.part(part.name, body);
     }
+    Ok(form)
 }
 
-/// Builder for creating HTTP clients
-pub struct ClientBuilder {
-    headers: Headers,
-    timeout: Duration,
-    retry_config: Option<RetryConfig>,
-    base_url: Option<String>,
+/// Encode bytes as lowercase hex
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
 }
 
-impl ClientBuilder {
-    /// Create a new client builder
-    pub fn new() -> Self {
-        Self {
-            headers: Headers::new(),
-            timeout: Duration::from_secs(30),
-            retry_config: None,
-            base_url: None,
+/// Unregisters a tagged request when it finishes or is dropped
+struct TagGuard<'a> {
+    client: &'a Client,
+    tag: String,
+    id: u64,
+}
+
+impl Drop for TagGuard<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .client
+            .config
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(handles) = in_flight.get_mut(&self.tag) {
+            handles.retain(|(id, _)| *id != self.id);
+            if handles.is_empty() {
+                in_flight.remove(&self.tag);
+            }
         }
     }
-    
-    /// Set default header
-    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(name, value);
-        self
+}
+
+/// Combine the URL's own query parameters with builder parameters
+///
+/// URL parameters whose (decoded) key is in `replaced` are dropped; the
+/// rest keep their original encoding and order, followed by `params`.
+fn merge_query(url: &str, params: &[(String, String)], replaced: &[String]) -> String {
+    if params.is_empty() && replaced.is_empty() {
+        return url.to_string();
     }
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
     
-    /// Set default headers
-    pub fn default_headers(mut self, headers: Headers) -> Self {
-        self.headers = headers;
-        self
-    }
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let key = serde_urlencoded::from_str::<Vec<(String, String)>>(pair)
+                .ok()
+                .and_then(|decoded| decoded.into_iter().next())
+                .map(|(key, _)| key);
+            !key.is_some_and(|key| replaced.contains(&key))
+        })
+        .map(str::to_string)
+        .collect();
+    pairs.extend(
+        params
+            .iter()
+            .filter_map(|param| serde_urlencoded::to_string([param]).ok()),
+    );
     
-    /// Set request timeout
-    pub fn timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = timeout;
-        self
+    let mut merged = path.to_string();
+    if !pairs.is_empty() {
+        merged.push('?');
+        merged.push_str(&pairs.join("&"));
     }
-    
-    /// Enable retries with default configuration
-    pub fn with_retries(mut self) -> Self {
-        self.retry_config = Some(RetryConfig::default());
-        self
+    if let Some(fragment) = fragment {
+        merged.push('#');
+        merged.push_str(fragment);
     }
-    
-    /// Set retry configuration
-    pub fn retry_config(mut self, config: RetryConfig) -> Self {
-        self.retry_config = Some(config);
-        self
+    merged
+}
+
+/// Resolve a request URL against a base URL
+///
+/// Absolute URLs are returned unchanged. Relative paths are resolved below
+/// the base's path whether or not they start with `/`, so a base of
+/// `https://api.example.com/v1` and a path of `/users` or `users` both
+/// give `https://api.example.com/v1/users`. An empty path yields the base
+/// itself.
+pub(crate) fn join_base(base: &str, url: &str) -> String {
+    if url.is_empty() {
+        return base.to_string();
     }
-    
-    /// Set base URL for all requests
-    pub fn base_url(mut self, url: impl Into<String>) -> Self {
-        self.base_url = Some(url.into());
-        self
+    if is_absolute(url) && reqwest::Url::parse(url).is_ok() {
+        return url.to_string();
     }
-    
-    /// Build the client
-    pub fn build(self) -> Result<Client> {
-        let inner = build_reqwest_client()?;
-        
-        Ok(Client {
-            inner,
-            config: Arc::new(ClientConfig {
-                default_headers: self.headers,
-                timeout: self.timeout,
-                retry_config: self.retry_config,
-                base_url: self.base_url,
-            }),
-        })
+    let Ok(parsed) = reqwest::Url::parse(base) else {
+        return format!("{}{}", base.trim_end_matches('/'), url);
+    };
+    if url.starts_with("//") {
+        return parsed.join(url).map_or_else(|_| url.to_string(), String::from);
+    }
+    // `./` keeps a colon in the first segment from reading as a scheme
+    match directory(parsed).join(&format!("./{}", url.trim_start_matches('/'))) {
+        Ok(joined) => joined.into(),
+        Err(_) => format!("{}{}", base.trim_end_matches('/'), url),
     }
 }
 
-impl Default for ClientBuilder {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Whether `url` is absolute: it starts with `scheme://`, or with an
+/// `http:` or `https:` scheme
+///
+/// `Url::parse` alone would also accept paths such as `users:42` or
+/// `urn:x`, which are resolved against the base instead.
+fn is_absolute(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return false;
+    };
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid && (rest.starts_with("//") || scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
 }
 
-/// Request builder for configuring individual requests
-pub struct RequestBuilder {
-    client: Client,
-    config: RequestConfig,
-    url: String,
+/// The path [`join_base`] joined onto `base` to give `url`, or `None` if
+/// `url` doesn't lie below `base`
+fn strip_base<'a>(base: &str, url: &'a str) -> Option<&'a str> {
+    if url == base {
+        return Some("");
+    }
+    let base = directory(reqwest::Url::parse(base).ok()?);
+    url.strip_prefix(base.as_str())
 }
 
-impl RequestBuilder {
-    /// Set request header
-    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.config.headers.insert(name, value);
-        self
+/// `url` with its path ending in `/` and no query or fragment, so joining
+/// a relative path resolves below it
+fn directory(mut url: reqwest::Url) -> reqwest::Url {
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
     }
+    url.set_query(None);
+    url.set_fragment(None);
+    url
+}
+
+/// Set query parameters on `url`, replacing existing values for those keys
+fn with_query(url: &str, pairs: &[(&str, String)]) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        let query = serde_urlencoded::to_string(pairs).unwrap_or_default();
+        let separator = if url.contains('?') { '&' } else { '?' };
+        return format!("{url}{separator}{query}");
+    };
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| pairs.iter().all(|(name, _)| key != name))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    parsed
+        .query_pairs_mut()
+        .clear()
+        .extend_pairs(kept)
+        .extend_pairs(pairs);
+    parsed.to_string()
+}
+
+/// Pick a pseudo-random index for load balancing
+///
+/// `RandomState` is seeded per instance, which is plenty for spreading
+/// load and avoids pulling in an RNG dependency.
+fn random_index() -> usize {
+    use std::hash::{BuildHasher, Hasher};
+    let hash = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    usize::try_from(hash >> 32).unwrap_or(0)
+}
+
+/// Serializes items into the chunks of a JSON array
+#[cfg(not(target_arch = "wasm32"))]
+struct JsonArrayChunks<I> {
+    items: I,
+    started: bool,
+    done: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<I> Iterator for JsonArrayChunks<I>
+where
+    I: Iterator,
+    I::Item: Serialize,
+{
+    type Item = std::result::Result<Vec<u8>, serde_json::Error>;
     
-    /// Set multiple headers
-    pub fn headers(mut self, headers: Headers) -> Self {
-        for (name, values) in headers.iter() {
-            for value in values {
-                self.config.headers.insert(name.clone(), value.clone());
-            }
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
-        self
+        let Some(item) = self.items.next() else {
+            self.done = true;
+            return Some(Ok(if self.started { b"]".to_vec() } else { b"[]".to_vec() }));
+        };
+        let mut chunk = vec![if self.started { b',' } else { b'[' }];
+        self.started = true;
+        if let Err(e) = serde_json::to_writer(&mut chunk, &item) {
+            self.done = true;
+            return Some(Err(e));
+        }
+        Some(Ok(chunk))
     }
-    
-    /// Set request body as JSON
-    pub fn json<T: serde::Serialize>(mut self, json: &T) -> Result<Self> {
-        let value = serde_json::to_value(json)?;
-        self.config.body = Some(Body::Json(value));
-        Ok(self)
+}
+
+/// Maximum number of body bytes quoted in `expect_json` parse errors
+const ERROR_SNIPPET_BYTES: usize = 200;
+
+/// Render the start of a body for an error message, marking truncation
+fn body_snippet(bytes: &[u8]) -> String {
+    if bytes.len() <= ERROR_SNIPPET_BYTES {
+        return String::from_utf8_lossy(bytes).into_owned();
     }
+    let snippet = String::from_utf8_lossy(&bytes[..ERROR_SNIPPET_BYTES]);
+    format!("{}... ({} bytes total)", snippet.trim_end_matches('\u{FFFD}'), bytes.len())
+}
+
+/// Decode a text body using the `charset` of its content type (UTF-8 by default)
+fn decode_text(bytes: &[u8], content_type: &str) -> String {
+    let encoding = content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, label)| {
+            encoding_rs::Encoding::for_label(label.trim().trim_matches('"').as_bytes())
+        })
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Read the browser's `PerformanceResourceTiming` entry for `url`
+#[cfg(target_arch = "wasm32")]
+fn resource_timing(url: &str) -> Option<TimingBreakdown> {
+    let performance = js_sys::Reflect::get(&js_sys::global(), &"performance".into()).ok()?;
+    let entries = js_sys::Reflect::get(&performance, &"getEntriesByName".into())
+        .ok()?
+        .dyn_into::<js_sys::Function>()
+        .ok()?
+        .call1(&performance, &url.into())
+        .ok()?
+        .dyn_into::<js_sys::Array>()
+        .ok()?;
+    let entry = entries.get(entries.length().checked_sub(1)?);
     
-    /// Set request body as text
-    pub fn text(mut self, text: impl Into<String>) -> Self {
-        self.config.body = Some(Body::Text(text.into()));
-        self
-    }
+    let field = |name: &str| {
+        js_sys::Reflect::get(&entry, &name.into())
+            .ok()
+            .and_then(|value| value.as_f64())
+            .unwrap_or(0.0)
+    };
+    let span = |start: f64, end: f64| Duration::from_secs_f64((end - start).max(0.0) / 1000.0);
+    // Zero start times mean the phase was skipped or hidden cross-origin
+    let phase = |start: f64, end: f64| (start > 0.0).then(|| span(start, end));
     
-    /// Set request body as bytes
-    pub fn bytes(mut self, bytes: Vec<u8>) -> Self {
-        self.config.body = Some(Body::Binary(bytes));
-        self
+    let tls_start = field("secureConnectionStart");
+    let connect_end = if tls_start > 0.0 { tls_start } else { field("connectEnd") };
+    Some(TimingBreakdown {
+        dns: phase(field("domainLookupStart"), field("domainLookupEnd")),
+        connect: phase(field("connectStart"), connect_end),
+        tls: phase(tls_start, field("connectEnd")),
+        wait: span(field("requestStart"), field("responseStart")),
+        download: span(field("responseStart"), field("responseEnd")),
+        total: span(field("startTime"), field("responseEnd")),
+    })
+}
+
+/// Measures elapsed time on a client's clock
+///
+/// `std::time::Instant` is unavailable in the browser, so wasm builds
+/// measure in `performance.now()` milliseconds instead.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    started: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    started_ms: f64,
+}
+
+impl Stopwatch {
+    /// Start measuring from the current time on `clock`
+    pub(crate) fn start(clock: &dyn Clock) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            started: clock.now(),
+            #[cfg(target_arch = "wasm32")]
+            started_ms: clock.now_ms(),
+        }
     }
     
-    /// Set request body as form data
-    pub fn form(mut self, data: std::collections::HashMap<String, String>) -> Self {
-        self.config.body = Some(Body::Form(data));
-        self
+    /// Time elapsed on `clock` since the stopwatch was started
+    pub(crate) fn elapsed(&self, clock: &dyn Clock) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            clock.now().saturating_duration_since(self.started)
+        }
+        
+        #[cfg(target_arch = "wasm32")]
+        {
+            Duration::from_secs_f64((clock.now_ms() - self.started_ms).max(0.0) / 1000.0)
+        }
     }
-    
-    /// Set request timeout
-    pub fn timeout(mut self, timeout: Duration) -> Self {
-        self.config.timeout = Some(timeout);
-        self
+}
+
+/// Copy of `config` whose timeout doesn't run past its deadline on `clock`
+fn within_deadline(config: &RequestConfig, clock: &dyn Clock) -> RequestConfig {
+    let mut config = config.clone();
+    if let Some(deadline) = config.deadline {
+        let remaining = deadline.remaining_on(clock);
+        config.timeout = Some(config.timeout.map_or(remaining, |timeout| timeout.min(remaining)));
+    }
+    config
+}
+
+/// Whether a `Retry-After` header on a `status` response asks for a wait
+///
+/// RFC 9110 defines it for `503`, `429` (RFC 6585) and redirects; on any
+/// other status it is ignored in favor of the normal backoff.
+fn honors_retry_after(status: u16) -> bool {
+    matches!(status, 429 | 503 | 300..=399)
+}
+
+/// Wait requested by a raw response's `Retry-After` header, if any,
+/// measured from `now`
+fn retry_after_header(response: &reqwest::Response, now: std::time::SystemTime) -> Option<Duration> {
+    let value = response.headers().get("retry-after")?.to_str().ok()?;
+    crate::time::parse_retry_after(value, now)
+}
+
+/// Serialize `data` to a flat map for query or form encoding
+///
+/// Values must be scalars or sequences of scalars; `null` values are
+/// dropped or replaced with `""` according to `nulls`.
+fn serde_fields<T: serde::Serialize>(
+    data: &T,
+    parameter: &str,
+    nulls: SerializeNulls,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let invalid = |reason: &str| Error::InvalidInput {
+        parameter: parameter.to_string(),
+        reason: reason.to_string(),
+    };
+    let serde_json::Value::Object(mut fields) = serde_json::to_value(data)? else {
+        return Err(invalid("Data must serialize to a map"));
+    };
+    let nested = |value: &serde_json::Value| value.is_object() || value.is_array();
+    for value in fields.values_mut() {
+        match value {
+            serde_json::Value::Array(items) if items.iter().any(nested) => {
+                return Err(invalid("Sequences may only contain scalars"));
+            }
+            serde_json::Value::Object(_) => {
+                return Err(invalid("Nested objects cannot be encoded"));
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    nulls.apply(item);
+                }
+            }
+            value => nulls.apply(value),
+        }
+    }
+    Ok(fields)
+}
+
+/// Reject a response whose `Content-Length` values disagree
+///
+/// Considers repeated headers as well as comma-joined values, which is how
+/// browsers report duplicates.
+fn check_content_length(headers: &Headers) -> Result<()> {
+    let Some(values) = headers.get("content-length") else {
+        return Ok(());
+    };
+    let mut lengths = values.iter().flat_map(|value| value.split(',')).map(str::trim);
+    let first = lengths.next().unwrap_or_default();
+    if lengths.all(|length| length == first) {
+        return Ok(());
     }
+    Err(Error::Parse {
+        message: format!("Conflicting Content-Length values: {}", values.join(", ")),
+        source: None,
+    })
+}
+
+/// Turn a read response into the attempt's result, applying status and
+/// content-type checks
+fn finish(response: Response, accepted: bool, config: &RequestConfig, now: std::time::SystemTime) -> Result<Response> {
+    // Check for HTTP errors
+    let response = if accepted { response } else { response.error_for_status_at(now)? };
     
-    /// Set response format preference
-    pub fn response_format(mut self, format: ResponseFormat) -> Self {
-        self.config.response_format = format;
-        self
+    if let Some(expected) = &config.required_content_type {
+        response.ensure_content_type(expected)?;
     }
     
-    /// Execute the request
-    pub async fn send(self) -> Result<Response> {
-        self.client.execute(self.url, self.config).await
+    Ok(response)
+}
+
+/// Turn a success whose body asks for a retry into an `Error::Http`,
+/// holding the response back in case no retry follows
+///
+/// Only done while retries remain, so the last attempt's response is
+/// returned as-is; so is one whose retry the deadline doesn't allow.
+fn retry_on_body(
+    result: Result<Response>,
+    retry: Option<&RetryConfig>,
+    attempt: u32,
+    clock: &dyn Clock,
+) -> (Result<Response>, Option<Response>) {
+    let Some(retry) =
+        retry.filter(|retry| attempt < retry.max_retries && !retry.retry_on_body_contains.is_empty())
+    else {
+        return (result, None);
+    };
+    let response = match result {
+        Ok(response) => response,
+        err => return (err, None),
+    };
+    let body = body_text(&response);
+    if !retry.body_requests_retry(&body) {
+        return (Ok(response), None);
     }
+    let err = Error::Http {
+        status: response.status,
+        status_text: response.status_text.clone(),
+        body: Some(body.into_owned()),
+        retry_after: response.retry_after_at(clock.system_time()),
+    };
+    (Err(err), Some(response))
 }
 
-/// Build a reqwest client with platform-specific configuration
-fn build_reqwest_client() -> Result<reqwest::Client> {
+/// A decoded body as text, for substring checks
+fn body_text(response: &Response) -> Cow<'_, str> {
+    match &response.body {
+        ResponseBody::Text(text) => Cow::Borrowed(text),
+        ResponseBody::Json(value) => Cow::Owned(value.to_string()),
+        ResponseBody::Binary(bytes) => String::from_utf8_lossy(bytes),
+        ResponseBody::Empty => Cow::Borrowed(""),
+    }
+}
+
+/// Pick how to decode a body, honoring preferred formats if any are set
+fn choose_format(config: &RequestConfig, content_type: &str) -> ResponseFormat {
+    if config.preferred_formats.is_empty() {
+        config.response_format
+    } else {
+        config
+            .preferred_formats
+            .iter()
+            .copied()
+            .find(|format| format.matches_content_type(content_type))
+            .unwrap_or(ResponseFormat::Auto)
+    }
+}
+
+/// Sleep for the given duration on the current platform
+async fn sleep(delay: Duration) {
     #[cfg(not(target_arch = "wasm32"))]
     {
-        reqwest::Client::builder()
-            .build()
-            .map_err(|e| Error::network("Failed to create HTTP client", e))
+        tokio::time::sleep(delay).await;
     }
     
     #[cfg(target_arch = "wasm32")]
     {
-        reqwest::Client::builder()
-            .build()
-            .map_err(|e| Error::network("Failed to create HTTP client", e))
+        use wasm_bindgen::JsCast;
+        let delay_ms = i32::try_from(delay.as_millis()).unwrap_or(i32::MAX);
+        let timer = js_sys::Promise::new(&mut |resolve, _| {
+            // Reached through the global object so it works in windows and
+            // workers alike; resolves at once where `setTimeout` is missing
+            let global = js_sys::global();
+            let scheduled = js_sys::Reflect::get(&global, &"setTimeout".into())
+                .ok()
+                .and_then(|set_timeout| set_timeout.dyn_into::<js_sys::Function>().ok())
+                .is_some_and(|set_timeout| set_timeout.call2(&global, &resolve, &delay_ms.into()).is_ok());
+            if !scheduled {
+                let _ = resolve.call0(&JsValue::UNDEFINED);
+            }
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(timer).await;
+    }
+}
+
+/// Headers of a raw response, skipping values that aren't valid strings
+fn response_headers(response: &reqwest::Response) -> Headers {
+    let mut headers = Headers::new();
+    for (name, value) in response.headers() {
+        if let Ok(value) = value.to_str() {
+            headers.insert(name.to_string(), value);
+        }
+    }
+    headers
+}
+
+/// Delay before retry number `attempt` after `error`
+///
+/// Honors the server's requested wait over our own backoff.
+fn retry_delay(error: &Error, attempt: u32, config: &RetryConfig) -> Duration {
+    match error {
+        Error::Http {
+            status,
+            retry_after: Some(wait),
+            ..
+        } if honors_retry_after(*status) => (*wait).min(config.max_delay),
+        _ => calculate_retry_delay(attempt, config),
     }
 }
 
@@ -520,11 +4257,10 @@ impl WasmClient {
         let client = self.inner.clone();
         future_to_promise(async move {
             // Parse method
-            let method = Method::from_str(&method)
-                .ok_or_else(|| Error::InvalidInput {
-                    parameter: "method".to_string(),
-                    reason: format!("Invalid HTTP method: {}", method),
-                })?;
+            let method = match Method::from_str(&method) {
+                Some(method) => method,
+                None => Method::custom(method)?,
+            };
             
             // Create request builder
             let mut request = client.request(method, &url);
@@ -630,4 +4366,147 @@ mod tests {
         assert_eq!(calculate_retry_delay(2, &config), Duration::from_millis(200));
         assert_eq!(calculate_retry_delay(3, &config), Duration::from_millis(400));
     }
+    
+    #[test]
+    fn test_canonical_string_format() {
+        let client = Client::new().unwrap();
+        let request = client
+            .post("https://api.example.com/v1/my files/item?b=2&a=hello world&a=1")
+            .header("X-Date", " 20240101T000000Z ")
+            .header("Host", "api.example.com")
+            .text("hello");
+        
+        assert_eq!(
+            request.canonical_string(&["X-Date", "host", "content-type"]),
+            "POST\n\
+             /v1/my%20files/item\n\
+             a=1&a=hello%20world&b=2\n\
+             content-type:text/plain;host:api.example.com;x-date:20240101T000000Z\n\
+             content-type;host;x-date\n\
+             2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+    
+    #[test]
+    fn test_canonical_string_ignores_header_order() {
+        let client = Client::new().unwrap();
+        let first = client
+            .request(Method::Get, "https://api.example.com/items?z=1&y=2")
+            .header("X-One", "1")
+            .header("X-Two", "2");
+        let second = client
+            .request(Method::Get, "https://api.example.com/items?y=2&z=1")
+            .header("x-two", "2")
+            .header("x-one", "1");
+        
+        assert_eq!(
+            first.canonical_string(&["x-one", "x-two"]),
+            second.canonical_string(&["X-Two", "X-One"])
+        );
+    }
+    
+    #[test]
+    fn test_to_curl() {
+        let client = Client::new().unwrap();
+        let request = client
+            .post("https://api.example.com/notes?draft=1")
+            .query("tag", "it's")
+            .header("X-Trace", "abc")
+            .header("Authorization", "Bearer secret")
+            .json(&serde_json::json!({"text": "say \"hi\", it's fine"}))
+            .unwrap();
+        
+        assert_eq!(
+            request.to_curl(),
+            "curl -X POST 'https://api.example.com/notes?draft=1&tag=it%27s' \
+             -H 'authorization: [REDACTED]' \
+             -H 'x-trace: abc' \
+             -H 'content-type: application/json' \
+             --data-raw '{\"text\":\"say \\\"hi\\\", it'\\''s fine\"}'"
+        );
+        assert!(request.to_curl_unredacted().contains("-H 'authorization: Bearer secret'"));
+        
+        let get = client.request(Method::Get, "https://api.example.com/");
+        assert_eq!(get.to_curl(), "curl -X GET 'https://api.example.com/'");
+    }
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_builder_from_env() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("HTTP_PROXY", "http://proxy.internal:3128"),
+            ("https_proxy", "http://secure-proxy.internal:3129"),
+            ("NO_PROXY", "localhost,127.0.0.1"),
+            ("RUST_FETCH_TIMEOUT_MS", "1500"),
+        ]);
+        let builder = ClientBuilder::new().from_env_with(|name| env.get(name).map(ToString::to_string));
+        
+        assert_eq!(builder.transport.proxy.http.as_deref(), Some("http://proxy.internal:3128"));
+        assert_eq!(builder.transport.proxy.https.as_deref(), Some("http://secure-proxy.internal:3129"));
+        assert_eq!(builder.transport.proxy.no_proxy.as_deref(), Some("localhost,127.0.0.1"));
+        
+        let client = builder.build().unwrap();
+        assert_eq!(client.config.timeout, Duration::from_millis(1500));
+    }
+    
+    #[test]
+    fn test_builder_from_env_ignores_http_proxy_in_cgi() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("REQUEST_METHOD", "GET"),
+            ("HTTP_PROXY", "http://attacker.example:8080"),
+            ("HTTPS_PROXY", ""),
+        ]);
+        let builder = ClientBuilder::new().from_env_with(|name| env.get(name).map(ToString::to_string));
+        assert_eq!(builder.transport.proxy.http, None);
+        assert_eq!(builder.transport.proxy.https, None);
+        
+        let mut env = env;
+        env.insert("http_proxy", "http://proxy.internal:3128");
+        let builder = ClientBuilder::new().from_env_with(|name| env.get(name).map(ToString::to_string));
+        assert_eq!(builder.transport.proxy.http.as_deref(), Some("http://proxy.internal:3128"));
+    }
+    
+    #[test]
+    fn test_check_content_length() {
+        let mut headers = Headers::new();
+        assert!(check_content_length(&headers).is_ok());
+        headers.insert("Content-Length", "42");
+        headers.insert("Content-Length", "42");
+        assert!(check_content_length(&headers).is_ok());
+        
+        headers.insert("Content-Length", "7");
+        let err = check_content_length(&headers).unwrap_err();
+        assert!(matches!(err, Error::Parse { ref message, .. } if message == "Conflicting Content-Length values: 42, 42, 7"));
+        
+        headers.set("Content-Length", "10, 12");
+        assert!(check_content_length(&headers).is_err());
+    }
+    
+    #[test]
+    fn test_join_base() {
+        let base = "https://api.example.com";
+        assert_eq!(join_base(base, "/users?page=2"), "https://api.example.com/users?page=2");
+        assert_eq!(join_base(base, "users"), "https://api.example.com/users");
+        assert_eq!(join_base(base, "https://other.com/x"), "https://other.com/x");
+        assert_eq!(join_base(base, "//cdn.example.com/a.js"), "https://cdn.example.com/a.js");
+        // Colons in a path don't make it absolute
+        assert_eq!(join_base(base, "users:42"), "https://api.example.com/users:42");
+        assert_eq!(join_base(base, "urn:x"), "https://api.example.com/urn:x");
+        assert_eq!(join_base(base, "ftp://files.example.com/a"), "ftp://files.example.com/a");
+        
+        for base in ["https://api.example.com/v1", "https://api.example.com/v1/"] {
+            assert_eq!(join_base(base, "/users"), "https://api.example.com/v1/users");
+            assert_eq!(join_base(base, "users/1"), "https://api.example.com/v1/users/1");
+        }
+        assert_eq!(join_base("https://api.example.com/v1", ""), "https://api.example.com/v1");
+    }
+    
+    #[test]
+    fn test_strip_base() {
+        let base = "https://api.example.com/v1";
+        assert_eq!(strip_base(base, "https://api.example.com/v1/users?page=2"), Some("users?page=2"));
+        assert_eq!(strip_base(base, base), Some(""));
+        assert_eq!(strip_base(base, "https://api.example.com/v10/users"), None);
+        assert_eq!(strip_base("https://api.example.com", "https://api.example.com.evil/x"), None);
+    }
 }
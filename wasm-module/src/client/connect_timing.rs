@@ -0,0 +1,71 @@
+//! Connection phase timing behind `ClientBuilder::timing_breakdown`
+//! (native only)
+//!
+//! reqwest 0.11 doesn't accept a custom connector, so the only connection
+//! phase the client can observe is the host name lookup, which it supplies
+//! itself through [`TimedResolver`]. hyper drives a new connection from the
+//! task awaiting the response, so lookups are credited to the request
+//! through a task-local set by [`scoped`].
+
+use crate::types::TimingBreakdown;
+use std::future::Future;
+use std::net::ToSocketAddrs;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static CURRENT: Arc<PhaseRecorder>;
+}
+
+/// Host name lookups made while sending one request
+#[derive(Default)]
+pub(super) struct PhaseRecorder {
+    dns: Mutex<Option<Duration>>,
+}
+
+impl PhaseRecorder {
+    /// Breakdown of a request whose head arrived after `head` and which
+    /// finished after `total`
+    ///
+    /// The lookup time is carved out of the time until the head, so the
+    /// phases always add up to `total`.
+    pub(super) fn breakdown(&self, head: Duration, total: Duration) -> TimingBreakdown {
+        let dns = *self.dns.lock().unwrap_or_else(PoisonError::into_inner);
+        TimingBreakdown {
+            dns,
+            connect: None,
+            tls: None,
+            wait: head.saturating_sub(dns.unwrap_or_default()),
+            download: total.saturating_sub(head),
+            total,
+        }
+    }
+}
+
+/// Run `send`, crediting the lookups it causes to `recorder`
+pub(super) async fn scoped<F: Future>(recorder: Option<&Arc<PhaseRecorder>>, send: F) -> F::Output {
+    match recorder {
+        Some(recorder) => CURRENT.scope(recorder.clone(), send).await,
+        None => send.await,
+    }
+}
+
+/// Looks host names up like reqwest's default resolver, with the system
+/// resolver on a blocking thread, and times each lookup
+pub(super) struct TimedResolver;
+
+impl reqwest::dns::Resolve for TimedResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let recorder = CURRENT.try_with(Arc::clone).ok();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs = tokio::task::spawn_blocking(move || (host.as_str(), 0).to_socket_addrs()).await??;
+            if let Some(recorder) = recorder {
+                let mut dns = recorder.dns.lock().unwrap_or_else(PoisonError::into_inner);
+                *dns = Some(dns.unwrap_or_default() + started.elapsed());
+            }
+            Ok(Box::new(addrs) as reqwest::dns::Addrs)
+        })
+    }
+}
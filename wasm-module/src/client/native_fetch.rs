@@ -0,0 +1,207 @@
+//! Browser `fetch` transport used on WASM with the `native-fetch` feature
+//!
+//! Sends each attempt straight through `fetch` instead of reqwest's WASM
+//! backend, which gives access to fetch-only options such as priority
+//! hints and `redirect: "error"`. Everything around the transport (retries,
+//! hooks, body decoding, status checks) is shared with the reqwest path.
+//! reqwest stays a dependency: streaming helpers such as
+//! [`Client::download_verified`] still go through it.
+
+use super::{check_content_length, choose_format, finish, merge_query, resource_timing, sleep, Client, Stopwatch};
+use crate::{
+    error::{Error, Result},
+    types::{ErrorBodyEnd, Headers, RequestConfig, Response, ResponseBody, TimingBreakdown},
+};
+use futures_util::future::{select, Either};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+impl Client {
+    /// Send one request through the browser's `fetch` and read the response
+    ///
+    /// The request timeout covers the whole exchange, body included; when
+    /// it fires, the fetch is aborted.
+    pub(super) async fn fetch_once(&self, url: String, config: RequestConfig) -> Result<Response> {
+        let started = Stopwatch::start(&*self.config.clock);
+        let url = merge_query(&url, &config.query, &config.query_replaced);
+        let config = self.prepare_send(&url, &config).await?;
+        
+        let controller = web_sys::AbortController::new()?;
+        let request = self.fetch_request(&url, &config, &controller.signal())?;
+        let exchange = self.fetch_exchange(request, &config, started);
+        let Some(limit) = config.timeout else {
+            return exchange.await;
+        };
+        
+        let timer = sleep(limit);
+        futures_util::pin_mut!(exchange, timer);
+        match select(exchange, timer).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => {
+                controller.abort();
+                Err(Error::Timeout {
+                    duration_ms: u64::try_from(limit.as_millis()).unwrap_or(u64::MAX),
+                })
+            }
+        }
+    }
+    
+    /// Build the fetch `Request` for a prepared config
+    fn fetch_request(
+        &self,
+        url: &str,
+        config: &RequestConfig,
+        signal: &web_sys::AbortSignal,
+    ) -> Result<web_sys::Request> {
+        let init = web_sys::RequestInit::new();
+        init.set_method(config.method.as_str());
+        init.set_signal(Some(signal));
+        if !config.follow_redirects {
+            // Makes fetch reject instead of following
+            init.set_redirect(web_sys::RequestRedirect::Error);
+        }
+        if let Some(priority) = &config.priority {
+            priority.apply(&init)?;
+        }
+        
+        let headers = web_sys::Headers::new()?;
+        for (name, values) in config.headers.iter() {
+            for value in values {
+                headers.append(name, value)?;
+            }
+        }
+        if let Some(body) = &config.body {
+            if !config.headers.contains("content-type") {
+                headers.set("content-type", body.content_type())?;
+            }
+            let bytes = body.encode(config.form_encoding)?;
+            self.record_transfer(bytes.len());
+            init.set_body(&js_sys::Uint8Array::from(bytes.as_slice()));
+        }
+        init.set_headers(&headers);
+        
+        Ok(web_sys::Request::new_with_str_and_init(url, &init)?)
+    }
+    
+    /// Run the fetch and turn the browser's response into a [`Response`]
+    async fn fetch_exchange(
+        &self,
+        request: web_sys::Request,
+        config: &RequestConfig,
+        started: Stopwatch,
+    ) -> Result<Response> {
+        let global = js_sys::global();
+        let promise = if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+            window.fetch_with_request(&request)
+        } else if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+            worker.fetch_with_request(&request)
+        } else {
+            return Err(Error::JsInterop {
+                message: "fetch is not available in this environment".to_string(),
+            });
+        };
+        let response: web_sys::Response = JsFuture::from(promise)
+            .await
+            .map_err(|e| Error::Network {
+                message: format!("fetch failed: {}", js_message(&e)),
+                source: None,
+            })?
+            .dyn_into()?;
+        let wait = started.elapsed(&*self.config.clock);
+        
+        let status = response.status();
+        let status_text = crate::error::status_text(status);
+        let url = response.url();
+        let accepted = self.accepts(config, status);
+        let success = (200..300).contains(&status) || accepted;
+        
+        let mut headers = Headers::new();
+        if let Some(entries) = js_sys::try_iter(&response.headers())? {
+            for entry in entries {
+                let pair = js_sys::Array::from(&entry?);
+                if let (Some(name), Some(value)) = (pair.get(0).as_string(), pair.get(1).as_string()) {
+                    headers.insert(name, value);
+                }
+            }
+        }
+        if self.config.strict_headers {
+            check_content_length(&headers)?;
+        }
+        
+        let body = if config.discard_body && success {
+            ResponseBody::Empty
+        } else {
+            let buffer = JsFuture::from(response.array_buffer()?)
+                .await
+                .map_err(|e| Error::Parse {
+                    message: format!("Failed to read response body: {}", js_message(&e)),
+                    source: None,
+                })?;
+            let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+            self.record_transfer(bytes.len());
+            
+            if let Some((limit, keep)) = self.config.error_body_limit {
+                if !success {
+                    return Err(Error::Http {
+                        status,
+                        status_text,
+                        body: Some(cap_error_body(&bytes, limit, keep)),
+                        retry_after: headers
+                            .get_first("retry-after")
+                            .and_then(|value| crate::time::parse_retry_after(value, self.config.clock.system_time())),
+                    });
+                }
+            }
+            let content_type = headers.get_first("content-type").unwrap_or("");
+            let format = choose_format(config, content_type);
+            self.decode_body(&bytes, format, content_type, config.expect_json)?
+        };
+        
+        let timing = if self.config.timing_breakdown {
+            let total = started.elapsed(&*self.config.clock);
+            resource_timing(&url).or(Some(TimingBreakdown {
+                wait,
+                download: total.saturating_sub(wait),
+                total,
+                ..TimingBreakdown::default()
+            }))
+        } else {
+            None
+        };
+        
+        let response = Response {
+            status,
+            status_text,
+            headers,
+            body,
+            redirects: usize::from(response.redirected()),
+            url,
+            request_id_headers: self.config.request_id_headers.clone(),
+            timing,
+            metrics: None,
+            peer_certificate: None,
+        };
+        finish(response, accepted, config, self.config.clock.system_time())
+    }
+}
+
+/// Keep at most `limit` bytes of an error body from the chosen end, lossily
+/// decoded as UTF-8
+fn cap_error_body(bytes: &[u8], limit: usize, keep: ErrorBodyEnd) -> String {
+    // A cut may split a multi-byte character at the truncated edge
+    match keep {
+        ErrorBodyEnd::Head => String::from_utf8_lossy(&bytes[..bytes.len().min(limit)])
+            .trim_end_matches('\u{FFFD}')
+            .to_string(),
+        ErrorBodyEnd::Tail => String::from_utf8_lossy(&bytes[bytes.len().saturating_sub(limit)..])
+            .trim_start_matches('\u{FFFD}')
+            .to_string(),
+    }
+}
+
+/// Message of a rejected JavaScript promise
+fn js_message(value: &wasm_bindgen::JsValue) -> String {
+    value
+        .dyn_ref::<js_sys::Error>()
+        .map_or_else(|| format!("{:?}", value), |error| String::from(error.message()))
+}
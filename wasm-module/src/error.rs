@@ -23,6 +23,9 @@ pub enum Error {
         status: u16,
         status_text: String,
         body: Option<String>,
+        /// Wait requested by the response's `Retry-After` header; for `429`
+        /// and `503` the retry loop uses it instead of exponential backoff
+        retry_after: Option<std::time::Duration>,
     },
 
     /// Parsing errors (JSON, headers, etc.)
@@ -42,6 +45,44 @@ pub enum Error {
 
     /// Request was cancelled
     Cancelled,
+
+    /// All retry attempts failed
+    ///
+    /// Returned when the final attempt's error would have been retried but
+    /// the retry limit or the request deadline left no room for another
+    /// attempt. An error the retry policy doesn't retry is returned as-is,
+    /// even after earlier retries.
+    RetriesExhausted {
+        /// Total number of attempts made, including the first one
+        attempts: u32,
+        /// Error returned by the final attempt
+        last: Box<Error>,
+    },
+
+    /// The client's transfer quota has been used up
+    QuotaExceeded {
+        /// Configured quota in bytes
+        limit: u64,
+        /// Bytes transferred so far
+        used: u64,
+    },
+
+    /// Obtaining credentials failed (e.g. the `OAuth2` token endpoint
+    /// rejected the request or returned no usable token)
+    Auth {
+        /// What went wrong
+        message: String,
+        /// Status returned by the token endpoint, if it responded
+        status: Option<u16>,
+    },
+
+    /// A downloaded body did not match its expected digest
+    ChecksumMismatch {
+        /// Expected digest, lowercase hex
+        expected: String,
+        /// Digest of the received body, lowercase hex
+        actual: String,
+    },
 }
 
 impl Error {
@@ -56,6 +97,17 @@ impl Error {
         }
     }
 
+    /// Create an HTTP error, filling `status_text` with the canonical
+    /// reason phrase (`404` becomes "Not Found", unknown codes "Unknown")
+    pub fn http(status: u16, body: Option<String>) -> Self {
+        Error::Http {
+            status,
+            status_text: status_text(status),
+            body,
+            retry_after: None,
+        }
+    }
+
     /// Create a parse error with a source
     pub fn parse<E: std::error::Error + Send + Sync + 'static>(
         message: impl Into<String>,
@@ -77,6 +129,10 @@ impl Error {
             Error::InvalidInput { .. } => "InvalidInputError",
             Error::JsInterop { .. } => "JsInteropError",
             Error::Cancelled => "CancelledError",
+            Error::RetriesExhausted { .. } => "RetriesExhaustedError",
+            Error::QuotaExceeded { .. } => "QuotaExceededError",
+            Error::Auth { .. } => "AuthError",
+            Error::ChecksumMismatch { .. } => "ChecksumMismatchError",
         }
     }
 
@@ -108,6 +164,7 @@ impl fmt::Display for Error {
                 status,
                 status_text,
                 body,
+                ..
             } => {
                 write!(f, "HTTP error {}: {}", status, status_text)?;
                 if let Some(body) = body {
@@ -134,6 +191,22 @@ impl fmt::Display for Error {
             Error::Cancelled => {
                 write!(f, "Request was cancelled")
             }
+            Error::RetriesExhausted { attempts, last } => {
+                write!(f, "Retries exhausted after {attempts} attempts: {last}")
+            }
+            Error::QuotaExceeded { limit, used } => {
+                write!(f, "Transfer quota of {limit} bytes exceeded ({used} bytes used)")
+            }
+            Error::Auth { message, status } => {
+                write!(f, "Authentication error: {message}")?;
+                if let Some(status) = status {
+                    write!(f, " (status {status})")?;
+                }
+                Ok(())
+            }
+            Error::ChecksumMismatch { expected, actual } => {
+                write!(f, "Checksum mismatch: expected {expected}, got {actual}")
+            }
         }
     }
 }
@@ -144,6 +217,7 @@ impl std::error::Error for Error {
             Error::Network { source, .. } | Error::Parse { source, .. } => source
                 .as_ref()
                 .map(|s| s.as_ref() as &(dyn std::error::Error + 'static)),
+            Error::RetriesExhausted { last, .. } => Some(last.as_ref()),
             _ => None,
         }
     }
@@ -152,12 +226,13 @@ impl std::error::Error for Error {
 /// Convert Error to JsValue for WASM boundary
 impl From<Error> for JsValue {
     fn from(error: Error) -> Self {
-        // Create a JavaScript error object with detailed information
-        let obj = js_sys::Object::new();
+        // Create a real JavaScript Error so `instanceof Error` holds and a
+        // stack trace is captured, named after the error kind
+        let obj = js_sys::Error::new(&error.to_string());
+        obj.set_name(error.kind());
 
         // Set error properties
         let _ = js_sys::Reflect::set(&obj, &"kind".into(), &error.kind().into());
-        let _ = js_sys::Reflect::set(&obj, &"message".into(), &error.to_string().into());
 
         // Add additional context based on error type
         match &error {
@@ -165,12 +240,18 @@ impl From<Error> for JsValue {
                 status,
                 status_text,
                 body,
+                retry_after,
             } => {
                 let _ = js_sys::Reflect::set(&obj, &"status".into(), &(*status as f64).into());
                 let _ = js_sys::Reflect::set(&obj, &"statusText".into(), &status_text.into());
                 if let Some(body) = body {
                     let _ = js_sys::Reflect::set(&obj, &"body".into(), &body.into());
                 }
+                if let Some(wait) = retry_after {
+                    #[allow(clippy::cast_precision_loss)]
+                    let wait_ms = wait.as_millis() as f64;
+                    let _ = js_sys::Reflect::set(&obj, &"retryAfterMs".into(), &wait_ms.into());
+                }
             }
             Error::Timeout { duration_ms } => {
                 let _ =
@@ -179,6 +260,26 @@ impl From<Error> for JsValue {
             Error::InvalidInput { parameter, .. } => {
                 let _ = js_sys::Reflect::set(&obj, &"parameter".into(), &parameter.into());
             }
+            Error::RetriesExhausted { attempts, last } => {
+                let _ = js_sys::Reflect::set(&obj, &"attempts".into(), &f64::from(*attempts).into());
+                let _ = js_sys::Reflect::set(&obj, &"lastKind".into(), &last.kind().into());
+            }
+            Error::QuotaExceeded { limit, used } => {
+                #[allow(clippy::cast_precision_loss)]
+                let (limit, used) = (*limit as f64, *used as f64);
+                let _ = js_sys::Reflect::set(&obj, &"limit".into(), &limit.into());
+                let _ = js_sys::Reflect::set(&obj, &"used".into(), &used.into());
+            }
+            Error::Auth {
+                status: Some(status),
+                ..
+            } => {
+                let _ = js_sys::Reflect::set(&obj, &"status".into(), &f64::from(*status).into());
+            }
+            Error::ChecksumMismatch { expected, actual } => {
+                let _ = js_sys::Reflect::set(&obj, &"expected".into(), &expected.into());
+                let _ = js_sys::Reflect::set(&obj, &"actual".into(), &actual.into());
+            }
             _ => {}
         }
 
@@ -186,22 +287,70 @@ impl From<Error> for JsValue {
     }
 }
 
-/// Convert from reqwest errors
-impl From<reqwest::Error> for Error {
-    fn from(err: reqwest::Error) -> Self {
+/// Error returned by
+/// [`RequestBuilder::send_with_attempts`](crate::client::RequestBuilder::send_with_attempts)
+/// when no attempt succeeded
+///
+/// Converts into [`Error`], so `?` works in functions returning [`Result`].
+#[derive(Debug)]
+pub struct FailedAttempts {
+    /// The final error, as [`RequestBuilder::send`](crate::client::RequestBuilder::send)
+    /// would return it
+    pub error: Error,
+    /// Every attempt made, in order; empty if the request failed before
+    /// it could be sent
+    pub attempts: Vec<crate::types::AttemptRecord>,
+}
+
+impl fmt::Display for FailedAttempts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (after {} attempts)", self.error, self.attempts.len())
+    }
+}
+
+impl std::error::Error for FailedAttempts {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<FailedAttempts> for Error {
+    fn from(failed: FailedAttempts) -> Self {
+        failed.error
+    }
+}
+
+/// Canonical reason phrase for `status`, or "Unknown"
+pub(crate) fn status_text(status: u16) -> String {
+    reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Whole milliseconds in `duration`, saturating at `u64::MAX`
+pub(crate) fn duration_ms(duration: std::time::Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+impl Error {
+    /// Convert a reqwest error from a request sent with `timeout` applied
+    ///
+    /// A timed out request reports `timeout` as its duration, falling back
+    /// to the client's 30 second default when it isn't known.
+    pub(crate) fn from_reqwest(err: reqwest::Error, timeout: Option<std::time::Duration>) -> Self {
         if err.is_timeout() {
-            Error::Timeout { duration_ms: 30000 } // Default timeout
+            Error::Timeout {
+                duration_ms: timeout.map_or(30000, duration_ms),
+            }
         } else if err.is_decode() {
             Error::Parse {
                 message: "Failed to decode response".to_string(),
                 source: Some(Box::new(err)),
             }
         } else if let Some(status) = err.status() {
-            Error::Http {
-                status: status.as_u16(),
-                status_text: status.canonical_reason().unwrap_or("Unknown").to_string(),
-                body: None,
-            }
+            Error::http(status.as_u16(), None)
         } else {
             Error::Network {
                 message: "Request failed".to_string(),
@@ -211,6 +360,13 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+/// Convert from reqwest errors
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Self::from_reqwest(err, None)
+    }
+}
+
 /// Convert from serde_json errors
 impl From<serde_json::Error> for Error {
     fn from(err: serde_json::Error) -> Self {
@@ -268,14 +424,45 @@ mod tests {
         assert!(Error::Http {
             status: 503,
             status_text: "".to_string(),
-            body: None
+            body: None,
+            retry_after: None,
         }
         .is_retryable());
         assert!(!Error::Http {
             status: 400,
             status_text: "".to_string(),
-            body: None
+            body: None,
+            retry_after: None,
         }
         .is_retryable());
     }
+
+    #[test]
+    fn test_retries_exhausted() {
+        let error = Error::RetriesExhausted {
+            attempts: 3,
+            last: Box::new(Error::Timeout { duration_ms: 100 }),
+        };
+        assert_eq!(error.kind(), "RetriesExhaustedError");
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "Retries exhausted after 3 attempts: Request timeout after 100ms"
+        );
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn test_http_constructor_fills_status_text() {
+        let error = Error::http(404, None);
+        assert!(matches!(
+            error,
+            Error::Http { status: 404, ref status_text, body: None, retry_after: None }
+                if status_text == "Not Found"
+        ));
+        assert_eq!(error.to_string(), "HTTP error 404: Not Found");
+
+        let error = Error::http(599, Some("overloaded".to_string()));
+        assert!(matches!(error, Error::Http { ref status_text, body: Some(_), .. } if status_text == "Unknown"));
+    }
 }
@@ -12,13 +12,42 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::future_to_promise;
 use serde_wasm_bindgen::{Serializer};
 use serde::Serialize;
+use std::sync::RwLock;
+
+/// Base URL prepended to relative URLs by the convenience functions
+static DEFAULT_BASE_URL: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set a base URL for `fetch_json`, `fetch_text` and `fetch_with_options`
+///
+/// Relative URLs passed to those functions are resolved against it, while
+/// absolute URLs (with a scheme) pass through unchanged. The setting is
+/// process-wide and guarded by a lock, so it is safe to change from any
+/// thread, but it affects every convenience call made afterwards. Pass an
+/// empty string to clear it. Clients created with [`Client::builder`] are
+/// not affected; use [`crate::client::ClientBuilder::base_url`] for those.
+#[wasm_bindgen]
+pub fn set_default_base_url(url: String) {
+    let mut base = DEFAULT_BASE_URL.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+    *base = if url.is_empty() { None } else { Some(url) };
+}
+
+/// Resolve a convenience-function URL against the default base URL
+///
+/// Joins like [`crate::client::ClientBuilder::base_url`] does.
+fn resolve_url(url: String) -> String {
+    let base = DEFAULT_BASE_URL.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+    match base.as_deref() {
+        Some(base) => crate::client::join_base(base, &url),
+        None => url,
+    }
+}
 
 /// Simple fetch function for JSON data
 #[wasm_bindgen]
 pub async fn fetch_json(url: String) -> Result<JsValue> {
     let client = Client::new()?;
     let response = client
-        .get(&url)
+        .get(resolve_url(url))
         .await?;
     
     match response.body {
@@ -48,7 +77,7 @@ pub fn fetch_json_promise(url: String) -> js_sys::Promise {
 pub async fn fetch_text(url: String) -> Result<String> {
     let client = Client::new()?;
     let response = client
-        .request(Method::Get, &url)
+        .request(Method::Get, resolve_url(url))
         .response_format(ResponseFormat::Text)
         .send()
         .await?;
@@ -73,14 +102,13 @@ pub async fn fetch_with_options(
     let client = Client::new()?;
     
     // Parse method
-    let method = Method::from_str(&method)
-        .ok_or_else(|| crate::error::Error::InvalidInput {
-            parameter: "method".to_string(),
-            reason: format!("Invalid HTTP method: {}", method),
-        })?;
+    let method = match Method::from_str(&method) {
+        Some(method) => method,
+        None => Method::custom(method)?,
+    };
     
     // Create request builder
-    let mut request = client.request(method, &url);
+    let mut request = client.request(method, resolve_url(url));
     
     // Parse headers if provided
     if !headers.is_null() && !headers.is_undefined() {
@@ -129,6 +157,40 @@ pub async fn fetch_with_options(
     Ok(obj.into())
 }
 
+/// Post a JSON body and deserialize the JSON response into `T`
+///
+/// Uses a shared default client, so connections are reused across calls.
+/// Relative URLs are resolved against [`set_default_base_url`].
+///
+/// # Errors
+///
+/// Non-2xx responses fail with `Error::Http`, and a body that doesn't match
+/// `T` fails with `Error::Parse`.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn post_json<B, T>(url: impl AsRef<str>, body: &B) -> Result<T>
+where
+    B: Serialize,
+    T: serde::de::DeserializeOwned,
+{
+    default_client()?
+        .post(resolve_url(url.as_ref().to_string()))
+        .json(body)?
+        .send()
+        .await?
+        .into_json()
+}
+
+/// Get the client shared by native convenience functions
+#[cfg(not(target_arch = "wasm32"))]
+fn default_client() -> Result<Client> {
+    static CLIENT: std::sync::OnceLock<Client> = std::sync::OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = Client::new()?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
 /// Create a configured HTTP client (exported for advanced use)
 #[wasm_bindgen]
 pub fn create_client() -> Result<WasmClient> {
@@ -219,6 +281,17 @@ mod tests {
         assert_eq!(status.as_f64().unwrap(), 201.0);
     }
 
+    #[wasm_bindgen_test]
+    async fn test_fetch_json_with_default_base_url() {
+        set_default_base_url("https://jsonplaceholder.typicode.com".to_string());
+        let result = fetch_json("/posts/1".to_string()).await;
+        set_default_base_url(String::new());
+        
+        assert!(result.is_ok());
+        let id = js_sys::Reflect::get(&result.unwrap(), &"id".into()).unwrap();
+        assert_eq!(id.as_f64(), Some(1.0));
+    }
+
     #[wasm_bindgen_test]
     fn test_fetch_json_promise() {
         let promise = fetch_json_promise("https://jsonplaceholder.typicode.com/posts/1".to_string());
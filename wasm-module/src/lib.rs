@@ -42,11 +42,32 @@
 pub mod client;
 pub mod error;
 pub mod types;
+pub mod time;
 
 // Feature modules
 
 pub mod http;
 
+pub mod auth;
+
+#[cfg(feature = "testing")]
+pub mod chaos;
+
+#[cfg(feature = "testing")]
+pub mod assert;
+
+#[cfg(feature = "testing")]
+pub mod mock;
+
+#[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+pub mod tls;
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
 // Re-export commonly used types
 pub use client::{Client, ClientBuilder};
 pub use error::{Error, Result};
@@ -61,7 +82,10 @@ pub mod prelude {
     pub use crate::{
         client::{Client, ClientBuilder},
         error::{Error, Result},
-        http::{fetch_json, fetch_text, fetch_with_options},
+        http::{fetch_json, fetch_text, fetch_with_options, set_default_base_url},
         types::{Headers, Method, Response, ResponseBody},
     };
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use crate::http::post_json;
 }
@@ -0,0 +1,238 @@
+//! Prometheus request metrics
+//!
+//! This module is only available with the `metrics` feature. A
+//! [`PrometheusRecorder`] is fed by the client's `on_complete` hook and
+//! keeps a request counter and a latency histogram per method, host and
+//! status class, rendered in the Prometheus text exposition format by
+//! [`PrometheusRecorder::gather`].
+//!
+//! ```rust,ignore
+//! use rust_fetch::metrics::PrometheusRecorder;
+//!
+//! let recorder = PrometheusRecorder::new();
+//! let client = Client::builder()
+//!     .on_complete(recorder.on_complete())
+//!     .build()?;
+//!
+//! client.get("https://api.example.com/users").await?;
+//! // Serve this from the `/metrics` endpoint
+//! let text = recorder.gather();
+//! ```
+
+use crate::types::{AttemptRecord, RequestContext};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Histogram bucket bounds in seconds, as used by the official clients
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Collects request counts and latencies for a Prometheus scrape
+///
+/// Clones share their state, so one handle can be passed to
+/// [`ClientBuilder::on_complete`](crate::client::ClientBuilder::on_complete)
+/// and another kept for [`PrometheusRecorder::gather`].
+#[derive(Clone)]
+pub struct PrometheusRecorder {
+    buckets: Arc<[f64]>,
+    series: Arc<Mutex<BTreeMap<Labels, Series>>>,
+}
+
+/// `(method, host, status class)`
+type Labels = (String, String, String);
+
+struct Series {
+    /// Non-cumulative count per bucket, plus one for `+Inf`
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl PrometheusRecorder {
+    /// Create a recorder with [`DEFAULT_BUCKETS`]
+    pub fn new() -> Self {
+        Self::with_buckets(DEFAULT_BUCKETS)
+    }
+
+    /// Create a recorder with custom histogram bucket bounds, in seconds
+    ///
+    /// Bounds are sorted; `+Inf` is always added.
+    pub fn with_buckets(buckets: &[f64]) -> Self {
+        let mut buckets = buckets.to_vec();
+        buckets.sort_by(f64::total_cmp);
+        Self {
+            buckets: buckets.into(),
+            series: Arc::default(),
+        }
+    }
+
+    /// A hook for [`ClientBuilder::on_complete`](crate::client::ClientBuilder::on_complete)
+    /// that records every finished request
+    pub fn on_complete(&self) -> impl Fn(&AttemptRecord, &RequestContext) + Send + Sync + 'static {
+        let recorder = self.clone();
+        move |record, _| recorder.record(record)
+    }
+
+    /// Record a finished request from the record of its final attempt
+    ///
+    /// For clients whose `on_complete` hook does more than feed this
+    /// recorder. The observed latency is the final attempt's `elapsed`.
+    pub fn record(&self, record: &AttemptRecord) {
+        let labels = (
+            record.method.as_str().to_string(),
+            host(&record.url),
+            status_class(record.status),
+        );
+        let seconds = record.elapsed.as_secs_f64();
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.buckets.len());
+
+        let mut series = self.series.lock().unwrap_or_else(PoisonError::into_inner);
+        let series = series.entry(labels).or_insert_with(|| Series {
+            buckets: vec![0; self.buckets.len() + 1],
+            count: 0,
+            sum: 0.0,
+        });
+        series.buckets[bucket] += 1;
+        series.count += 1;
+        series.sum += seconds;
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    ///
+    /// Exposes `http_client_requests_total` (counter) and
+    /// `http_client_request_duration_seconds` (histogram), both labeled
+    /// with `method`, `host` and `status` (`2xx`…`5xx`, or `error` when no
+    /// response was received).
+    pub fn gather(&self) -> String {
+        let series = self.series.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+
+        out.push_str("# HELP http_client_requests_total Completed HTTP requests.\n");
+        out.push_str("# TYPE http_client_requests_total counter\n");
+        for (labels, series) in series.iter() {
+            let _ = writeln!(out, "http_client_requests_total{{{}}} {}", label_set(labels), series.count);
+        }
+
+        out.push_str("# HELP http_client_request_duration_seconds Duration of the final attempt of each request.\n");
+        out.push_str("# TYPE http_client_request_duration_seconds histogram\n");
+        for (labels, series) in series.iter() {
+            let labels = label_set(labels);
+            let mut cumulative = 0;
+            let bounds = self.buckets.iter().map(f64::to_string).chain(["+Inf".to_string()]);
+            for (bound, count) in bounds.zip(&series.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "http_client_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(out, "http_client_request_duration_seconds_sum{{{}}} {}", labels, series.sum);
+            let _ = writeln!(out, "http_client_request_duration_seconds_count{{{}}} {}", labels, series.count);
+        }
+        out
+    }
+}
+
+impl Default for PrometheusRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for PrometheusRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let series = self.series.lock().unwrap_or_else(PoisonError::into_inner);
+        f.debug_struct("PrometheusRecorder")
+            .field("buckets", &self.buckets)
+            .field("series", &series.len())
+            .finish()
+    }
+}
+
+/// `host[:port]` of `url`, or `unknown` if it has none
+fn host(url: &str) -> String {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return "unknown".to_string();
+    };
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => "unknown".to_string(),
+    }
+}
+
+fn status_class(status: Option<u16>) -> String {
+    match status {
+        Some(status) => format!("{}xx", status / 100),
+        None => "error".to_string(),
+    }
+}
+
+fn label_set((method, host, status): &Labels) -> String {
+    format!(
+        "method=\"{}\",host=\"{}\",status=\"{}\"",
+        escape(method),
+        escape(host),
+        escape(status)
+    )
+}
+
+/// Escape a label value as the exposition format requires
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Method;
+    use std::time::Duration;
+
+    fn record(status: Option<u16>, elapsed: Duration) -> AttemptRecord {
+        AttemptRecord {
+            attempt: 1,
+            method: Method::Get,
+            url: "https://api.example.com:8443/users?page=2".to_string(),
+            status,
+            error: None,
+            elapsed,
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn test_gather_counts_and_buckets() {
+        let recorder = PrometheusRecorder::with_buckets(&[0.1, 0.01]);
+        recorder.record(&record(Some(200), Duration::from_millis(5)));
+        recorder.record(&record(Some(204), Duration::from_millis(50)));
+        recorder.record(&record(None, Duration::from_secs(1)));
+
+        let text = recorder.gather();
+        let ok = r#"method="GET",host="api.example.com:8443",status="2xx""#;
+        assert!(text.contains(&format!("http_client_requests_total{{{ok}}} 2\n")));
+        assert!(text.contains(&format!("http_client_request_duration_seconds_bucket{{{ok},le=\"0.01\"}} 1\n")));
+        assert!(text.contains(&format!("http_client_request_duration_seconds_bucket{{{ok},le=\"0.1\"}} 2\n")));
+        assert!(text.contains(&format!("http_client_request_duration_seconds_bucket{{{ok},le=\"+Inf\"}} 2\n")));
+        assert!(text.contains(&format!(
+            "http_client_request_duration_seconds_sum{{{}}} {}\n",
+            ok,
+            Duration::from_millis(5).as_secs_f64() + Duration::from_millis(50).as_secs_f64()
+        )));
+        assert!(text.contains(r#"http_client_requests_total{method="GET",host="api.example.com:8443",status="error"} 1"#));
+    }
+
+    #[test]
+    fn test_label_helpers() {
+        assert_eq!(host("http://localhost/x"), "localhost");
+        assert_eq!(host("not a url"), "unknown");
+        assert_eq!(status_class(Some(503)), "5xx");
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
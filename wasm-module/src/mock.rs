@@ -0,0 +1,238 @@
+//! Canned responses for unit testing without a network
+//!
+//! This module is only available with the `testing` feature. A client built
+//! with [`ClientBuilder::mock`](crate::client::ClientBuilder::mock) answers
+//! every request from a [`MockClient`] instead of sending it, while retries,
+//! hooks and status handling run exactly as they would against a server.
+//!
+//! ```rust,ignore
+//! use rust_fetch::mock::{self, MockClient};
+//!
+//! let mock = MockClient::new();
+//! mock.on(Method::Get, "/items", mock::response(503, ResponseBody::Empty))
+//!     .on(Method::Get, "/items", mock::response(200, ResponseBody::Text("[]".into())));
+//! let client = Client::builder()
+//!     .base_url("https://api.example.com")
+//!     .retry_config(RetryConfig::default())
+//!     .mock(&mock)
+//!     .build()?;
+//!
+//! assert_eq!(client.get("/items").await?.text(), Some("[]"));
+//! assert_eq!(mock.requests().len(), 2);
+//! ```
+
+use crate::error::{Error, Result};
+use crate::types::{Headers, Method, Response, ResponseBody};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A response with `status`, its canonical reason phrase, and `body`
+pub fn response(status: u16, body: ResponseBody) -> Response {
+    Response {
+        status,
+        status_text: crate::error::status_text(status),
+        body,
+        ..Response::default()
+    }
+}
+
+/// A request answered by a [`MockClient`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    /// Request method
+    pub method: Method,
+    /// Full URL, including query parameters
+    pub url: String,
+    /// Headers as they would have been sent, after client-wide additions
+    pub headers: Headers,
+    /// Encoded body, or `None` without one or for a streaming body
+    pub body: Option<Vec<u8>>,
+}
+
+/// Registered responses and the requests received so far
+///
+/// Clones share their state, so a test can keep one handle for assertions
+/// after passing another to [`ClientBuilder::mock`](crate::client::ClientBuilder::mock).
+#[derive(Clone, Default)]
+pub struct MockClient {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<MockRequest>,
+}
+
+struct Route {
+    method: Method,
+    pattern: String,
+    /// Returned in order; the last one repeats
+    responses: VecDeque<Response>,
+}
+
+impl MockClient {
+    /// Create a mock with no registered responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `method` requests matching `pattern` with `response`
+    ///
+    /// A pattern matches the full URL or its path and query
+    /// (`/users/1?page=2`); a trailing `*` matches any suffix. Registering
+    /// several responses for the same method and pattern queues them: each
+    /// is returned once, in order, and the last one repeats. Routes are
+    /// tried in registration order, and a request matching none fails with
+    /// `Error::Network`.
+    pub fn on(&self, method: Method, pattern: impl Into<String>, response: Response) -> &Self {
+        let pattern = pattern.into();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state
+            .routes
+            .iter_mut()
+            .find(|route| route.method == method && route.pattern == pattern)
+        {
+            Some(route) => route.responses.push_back(response),
+            None => state.routes.push(Route {
+                method,
+                pattern,
+                responses: VecDeque::from([response]),
+            }),
+        }
+        self
+    }
+
+    /// Every request received so far, in the order they were made
+    ///
+    /// Each retry is a separate request.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .requests
+            .clone()
+    }
+
+    /// Record `request` and pick its response
+    pub(crate) fn respond(&self, request: MockRequest) -> Result<Response> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let url = request.url.clone();
+        let method = request.method;
+        state.requests.push(request);
+
+        let route = state
+            .routes
+            .iter_mut()
+            .find(|route| route.method == method && matches(&route.pattern, &url));
+        let response = match route {
+            Some(route) if route.responses.len() > 1 => route.responses.pop_front(),
+            Some(route) => route.responses.front().cloned(),
+            None => None,
+        };
+        let mut response = response.ok_or_else(|| Error::Network {
+            message: format!("No mock response for {} {}", method.as_str(), url),
+            source: None,
+        })?;
+        if response.url.is_empty() {
+            response.url = url;
+        }
+        Ok(response)
+    }
+}
+
+/// Convert a mock response into the raw reqwest response a server would
+/// have sent, for streaming and other raw paths
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn into_reqwest(response: Response) -> Result<reqwest::Response> {
+    use reqwest::ResponseBuilderExt;
+    
+    let body = match response.body {
+        ResponseBody::Text(text) => text.into_bytes(),
+        ResponseBody::Json(json) => serde_json::to_vec(&json)?,
+        ResponseBody::Binary(bytes) => bytes,
+        ResponseBody::Empty => Vec::new(),
+    };
+    let mut builder = http::Response::builder().status(response.status);
+    for (name, values) in response.headers.iter() {
+        for value in values {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+    }
+    if let Ok(url) = reqwest::Url::parse(&response.url) {
+        builder = builder.url(url);
+    }
+    let response = builder.body(body).map_err(|e| Error::InvalidInput {
+        parameter: "mock response".to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(reqwest::Response::from(response))
+}
+
+impl std::fmt::Debug for MockClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        f.debug_struct("MockClient")
+            .field("routes", &state.routes.len())
+            .field("requests", &state.requests.len())
+            .finish()
+    }
+}
+
+/// Whether `pattern` matches `url` or its path and query
+fn matches(pattern: &str, url: &str) -> bool {
+    let path = reqwest::Url::parse(url).ok().map(|parsed| {
+        let mut path = parsed.path().to_string();
+        if let Some(query) = parsed.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        path
+    });
+    let mut candidates = std::iter::once(url).chain(path.as_deref());
+    match pattern.strip_suffix('*') {
+        Some(prefix) => candidates.any(|candidate| candidate.starts_with(prefix)),
+        None => candidates.any(|candidate| candidate == pattern),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(url: &str) -> MockRequest {
+        MockRequest {
+            method: Method::Get,
+            url: url.to_string(),
+            headers: Headers::new(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_matches_url_or_path() {
+        let url = "https://api.example.com/users/1?page=2";
+        assert!(matches(url, url));
+        assert!(matches("/users/1?page=2", url));
+        assert!(matches("/users/*", url));
+        assert!(matches("https://api.example.com/*", url));
+        assert!(!matches("/users/1", url));
+        assert!(!matches("/teams/*", url));
+    }
+
+    #[test]
+    fn test_queued_responses_then_last_repeats() {
+        let mock = MockClient::new();
+        mock.on(Method::Get, "/a", response(503, ResponseBody::Empty))
+            .on(Method::Get, "/a", response(200, ResponseBody::Empty));
+
+        let statuses: Vec<u16> = (0..3)
+            .map(|_| mock.respond(get("https://x.test/a")).unwrap().status)
+            .collect();
+        assert_eq!(statuses, vec![503, 200, 200]);
+
+        let error = mock.respond(get("https://x.test/b")).unwrap_err();
+        assert_eq!(error.to_string(), "Network error: No mock response for GET https://x.test/b");
+        assert_eq!(mock.requests().len(), 4);
+    }
+}
@@ -0,0 +1,325 @@
+//! Time helpers shared by the client and response types
+
+use std::time::{Duration, SystemTime};
+
+/// Parse a `Retry-After` header value into how long to wait from `now`
+///
+/// Accepts both forms allowed by RFC 9110: delta-seconds (`120`) and an
+/// HTTP-date (`Wed, 21 Oct 2015 07:28:00 GMT`). Dates in the past yield
+/// a zero wait. Returns `None` for anything else, including negative or
+/// fractional seconds.
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use rust_fetch::time::parse_retry_after;
+///
+/// let now = SystemTime::now();
+/// assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+/// assert_eq!(parse_retry_after("soon", now), None);
+/// ```
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        // Absurdly large values saturate instead of failing to parse
+        return Some(Duration::from_secs(value.parse().unwrap_or(u64::MAX)));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Current wall-clock time
+///
+/// `SystemTime::now` panics on `wasm32-unknown-unknown`, so WASM builds
+/// read the clock from JavaScript instead.
+pub(crate) fn now() -> SystemTime {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SystemTime::now()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        SystemTime::UNIX_EPOCH + Duration::from_secs_f64(js_sys::Date::now().max(0.0) / 1000.0)
+    }
+}
+
+/// Source of the current time for a client
+///
+/// Read for deadline checks, attempt timing, token expiry and `Retry-After`
+/// dates. Clients use [`SystemClock`] unless given another clock with
+/// [`ClientBuilder::clock`](crate::client::ClientBuilder::clock); with the
+/// `testing` feature, [`TestClock`] is a clock that only moves when told
+/// to. Sleeps between retries always take real time.
+pub trait Clock: Send + Sync {
+    /// Current monotonic time
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> std::time::Instant;
+    
+    /// Current monotonic time in milliseconds, on the `performance.now()`
+    /// clock
+    #[cfg(target_arch = "wasm32")]
+    fn now_ms(&self) -> f64;
+    
+    /// Current wall-clock time
+    fn system_time(&self) -> SystemTime;
+}
+
+/// The platform's real clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> std::time::Instant {
+        std::time::Instant::now()
+    }
+    
+    #[cfg(target_arch = "wasm32")]
+    fn now_ms(&self) -> f64 {
+        performance_now()
+    }
+    
+    fn system_time(&self) -> SystemTime {
+        now()
+    }
+}
+
+/// A clock that stands still until advanced, for deterministic tests
+///
+/// Only available with the `testing` feature. Clones share the same time,
+/// so a test can keep one to advance after handing another to
+/// [`ClientBuilder::clock`](crate::client::ClientBuilder::clock).
+///
+/// ```rust,ignore
+/// let clock = TestClock::new();
+/// let client = Client::builder().clock(clock.clone()).build()?;
+/// let request = client.request(Method::Get, url).deadline(Duration::from_secs(5));
+/// clock.advance(Duration::from_secs(6));
+/// assert!(matches!(request.send().await, Err(Error::Timeout { .. })));
+/// ```
+#[cfg(feature = "testing")]
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    origin: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    origin_ms: f64,
+    system_origin: SystemTime,
+    offset: std::sync::Arc<std::sync::Mutex<Duration>>,
+}
+
+#[cfg(feature = "testing")]
+impl TestClock {
+    /// Create a clock frozen at the current time
+    pub fn new() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            origin: std::time::Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            origin_ms: performance_now(),
+            system_origin: now(),
+            offset: std::sync::Arc::default(),
+        }
+    }
+    
+    /// Move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        *self.offset.lock().unwrap_or_else(std::sync::PoisonError::into_inner) += duration;
+    }
+    
+    /// Total time the clock has been advanced
+    pub fn elapsed(&self) -> Duration {
+        *self.offset.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "testing")]
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "testing")]
+impl Clock for TestClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> std::time::Instant {
+        self.origin + self.elapsed()
+    }
+    
+    #[cfg(target_arch = "wasm32")]
+    fn now_ms(&self) -> f64 {
+        self.origin_ms + self.elapsed().as_secs_f64() * 1000.0
+    }
+    
+    fn system_time(&self) -> SystemTime {
+        self.system_origin + self.elapsed()
+    }
+}
+
+/// An absolute point in time by which a request must finish
+///
+/// Native builds measure against [`std::time::Instant`]; WASM builds use
+/// the `performance.now()` clock, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    at: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    at_ms: f64,
+}
+
+impl Deadline {
+    /// Deadline at `instant`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn at(instant: std::time::Instant) -> Self {
+        Self { at: instant }
+    }
+    
+    /// Deadline at `ms` on the `performance.now()` clock
+    #[cfg(target_arch = "wasm32")]
+    pub fn at_ms(ms: f64) -> Self {
+        Self { at_ms: ms }
+    }
+    
+    /// Deadline `duration` from now
+    pub fn after(duration: Duration) -> Self {
+        Self::after_on(&SystemClock, duration)
+    }
+    
+    /// Deadline `duration` from now on `clock`
+    pub(crate) fn after_on(clock: &dyn Clock, duration: Duration) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Self::at(clock.now() + duration)
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            Self::at_ms(clock.now_ms() + duration.as_secs_f64() * 1000.0)
+        }
+    }
+    
+    /// Time left until the deadline; zero once it has passed
+    pub fn remaining(&self) -> Duration {
+        self.remaining_on(&SystemClock)
+    }
+    
+    /// Time left until the deadline on `clock`
+    pub(crate) fn remaining_on(&self, clock: &dyn Clock) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.at.saturating_duration_since(clock.now())
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            Duration::from_secs_f64((self.at_ms - clock.now_ms()).max(0.0) / 1000.0)
+        }
+    }
+    
+    /// Whether the deadline has passed
+    pub fn has_passed(&self) -> bool {
+        self.remaining().is_zero()
+    }
+    
+    /// The earlier of two optional deadlines
+    pub(crate) fn earliest(current: Option<Self>, other: Self) -> Self {
+        match current {
+            Some(current) if current < other => current,
+            _ => other,
+        }
+    }
+}
+
+/// Current `performance.now()` reading in milliseconds
+///
+/// Reached through the global object so it works in windows and workers
+/// alike; falls back to `Date.now()` where `performance` is missing.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn performance_now() -> f64 {
+    let global = js_sys::global();
+    js_sys::Reflect::get(&global, &"performance".into())
+        .ok()
+        .filter(|performance| !performance.is_undefined())
+        .and_then(|performance| {
+            use wasm_bindgen::JsCast;
+            js_sys::Reflect::get(&performance, &"now".into())
+                .ok()?
+                .dyn_into::<js_sys::Function>()
+                .ok()?
+                .call0(&performance)
+                .ok()?
+                .as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        let now = at(0);
+        assert_eq!(parse_retry_after("90", now), Some(Duration::from_secs(90)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("-5", now), None);
+        assert_eq!(parse_retry_after("1.5", now), None);
+        assert_eq!(parse_retry_after("", now), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        // Wed, 21 Oct 2015 07:28:00 GMT
+        let date = at(1_445_412_480);
+        let value = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(
+            parse_retry_after(value, date - Duration::from_secs(90)),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(parse_retry_after(value, date), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015", date), None);
+    }
+
+    #[test]
+    fn test_deadline_remaining_and_earliest() {
+        let soon = Deadline::after(Duration::from_millis(50));
+        let later = Deadline::after(Duration::from_secs(90));
+        assert!(!later.has_passed());
+        assert!(later.remaining() > Duration::from_secs(89));
+        assert_eq!(Deadline::earliest(Some(soon), later), soon);
+        assert_eq!(Deadline::earliest(Some(later), soon), soon);
+        assert_eq!(Deadline::earliest(None, later), later);
+        
+        let past = Deadline::at(std::time::Instant::now());
+        assert!(past.has_passed());
+        assert_eq!(past.remaining(), Duration::ZERO);
+    }
+    
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_test_clock_moves_only_when_advanced() {
+        let clock = TestClock::new();
+        let deadline = Deadline::after_on(&clock, Duration::from_secs(5));
+        let system = clock.system_time();
+        assert_eq!(deadline.remaining_on(&clock), Duration::from_secs(5));
+        
+        clock.clone().advance(Duration::from_secs(3));
+        assert_eq!(deadline.remaining_on(&clock), Duration::from_secs(2));
+        assert_eq!(clock.system_time(), system + Duration::from_secs(3));
+        
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(deadline.remaining_on(&clock), Duration::ZERO);
+    }
+    
+    #[test]
+    fn test_parse_retry_after_past_date_is_zero() {
+        let now = at(1_445_412_480 + 3600);
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Peer certificate inspection and pinning (native only)
+//!
+//! This module is only available with the `tls` feature. reqwest exposes
+//! just the leaf certificate the server presented, not the rest of its
+//! chain, so that is the certificate inspected and pinned against.
+//!
+//! Pinned clients handshake with rustls and check the pin while the
+//! connection is being set up, so nothing is sent to a server presenting
+//! any other certificate.
+//!
+//! ```rust,ignore
+//! let client = Client::builder()
+//!     .pin_certificate("96:10:cd:3b:...:04:ad")
+//!     .build()?;
+//! let response = client.get("https://api.example.com/").await?;
+//! for cert in response.peer_certificates().unwrap_or_default() {
+//!     println!("{} issued by {}", cert.subject, cert.issuer);
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+use std::time::{Duration, SystemTime};
+
+/// Details of a certificate presented by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    /// Subject distinguished name, e.g. `O=Example, CN=example.com`
+    pub subject: String,
+    /// Issuer distinguished name
+    pub issuer: String,
+    /// Start of the validity period
+    pub not_before: SystemTime,
+    /// End of the validity period
+    pub not_after: SystemTime,
+    /// SHA-256 digest of the DER encoding, lowercase hex without separators
+    pub sha256_fingerprint: String,
+}
+
+impl CertificateInfo {
+    /// Parse a DER-encoded X.509 certificate
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if `der` is not a valid certificate.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der).map_err(|e| Error::Parse {
+            message: format!("Invalid X.509 certificate: {e}"),
+            source: None,
+        })?;
+        // Dates before 1970 are clamped to the epoch
+        let time = |time: x509_parser::time::ASN1Time| {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(time.timestamp()).unwrap_or(0))
+        };
+        let validity = cert.validity();
+        Ok(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            not_before: time(validity.not_before),
+            not_after: time(validity.not_after),
+            sha256_fingerprint: fingerprint(der),
+        })
+    }
+}
+
+/// SHA-256 fingerprint of a DER certificate, lowercase hex
+pub(crate) fn fingerprint(der: &[u8]) -> String {
+    use sha2::Digest;
+    crate::client::hex_encode(&sha2::Sha256::digest(der))
+}
+
+/// Normalize a SHA-256 fingerprint to lowercase hex without separators
+///
+/// Accepts the `AB:CD:...` form printed by `openssl x509 -fingerprint`
+/// as well as plain hex. Returns `None` unless it is 32 bytes of hex.
+pub(crate) fn normalize_fingerprint(fingerprint: &str) -> Option<String> {
+    let hex: String = fingerprint
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    (hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hex)
+}
+
+/// DER encoding of the leaf certificate a raw response was served with
+pub(crate) fn leaf_der(response: &reqwest::Response) -> Option<&[u8]> {
+    response
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(reqwest::tls::TlsInfo::peer_certificate)
+}
+
+const PIN_MISMATCH: &str = "certificate pin mismatch";
+
+/// Reject a leaf certificate matching none of the normalized `pins`
+///
+/// A connection without a certificate (plain HTTP) is rejected too, since
+/// it can't be verified against the pin.
+pub(crate) fn check_pin(leaf: Option<&[u8]>, pins: &[String]) -> Result<()> {
+    match leaf {
+        Some(der) if pins.contains(&fingerprint(der)) => Ok(()),
+        _ => Err(Error::Network {
+            message: PIN_MISMATCH.to_string(),
+            source: None,
+        }),
+    }
+}
+
+/// rustls configuration accepting only servers whose leaf certificate
+/// matches one of the normalized `pins`
+///
+/// A pinned certificate is trusted as-is, in place of CA validation, so
+/// self-signed certificates can be pinned; it must still be within its
+/// validity period, and the server must prove it holds its key.
+pub(crate) fn pinned_config(pins: Vec<String>) -> rustls::ClientConfig {
+    let mut config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(std::sync::Arc::new(PinnedVerifier { pins }))
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config
+}
+
+/// Whether `err` failed because the server's certificate matched no pin
+pub(crate) fn is_pin_mismatch(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if matches!(err.downcast_ref::<rustls::Error>(), Some(rustls::Error::General(message)) if message == PIN_MISMATCH) {
+            return true;
+        }
+        // `io::Error::source` skips the error it wraps, so step into it
+        current = match err.downcast_ref::<std::io::Error>() {
+            Some(io) => io.get_ref().map(|inner| inner as &(dyn std::error::Error + 'static)),
+            None => err.source(),
+        };
+    }
+    false
+}
+
+/// Certificate verifier behind [`pinned_config`]
+struct PinnedVerifier {
+    pins: Vec<String>,
+}
+
+impl rustls::client::ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<rustls::client::ServerCertVerified, rustls::Error> {
+        if !self.pins.contains(&fingerprint(&end_entity.0)) {
+            return Err(rustls::Error::General(PIN_MISMATCH.to_string()));
+        }
+        let info = CertificateInfo::from_der(&end_entity.0)
+            .map_err(|_| rustls::Error::InvalidCertificate(rustls::CertificateError::BadEncoding))?;
+        if now < info.not_before {
+            return Err(rustls::Error::InvalidCertificate(rustls::CertificateError::NotValidYet));
+        }
+        if now > info.not_after {
+            return Err(rustls::Error::InvalidCertificate(rustls::CertificateError::Expired));
+        }
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    /// Self-signed P-256 certificate for `O=rust-fetch, CN=test.example`
+    const CERT: &str = "\
+        MIIBrTCCAVOgAwIBAgIUBNPXYeIiZU3czHVyctIvR9Q1erkwCgYIKoZIzj0EAwIwLDETMBEGA1UE\
+        CgwKcnVzdC1mZXRjaDEVMBMGA1UEAwwMdGVzdC5leGFtcGxlMB4XDTI2MTAxNjE4MTUzMloXDTM2\
+        MTAxMzE4MTUzMlowLDETMBEGA1UECgwKcnVzdC1mZXRjaDEVMBMGA1UEAwwMdGVzdC5leGFtcGxl\
+        MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAErcipd3lJ++tPRjCA7GwPSKSzUo1fdCR6WI+bgLKv\
+        BszO0lekTdjEWaEaojDqYuZn8fPts+fIVOE1X97xvP4vN6NTMFEwHQYDVR0OBBYEFAzvaY4T2pc/\
+        mXB5y24we5Br0XOMMB8GA1UdIwQYMBaAFAzvaY4T2pc/mXB5y24we5Br0XOMMA8GA1UdEwEB/wQF\
+        MAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgCSFZ41uX2LAqmx2rKKnYjaoCYM3ufynqKjEifSWhOJ0C\
+        IQCDrhuBNX5KDV1zdHMUm4boY2oGtxLrq8Ypt7Hx+A5BHQ==";
+
+    /// As printed by `openssl x509 -noout -fingerprint -sha256`
+    const FINGERPRINT: &str = "96:10:CD:3B:F0:44:72:56:64:2B:BC:2B:E5:3D:A5:1D:\
+        09:AC:08:7A:16:D3:14:F4:F7:8C:E1:F6:78:18:04:AD";
+
+    fn der() -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD.decode(CERT).unwrap()
+    }
+
+    #[test]
+    fn test_certificate_info_from_der() {
+        let info = CertificateInfo::from_der(&der()).unwrap();
+        assert_eq!(info.subject, "O=rust-fetch, CN=test.example");
+        assert_eq!(info.issuer, info.subject);
+        assert_eq!(info.not_before, SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_174_532));
+        assert_eq!(info.not_after, SystemTime::UNIX_EPOCH + Duration::from_secs(2_107_534_532));
+        assert_eq!(Some(info.sha256_fingerprint), normalize_fingerprint(FINGERPRINT));
+
+        assert!(CertificateInfo::from_der(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_check_pin() {
+        let der = der();
+        let pin = normalize_fingerprint(FINGERPRINT).unwrap();
+        assert!(check_pin(Some(&der), std::slice::from_ref(&pin)).is_ok());
+
+        let other = "00".repeat(32);
+        let err = check_pin(Some(&der), std::slice::from_ref(&other)).unwrap_err();
+        assert!(matches!(err, Error::Network { ref message, .. } if message == "certificate pin mismatch"));
+        assert!(check_pin(Some(&der), &[other, pin.clone()]).is_ok());
+        assert!(check_pin(None, &[pin]).is_err());
+    }
+
+    #[test]
+    fn test_normalize_fingerprint() {
+        let hex = "9610cd3bf0447256642bbc2be53da51d09ac087a16d314f4f78ce1f6781804ad";
+        assert_eq!(normalize_fingerprint(FINGERPRINT).as_deref(), Some(hex));
+        assert_eq!(normalize_fingerprint(&hex.to_uppercase()).as_deref(), Some(hex));
+        assert_eq!(normalize_fingerprint("96:10:CD"), None);
+        assert_eq!(normalize_fingerprint(&"zz".repeat(32)), None);
+    }
+}
@@ -8,8 +8,9 @@ use wasm_bindgen::prelude::*;
 use serde::{Serialize, Deserialize};
 
 /// HTTP method enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "UPPERCASE")]
+///
+/// Serializes as the method name, e.g. `"GET"` or `"PURGE"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     Get,
     Post,
@@ -20,6 +21,36 @@ pub enum Method {
     Options,
     Connect,
     Trace,
+    /// A nonstandard method such as `PURGE` or `PROPFIND`, sent verbatim;
+    /// create it with [`Method::custom`]
+    Custom(CustomMethod),
+}
+
+/// Name of a [`Method::Custom`], a valid HTTP token of at most
+/// [`CustomMethod::MAX_LEN`] bytes
+///
+/// Stored inline so [`Method`] stays `Copy`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomMethod {
+    name: [u8; CustomMethod::MAX_LEN],
+    len: usize,
+}
+
+impl CustomMethod {
+    /// Longest custom method name accepted
+    pub const MAX_LEN: usize = 32;
+    
+    /// The method name
+    pub fn as_str(&self) -> &str {
+        // Only ASCII tokens are ever stored
+        std::str::from_utf8(&self.name[..self.len]).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for CustomMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
 }
 
 impl Method {
@@ -39,6 +70,62 @@ impl Method {
         }
     }
     
+    /// Create a method from any valid HTTP method token
+    ///
+    /// Standard names in uppercase map to their own variants; anything else
+    /// becomes [`Method::Custom`], keeping its case since methods are
+    /// case-sensitive.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidInput` if `token` is empty, longer than
+    /// [`CustomMethod::MAX_LEN`] or contains characters not allowed in an
+    /// HTTP token.
+    pub fn custom(token: impl AsRef<str>) -> Result<Self, crate::error::Error> {
+        let token = token.as_ref();
+        if !is_token(token) || token.len() > CustomMethod::MAX_LEN {
+            return Err(crate::error::Error::InvalidInput {
+                parameter: "method".to_string(),
+                reason: format!("Invalid HTTP method token: {token:?}"),
+            });
+        }
+        Ok(match token {
+            "GET" => Method::Get,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "DELETE" => Method::Delete,
+            "PATCH" => Method::Patch,
+            "HEAD" => Method::Head,
+            "OPTIONS" => Method::Options,
+            "CONNECT" => Method::Connect,
+            "TRACE" => Method::Trace,
+            _ => {
+                let mut name = [0; CustomMethod::MAX_LEN];
+                name[..token.len()].copy_from_slice(token.as_bytes());
+                Method::Custom(CustomMethod {
+                    name,
+                    len: token.len(),
+                })
+            }
+        })
+    }
+    
+    /// The method name as sent on the wire
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Patch => "PATCH",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Connect => "CONNECT",
+            Method::Trace => "TRACE",
+            Method::Custom(custom) => custom.as_str(),
+        }
+    }
+    
     /// Convert to reqwest Method
     pub fn to_reqwest(&self) -> reqwest::Method {
         match self {
@@ -51,6 +138,40 @@ impl Method {
             Method::Options => reqwest::Method::OPTIONS,
             Method::Connect => reqwest::Method::CONNECT,
             Method::Trace => reqwest::Method::TRACE,
+            // Custom names are validated tokens, which reqwest always accepts
+            Method::Custom(custom) => {
+                reqwest::Method::from_bytes(custom.as_str().as_bytes()).unwrap_or(reqwest::Method::GET)
+            }
+        }
+    }
+}
+
+/// Whether `value` is a non-empty HTTP token (RFC 9110 `tchar`s)
+pub(crate) fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Method {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Method {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match Method::from_str(&name) {
+            Some(method) => Ok(method),
+            None => Method::custom(name).map_err(serde::de::Error::custom),
         }
     }
 }
@@ -62,7 +183,8 @@ impl Default for Method {
 }
 
 /// Response format preference
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ResponseFormat {
     /// Automatically detect based on Content-Type
     Auto,
@@ -74,8 +196,66 @@ pub enum ResponseFormat {
     Binary,
 }
 
+impl ResponseFormat {
+    /// Media range advertised in the `Accept` header for this format
+    pub fn accept(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Text => "text/*",
+            ResponseFormat::Binary | ResponseFormat::Auto => "*/*",
+        }
+    }
+    
+    /// Check whether a response `Content-Type` satisfies this format
+    ///
+    /// `Binary` and `Auto` accept any content type.
+    pub fn matches_content_type(&self, content_type: &str) -> bool {
+        let content_type = content_type.to_lowercase();
+        match self {
+            ResponseFormat::Json => content_type.contains("json"),
+            ResponseFormat::Text => {
+                content_type.trim_start().starts_with("text/") || content_type.contains("xml")
+            }
+            ResponseFormat::Binary | ResponseFormat::Auto => true,
+        }
+    }
+}
+
+impl std::fmt::Display for ResponseFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ResponseFormat::Auto => "auto",
+            ResponseFormat::Json => "json",
+            ResponseFormat::Text => "text",
+            ResponseFormat::Binary => "binary",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for ResponseFormat {
+    type Err = crate::error::Error;
+    
+    /// Parse a lowercase format name (case-insensitive), e.g. from a CLI flag
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ResponseFormat::Auto),
+            "json" => Ok(ResponseFormat::Json),
+            "text" => Ok(ResponseFormat::Text),
+            "binary" => Ok(ResponseFormat::Binary),
+            _ => Err(crate::error::Error::InvalidInput {
+                parameter: "format".to_string(),
+                reason: format!("Unknown response format: {s}"),
+            }),
+        }
+    }
+}
+
 /// HTTP headers collection
-#[derive(Debug, Clone, Default)]
+///
+/// Serializes as a map from lowercase name to the list of values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
 pub struct Headers {
     inner: HashMap<String, Vec<String>>,
 }
@@ -86,6 +266,18 @@ impl Headers {
         Self::default()
     }
     
+    /// Create an empty headers collection with room for `capacity` distinct names
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: HashMap::with_capacity(capacity),
+        }
+    }
+    
+    /// Reserve room for at least `additional` more distinct header names
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+    
     /// Add a header value
     pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
         let name = name.into().to_lowercase();
@@ -110,6 +302,11 @@ impl Headers {
         self.get(name).and_then(|v| v.first().map(|s| s.as_str()))
     }
     
+    /// Number of values for a header, `0` if absent
+    pub fn count(&self, name: &str) -> usize {
+        self.get(name).map_or(0, Vec::len)
+    }
+    
     /// Remove a header
     pub fn remove(&mut self, name: &str) -> Option<Vec<String>> {
         self.inner.remove(&name.to_lowercase())
@@ -146,8 +343,30 @@ impl Headers {
     }
 }
 
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = HashMap::<String, Vec<String>>::deserialize(deserializer)?;
+        let mut headers = Headers::with_capacity(map.len());
+        for (name, values) in map {
+            for value in values {
+                headers.insert(name.clone(), value);
+            }
+        }
+        Ok(headers)
+    }
+}
+
 /// Request configuration
-#[derive(Debug, Clone)]
+///
+/// Serializable so request specs can be persisted and replayed later with
+/// [`crate::client::Client::execute_config`]. Closures (`body_factory`,
+/// `fallback`, `on_progress`) and the `deadline` are skipped when serializing and come
+/// back as `None`; missing fields deserialize to their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+// Each flag is an independent per-request switch set by its own builder
+// method; kept flat so serialized specs read as plain key/value pairs
+#[allow(clippy::struct_excessive_bools)]
 pub struct RequestConfig {
     /// HTTP method
     pub method: Method,
@@ -163,6 +382,52 @@ pub struct RequestConfig {
     pub max_redirects: u32,
     /// Response format preference
     pub response_format: ResponseFormat,
+    /// Maximum time for reading the response body (native only)
+    pub read_timeout: Option<Duration>,
+    /// Ordered format preferences; when non-empty, the format is picked by
+    /// matching the response `Content-Type` against this list instead of
+    /// using `response_format`
+    pub preferred_formats: Vec<ResponseFormat>,
+    /// Encoding of sequence-valued fields in `Body::FormFields`
+    pub form_encoding: FormEncoding,
+    /// Produces the body for each attempt; overrides `body` when set
+    #[serde(skip)]
+    pub body_factory: Option<BodyFactory>,
+    /// Statuses accepted as success in addition to 2xx
+    pub success_statuses: Vec<std::ops::RangeInclusive<u16>>,
+    /// Substitutes a response for the final error once retries are exhausted
+    #[serde(skip)]
+    pub fallback: Option<Fallback>,
+    /// Include the `Content-Type` and a body snippet in JSON parse errors
+    pub expect_json: bool,
+    /// Media type the response `Content-Type` must match
+    pub required_content_type: Option<String>,
+    /// Browser fetch priority hint (WASM only)
+    pub priority: Option<FetchPriority>,
+    /// Group key used by `Client::cancel_tag`
+    pub tag: Option<String>,
+    /// Query parameters appended to the URL's own, in order
+    pub query: Vec<(String, String)>,
+    /// Keys whose parameters in the URL are dropped before appending `query`
+    pub query_replaced: Vec<String>,
+    /// Values handed to the client's `on_retry` and `on_complete` hooks
+    #[serde(skip)]
+    pub context: RequestContext,
+    /// Send sensitive headers with a `TRACE` request instead of stripping them
+    pub allow_trace_sensitive: bool,
+    /// Send `Connection: close` so the connection isn't reused (native only)
+    pub connection_close: bool,
+    /// Skip reading the body of successful responses
+    pub discard_body: bool,
+    /// Point in time by which every attempt, including retries, must finish
+    #[serde(skip)]
+    pub deadline: Option<crate::time::Deadline>,
+    /// Called as the response body downloads; ignored on WASM, where the
+    /// body isn't read in chunks
+    #[serde(skip)]
+    pub on_progress: Option<ProgressCallback>,
+    /// Base URL to resolve the request URL against instead of the client's
+    pub base_url: Option<String>,
 }
 
 impl Default for RequestConfig {
@@ -175,12 +440,131 @@ impl Default for RequestConfig {
             follow_redirects: true,
             max_redirects: 10,
             response_format: ResponseFormat::Auto,
+            read_timeout: None,
+            preferred_formats: Vec::new(),
+            form_encoding: FormEncoding::default(),
+            body_factory: None,
+            success_statuses: Vec::new(),
+            fallback: None,
+            expect_json: false,
+            required_content_type: None,
+            priority: None,
+            tag: None,
+            query: Vec::new(),
+            query_replaced: Vec::new(),
+            context: RequestContext::default(),
+            allow_trace_sensitive: false,
+            connection_close: false,
+            discard_body: false,
+            deadline: None,
+            on_progress: None,
+            base_url: None,
+        }
+    }
+}
+
+impl RequestConfig {
+    /// Layer `overrides` on top of this config
+    ///
+    /// A field of `overrides` wins when it differs from
+    /// [`RequestConfig::default`], so an override only needs to set what it
+    /// changes. Precisely:
+    ///
+    /// - `method`, `timeout`, `max_redirects`, `response_format` and
+    ///   `form_encoding` are replaced when not the default (`GET`, 30
+    ///   seconds, 10, `Auto`, `Repeated`); an explicit `timeout: None` does
+    ///   count as a change
+    /// - `read_timeout`, `fallback`, `required_content_type`, `priority`,
+    ///   `tag`, `on_progress` and `base_url` are replaced when `Some`
+    /// - `body` and `body_factory` are replaced together when the override
+    ///   sets either, so an override body isn't shadowed by an inherited
+    ///   factory (which would win at send time) and vice versa
+    /// - `preferred_formats` is replaced when non-empty
+    /// - `headers` merge per name: a name set in `overrides` takes its
+    ///   values from there, other names keep this config's values
+    /// - `query` is appended after this config's parameters, except for
+    ///   keys in the override's `query_replaced`, which are dropped from
+    ///   here first; `query_replaced` and `success_statuses` are combined
+    /// - `context` values merge, the override's winning for a shared type
+    /// - the flags `expect_json`, `allow_trace_sensitive`,
+    ///   `connection_close` and `discard_body` are set if either sets them,
+    ///   and `follow_redirects` is cleared if either clears it
+    /// - `deadline` is the earlier of the two
+    ///
+    /// A consequence of the rule is that an override can't reset a field
+    /// to its default value, e.g. a `GET` override keeps a `POST` base.
+    #[must_use]
+    pub fn merge(&self, overrides: &RequestConfig) -> RequestConfig {
+        let defaults = RequestConfig::default();
+        let mut merged = self.clone();
+        
+        if overrides.method != defaults.method {
+            merged.method = overrides.method;
+        }
+        if overrides.timeout != defaults.timeout {
+            merged.timeout = overrides.timeout;
+        }
+        if overrides.max_redirects != defaults.max_redirects {
+            merged.max_redirects = overrides.max_redirects;
+        }
+        if overrides.response_format != defaults.response_format {
+            merged.response_format = overrides.response_format;
+        }
+        if overrides.form_encoding != defaults.form_encoding {
+            merged.form_encoding = overrides.form_encoding;
+        }
+        
+        if overrides.body.is_some() || overrides.body_factory.is_some() {
+            merged.body.clone_from(&overrides.body);
+            merged.body_factory.clone_from(&overrides.body_factory);
+        }
+        merged.read_timeout = overrides.read_timeout.or(merged.read_timeout);
+        merged.fallback = overrides.fallback.clone().or(merged.fallback);
+        merged.required_content_type = overrides
+            .required_content_type
+            .clone()
+            .or(merged.required_content_type);
+        merged.priority = overrides.priority.or(merged.priority);
+        merged.tag = overrides.tag.clone().or(merged.tag);
+        merged.base_url = overrides.base_url.clone().or(merged.base_url);
+        merged.on_progress = overrides.on_progress.clone().or(merged.on_progress);
+        if !overrides.preferred_formats.is_empty() {
+            merged.preferred_formats.clone_from(&overrides.preferred_formats);
         }
+        
+        for (name, values) in overrides.headers.iter() {
+            merged.headers.inner.insert(name.clone(), values.clone());
+        }
+        
+        merged.query.retain(|(key, _)| !overrides.query_replaced.contains(key));
+        merged.query.extend(overrides.query.iter().cloned());
+        for key in &overrides.query_replaced {
+            if !merged.query_replaced.contains(key) {
+                merged.query_replaced.push(key.clone());
+            }
+        }
+        merged.success_statuses.extend(overrides.success_statuses.iter().cloned());
+        if !overrides.context.is_empty() {
+            std::sync::Arc::make_mut(&mut merged.context.0)
+                .extend(overrides.context.0.iter().map(|(key, value)| (*key, value.clone())));
+        }
+        
+        merged.expect_json |= overrides.expect_json;
+        merged.allow_trace_sensitive |= overrides.allow_trace_sensitive;
+        merged.connection_close |= overrides.connection_close;
+        merged.discard_body |= overrides.discard_body;
+        merged.follow_redirects &= overrides.follow_redirects;
+        if let Some(deadline) = overrides.deadline {
+            merged.deadline = Some(crate::time::Deadline::earliest(merged.deadline, deadline));
+        }
+        
+        merged
     }
 }
 
 /// Request body variants
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Body {
     /// Text body
     Text(String),
@@ -190,16 +574,346 @@ pub enum Body {
     Binary(Vec<u8>),
     /// Form data
     Form(HashMap<String, String>),
+    /// Form data whose fields may hold sequences; see [`FormEncoding`]
+    FormFields(serde_json::Map<String, serde_json::Value>),
+    /// Streamed body that is sent once without buffering (native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    Stream(StreamBody),
+    /// Streamed `multipart/form-data` body, sent once (native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    Multipart(MultipartBody),
+}
+
+/// A one-shot streaming request body
+///
+/// Cloning shares the underlying stream, so only the first send gets the
+/// data; later attempts (retries, 307/308 redirects) fail with
+/// `Error::InvalidInput` instead of silently sending an empty body.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct StreamBody(std::sync::Arc<std::sync::Mutex<Option<reqwest::Body>>>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StreamBody {
+    /// Wrap a reqwest body, typically built with `reqwest::Body::wrap_stream`
+    pub fn new(body: reqwest::Body) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(Some(body))))
+    }
+    
+    /// Take the body for sending; `None` once it has been taken
+    pub(crate) fn take(&self) -> Option<reqwest::Body> {
+        self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for StreamBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StreamBody(..)")
+    }
+}
+
+/// A one-shot streamed multipart body
+///
+/// Like [`StreamBody`], clones share the parts and only the first send
+/// gets them.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct MultipartBody(std::sync::Arc<std::sync::Mutex<Option<Vec<StreamPart>>>>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MultipartBody {
+    /// Wrap the parts to upload, in order
+    pub fn new(parts: Vec<StreamPart>) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(Some(parts))))
+    }
+    
+    /// Take the parts for sending; `None` once they have been taken
+    pub(crate) fn take(&self) -> Option<Vec<StreamPart>> {
+        self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for MultipartBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MultipartBody(..)")
+    }
+}
+
+/// One field of a streamed multipart upload (native only)
+///
+/// Text fields are held in memory; file and reader parts are read in
+/// chunks while the request is sent.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct StreamPart {
+    pub(crate) name: String,
+    pub(crate) source: PartSource,
+    pub(crate) file_name: Option<String>,
+    pub(crate) content_type: Option<String>,
+}
+
+/// Where a [`StreamPart`]'s content comes from
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) enum PartSource {
+    Text(String),
+    File(std::path::PathBuf),
+    Reader(Box<dyn tokio::io::AsyncRead + Send + Sync + Unpin>),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StreamPart {
+    /// An in-memory text field
+    pub fn text(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self::with_source(name, PartSource::Text(value.into()))
+    }
+    
+    /// A file streamed from `path`, opened when the request is sent
+    ///
+    /// The part's file name defaults to the last component of `path`.
+    pub fn file(name: impl Into<String>, path: impl Into<std::path::PathBuf>) -> Self {
+        let path = path.into();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        Self {
+            file_name,
+            ..Self::with_source(name, PartSource::File(path))
+        }
+    }
+    
+    /// Content streamed from an async reader
+    pub fn reader<R>(name: impl Into<String>, reader: R) -> Self
+    where
+        R: tokio::io::AsyncRead + Send + Sync + Unpin + 'static,
+    {
+        Self::with_source(name, PartSource::Reader(Box::new(reader)))
+    }
+    
+    /// Set the `filename` reported in the part's `Content-Disposition`
+    #[must_use]
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+    
+    /// Set the part's `Content-Type`
+    #[must_use]
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+    
+    fn with_source(name: impl Into<String>, source: PartSource) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            file_name: None,
+            content_type: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for StreamPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match &self.source {
+            PartSource::Text(_) => "text",
+            PartSource::File(_) => "file",
+            PartSource::Reader(_) => "reader",
+        };
+        f.debug_struct("StreamPart")
+            .field("name", &self.name)
+            .field("source", &source)
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .finish()
+    }
+}
+
+/// Produces a fresh request body for every attempt
+#[derive(Clone)]
+pub struct BodyFactory(std::sync::Arc<dyn Fn() -> Body + Send + Sync>);
+
+impl BodyFactory {
+    /// Wrap a body-producing closure
+    pub fn new(factory: impl Fn() -> Body + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(factory))
+    }
+    
+    /// Produce a new body
+    pub fn make(&self) -> Body {
+        (self.0)()
+    }
+}
+
+impl std::fmt::Debug for BodyFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BodyFactory(..)")
+    }
+}
+
+/// Produces a substitute response when a request fails for good
+#[derive(Clone)]
+pub struct Fallback(std::sync::Arc<FallbackFn>);
+
+type FallbackFn = dyn Fn(&crate::error::Error) -> Option<Response> + Send + Sync;
+
+impl Fallback {
+    /// Wrap a fallback closure
+    pub fn new(
+        fallback: impl Fn(&crate::error::Error) -> Option<Response> + Send + Sync + 'static,
+    ) -> Self {
+        Self(std::sync::Arc::new(fallback))
+    }
+    
+    /// Ask for a substitute response for `error`
+    pub fn recover(&self, error: &crate::error::Error) -> Option<Response> {
+        (self.0)(error)
+    }
+}
+
+impl std::fmt::Debug for Fallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Fallback(..)")
+    }
+}
+
+/// Receives download progress as `(bytes_so_far, total)`
+///
+/// `total` comes from the response `Content-Length`, `None` when unknown.
+/// Only called on native; WASM requests ignore it.
+#[derive(Clone)]
+pub struct ProgressCallback(std::sync::Arc<std::sync::Mutex<ProgressFn>>);
+
+type ProgressFn = dyn Fn(u64, Option<u64>) + Send;
+
+impl ProgressCallback {
+    /// Wrap a progress closure
+    ///
+    /// The closure needn't be `Sync`: calls are serialized by a lock.
+    pub fn new(callback: impl Fn(u64, Option<u64>) + Send + 'static) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(callback)))
+    }
+    
+    /// Report `downloaded` bytes out of `total`
+    pub fn report(&self, downloaded: u64, total: Option<u64>) {
+        let callback = self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        callback(downloaded, total);
+    }
+}
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback(..)")
+    }
+}
+
+/// Request-scoped values passed through to client callbacks
+///
+/// Holds at most one value per type, set with
+/// `RequestBuilder::context` and read back with [`RequestContext::get`]
+/// in `on_retry`/`on_complete` hooks. Cloning is cheap.
+#[derive(Clone, Default)]
+pub struct RequestContext(std::sync::Arc<HashMap<std::any::TypeId, ContextValue>>);
+
+type ContextValue = std::sync::Arc<dyn std::any::Any + Send + Sync>;
+
+impl RequestContext {
+    /// Store `value`, replacing any earlier value of the same type
+    pub fn insert<T: std::any::Any + Send + Sync>(&mut self, value: T) {
+        std::sync::Arc::make_mut(&mut self.0)
+            .insert(std::any::TypeId::of::<T>(), std::sync::Arc::new(value));
+    }
+    
+    /// Get the stored value of type `T`, if any
+    pub fn get<T: std::any::Any + Send + Sync>(&self) -> Option<&T> {
+        self.0
+            .get(&std::any::TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+    
+    /// Whether no values are stored
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Debug for RequestContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RequestContext({} values)", self.0.len())
+    }
+}
+
+/// How sequence-valued form fields are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormEncoding {
+    /// Repeat the key for every element: `tag=a&tag=b`
+    #[default]
+    Repeated,
+    /// Append empty brackets to the key: `tag[]=a&tag[]=b` (PHP, Rails)
+    Brackets,
+    /// Append the element index to the key: `tag[0]=a&tag[1]=b`
+    IndexedBrackets,
+}
+
+/// How `None` (JSON `null`) fields are written when serializing a struct
+/// to query or form parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializeNulls {
+    /// Leave the parameter out entirely
+    #[default]
+    Omit,
+    /// Send the parameter with an empty value: `page=`
+    EmptyString,
+}
+
+impl SerializeNulls {
+    /// Rewrite a `null` value according to this policy
+    pub(crate) fn apply(self, value: &mut serde_json::Value) {
+        if self == SerializeNulls::EmptyString && value.is_null() {
+            *value = serde_json::Value::String(String::new());
+        }
+    }
 }
 
 impl Body {
+    /// Whether this is a one-shot [`Body::Stream`] or [`Body::Multipart`]
+    pub fn is_stream(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Body::Stream(_) | Body::Multipart(_) = self {
+            return true;
+        }
+        false
+    }
+    
     /// Convert to bytes
     pub fn to_bytes(&self) -> Result<Vec<u8>, crate::error::Error> {
+        self.encode(FormEncoding::default())
+    }
+    
+    /// Convert to bytes, encoding sequence-valued form fields with `encoding`
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidInput` for streaming bodies, which can't be
+    /// buffered, and with `Error::Parse` if a JSON or form body can't be
+    /// encoded.
+    pub fn encode(&self, encoding: FormEncoding) -> Result<Vec<u8>, crate::error::Error> {
         match self {
             Body::Text(s) => Ok(s.as_bytes().to_vec()),
             Body::Json(v) => serde_json::to_vec(v)
                 .map_err(|e| crate::error::Error::parse("Failed to serialize JSON", e)),
             Body::Binary(b) => Ok(b.clone()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Body::Stream(_) | Body::Multipart(_) => Err(crate::error::Error::InvalidInput {
+                parameter: "body".to_string(),
+                reason: "Streaming bodies cannot be buffered".to_string(),
+            }),
             Body::Form(map) => {
                 let encoded = serde_urlencoded::to_string(map)
                     .map_err(|e| crate::error::Error::Parse {
@@ -208,6 +922,36 @@ impl Body {
                     })?;
                 Ok(encoded.into_bytes())
             }
+            Body::FormFields(fields) => {
+                let mut pairs = Vec::new();
+                for (name, value) in fields {
+                    match value {
+                        serde_json::Value::Array(items) => {
+                            for (i, item) in items.iter().enumerate() {
+                                let key = match encoding {
+                                    FormEncoding::Repeated => name.clone(),
+                                    FormEncoding::Brackets => format!("{name}[]"),
+                                    FormEncoding::IndexedBrackets => format!("{name}[{i}]"),
+                                };
+                                if let Some(item) = form_scalar(item) {
+                                    pairs.push((key, item));
+                                }
+                            }
+                        }
+                        value => {
+                            if let Some(value) = form_scalar(value) {
+                                pairs.push((name.clone(), value));
+                            }
+                        }
+                    }
+                }
+                let encoded = serde_urlencoded::to_string(pairs)
+                    .map_err(|e| crate::error::Error::Parse {
+                        message: "Failed to encode form data".to_string(),
+                        source: Some(Box::new(e)),
+                    })?;
+                Ok(encoded.into_bytes())
+            }
         }
     }
     
@@ -217,13 +961,81 @@ impl Body {
             Body::Text(_) => "text/plain",
             Body::Json(_) => "application/json",
             Body::Binary(_) => "application/octet-stream",
-            Body::Form(_) => "application/x-www-form-urlencoded",
+            #[cfg(not(target_arch = "wasm32"))]
+            Body::Stream(_) => "application/octet-stream",
+            #[cfg(not(target_arch = "wasm32"))]
+            Body::Multipart(_) => "multipart/form-data",
+            Body::Form(_) | Body::FormFields(_) => "application/x-www-form-urlencoded",
         }
     }
 }
 
+/// Render a scalar JSON value as a form or query value; `null` fields are
+/// omitted
+pub(crate) fn form_scalar(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// A single JSON Patch (RFC 6902) operation
+///
+/// Paths are JSON Pointers (RFC 6901). A list of operations serializes to
+/// the RFC 6902 array form, e.g. `[{"op": "remove", "path": "/a"}]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Add a value at `path`
+    Add {
+        /// Target location
+        path: String,
+        /// Value to add
+        value: serde_json::Value,
+    },
+    /// Remove the value at `path`
+    Remove {
+        /// Target location
+        path: String,
+    },
+    /// Replace the value at `path`
+    Replace {
+        /// Target location
+        path: String,
+        /// Replacement value
+        value: serde_json::Value,
+    },
+    /// Move the value at `from` to `path`
+    Move {
+        /// Source location
+        from: String,
+        /// Target location
+        path: String,
+    },
+    /// Copy the value at `from` to `path`
+    Copy {
+        /// Source location
+        from: String,
+        /// Target location
+        path: String,
+    },
+    /// Check that the value at `path` equals `value`
+    Test {
+        /// Target location
+        path: String,
+        /// Expected value
+        value: serde_json::Value,
+    },
+}
+
 /// HTTP response wrapper
-#[derive(Debug)]
+///
+/// Serializable so responses can be stored by an external cache; see
+/// [`Response::to_cache_bytes`]. Missing fields deserialize to their
+/// defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Response {
     /// HTTP status code
     pub status: u16,
@@ -235,22 +1047,239 @@ pub struct Response {
     pub body: ResponseBody,
     /// Request URL (after redirects)
     pub url: String,
+    /// Header names checked, in order, by [`Response::server_request_id`]
+    pub request_id_headers: Vec<String>,
+    /// Per-phase timing, when enabled with
+    /// [`crate::client::ClientBuilder::timing_breakdown`]
+    pub timing: Option<TimingBreakdown>,
+    /// Attempt count and retry-inclusive timing, when enabled with
+    /// [`crate::client::ClientBuilder::request_metrics`]
+    pub metrics: Option<RequestMetrics>,
+    /// Number of redirects followed to reach `url`
+    pub redirects: usize,
+    /// DER encoding of the server's leaf certificate, for HTTPS requests;
+    /// only recorded on native with the `tls` feature, `None` otherwise
+    pub peer_certificate: Option<Vec<u8>>,
+}
+
+/// Connection lifetime hints from a `Keep-Alive` response header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAlive {
+    /// How long the server keeps an idle connection open
+    pub timeout: Option<Duration>,
+    /// How many more requests the server accepts on the connection
+    pub max: Option<u32>,
+}
+
+impl KeepAlive {
+    /// Parse the `Keep-Alive` header, e.g. `timeout=5, max=100`
+    ///
+    /// Unknown or malformed parameters are ignored; `None` if the header is
+    /// missing or has neither `timeout` nor `max`.
+    pub(crate) fn from_headers(headers: &Headers) -> Option<Self> {
+        let mut hint = KeepAlive { timeout: None, max: None };
+        for param in headers.get_first("keep-alive")?.split(',') {
+            let Some((name, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "timeout" => hint.timeout = value.parse().ok().map(Duration::from_secs),
+                "max" => hint.max = value.parse().ok(),
+                _ => {}
+            }
+        }
+        (hint.timeout.is_some() || hint.max.is_some()).then_some(hint)
+    }
+}
+
+/// Latency breakdown of a single request
+///
+/// Phase availability differs per platform:
+///
+/// - Native: `dns` is measured for every host name lookup. reqwest accepts
+///   no custom connector, so `connect` and `tls` are always `None` and
+///   their time is part of `wait`. Requests on a reused connection have no
+///   lookup. All phases add up to `total`.
+/// - WASM: all phases come from the browser's `PerformanceResourceTiming`
+///   entry. Cross-origin requests without a `Timing-Allow-Origin` header
+///   only expose `total`, so the other phases may be `None` or zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TimingBreakdown {
+    /// DNS resolution
+    pub dns: Option<Duration>,
+    /// TCP connection setup, excluding TLS
+    pub connect: Option<Duration>,
+    /// TLS handshake
+    pub tls: Option<Duration>,
+    /// From sending the request until the first response byte
+    pub wait: Duration,
+    /// Reading the response body
+    pub download: Duration,
+    /// Whole request, including any redirects
+    pub total: Duration,
+}
+
+impl TimingBreakdown {
+    /// Time from the start of the request until the first response byte
+    pub fn time_to_first_byte(&self) -> Duration {
+        [self.dns, self.connect, self.tls]
+            .into_iter()
+            .flatten()
+            .sum::<Duration>()
+            + self.wait
+    }
+}
+
+/// Attempt count and timing of a whole request, retries included
+///
+/// Measured with `Instant` on native and `performance.now()` on WASM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RequestMetrics {
+    /// Number of attempts made, including the successful one
+    pub attempts: u32,
+    /// From the first attempt until the response, including backoff delays
+    pub total: Duration,
+    /// Time spent on the successful attempt
+    pub last_attempt: Duration,
+    /// Backoff slept between attempts, part of `total`
+    ///
+    /// Tells a slow server apart from an aggressive retry policy.
+    #[serde(default)]
+    pub backoff_total: Duration,
+}
+
+/// Response headers commonly used by servers for their own request IDs
+pub const DEFAULT_REQUEST_ID_HEADERS: &[&str] = &[
+    "x-request-id",
+    "x-amzn-requestid",
+    "x-amz-request-id",
+    "x-correlation-id",
+    "request-id",
+];
+
+impl Default for Response {
+    fn default() -> Self {
+        Self {
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: Headers::new(),
+            body: ResponseBody::Empty,
+            url: String::new(),
+            request_id_headers: DEFAULT_REQUEST_ID_HEADERS
+                .iter()
+                .map(|name| (*name).to_string())
+                .collect(),
+            timing: None,
+            metrics: None,
+            redirects: 0,
+            peer_certificate: None,
+        }
+    }
+}
+
+/// A response whose body is read chunk by chunk instead of buffered
+///
+/// Returned by [`crate::client::RequestBuilder::send_stream`]; the status
+/// and headers are available up front and the value itself is a
+/// [`futures_util::Stream`] of body chunks as they arrive. Native only.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ResponseStream {
+    /// HTTP status code
+    pub status: u16,
+    /// Status text
+    pub status_text: String,
+    /// Response headers
+    pub headers: Headers,
+    /// Request URL (after redirects)
+    pub url: String,
+    pub(crate) chunks: std::pin::Pin<
+        Box<dyn futures_util::Stream<Item = Result<bytes::Bytes, crate::error::Error>> + Send>,
+    >,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl futures_util::Stream for ResponseStream {
+    type Item = Result<bytes::Bytes, crate::error::Error>;
+    
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.chunks.as_mut().poll_next(cx)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseStream")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
 }
 
 /// Response body variants
-#[derive(Debug)]
+///
+/// Serializes externally tagged, e.g. `{"text": "hi"}`, with binary bodies
+/// as base64 and `Empty` as `"empty"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ResponseBody {
     /// Text response
     Text(String),
     /// JSON response
     Json(serde_json::Value),
     /// Binary response
-    Binary(Vec<u8>),
+    Binary(#[serde(with = "base64_bytes")] Vec<u8>),
     /// Empty response
     Empty,
 }
 
+/// Serde adapter storing bytes as a base64 string
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+    
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+    
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl Response {
+    /// Serialize this response for storage in a cache
+    ///
+    /// The format is JSON, so it is stable across platforms and readable
+    /// when debugging; restore it with [`Response::from_cache_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if the response can't be serialized.
+    pub fn to_cache_bytes(&self) -> Result<Vec<u8>, crate::error::Error> {
+        serde_json::to_vec(self)
+            .map_err(|e| crate::error::Error::parse("Failed to serialize response", e))
+    }
+    
+    /// Restore a response stored with [`Response::to_cache_bytes`]
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if `bytes` is not a stored response.
+    pub fn from_cache_bytes(bytes: &[u8]) -> Result<Self, crate::error::Error> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| crate::error::Error::parse("Invalid cached response", e))
+    }
+    
     /// Get body as text
     pub fn text(&self) -> Option<&str> {
         match &self.body {
@@ -281,7 +1310,59 @@ impl Response {
         (200..300).contains(&self.status)
     }
     
-    /// Check if response is redirect (3xx)
+    /// Body size announced by the server in `Content-Length`
+    ///
+    /// `None` when the length is unknown, e.g. for chunked transfers, so
+    /// progress displays can tell an indeterminate download from an empty
+    /// one. A malformed header also yields `None`.
+    pub fn expected_total(&self) -> Option<u64> {
+        self.headers
+            .get_first("content-length")
+            .and_then(|value| value.trim().parse().ok())
+    }
+    
+    /// How long the server asked the client to wait, from `Retry-After`
+    ///
+    /// Handles both delta-seconds and HTTP-date values (see
+    /// [`crate::time::parse_retry_after`]); a date in the past yields a zero
+    /// wait. `None` if the header is missing or malformed.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        self.retry_after_at(crate::time::now())
+    }
+    
+    /// [`Response::retry_after`] measured from `now`
+    pub(crate) fn retry_after_at(&self, now: std::time::SystemTime) -> Option<std::time::Duration> {
+        self.headers
+            .get_first("retry-after")
+            .and_then(|value| crate::time::parse_retry_after(value, now))
+    }
+    
+    /// Connection lifetime hints from the `Keep-Alive` header, if any
+    pub fn keep_alive(&self) -> Option<KeepAlive> {
+        KeepAlive::from_headers(&self.headers)
+    }
+    
+    /// Certificates the server presented, for HTTPS requests (native only,
+    /// requires the `tls` feature)
+    ///
+    /// reqwest only exposes the leaf certificate, so this holds at most one
+    /// entry. `None` for plain HTTP or when the certificate can't be parsed.
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    pub fn peer_certificates(&self) -> Option<Vec<crate::tls::CertificateInfo>> {
+        let der = self.peer_certificate.as_deref()?;
+        crate::tls::CertificateInfo::from_der(der).ok().map(|info| vec![info])
+    }
+    
+    /// Number of redirects the client followed to reach this response
+    ///
+    /// On WASM the browser follows redirects itself without reporting them,
+    /// so this is `0`; with the `native-fetch` feature it is `1` when at
+    /// least one redirect happened.
+    pub fn redirect_count(&self) -> usize {
+        self.redirects
+    }
+    
+    /// Check if response is redirect (3xx)
     pub fn is_redirect(&self) -> bool {
         (300..400).contains(&self.status)
     }
@@ -295,6 +1376,661 @@ impl Response {
     pub fn is_server_error(&self) -> bool {
         (500..600).contains(&self.status)
     }
+    
+    /// Deserialize the body as JSON into `T`, consuming the response
+    pub(crate) fn into_json<T: serde::de::DeserializeOwned>(self) -> Result<T, crate::error::Error> {
+        let value = match self.body {
+            ResponseBody::Json(value) => serde_json::from_value(value)?,
+            ResponseBody::Text(text) => serde_json::from_str(&text)?,
+            ResponseBody::Binary(bytes) => serde_json::from_slice(&bytes)?,
+            ResponseBody::Empty => serde_json::from_slice(b"")?,
+        };
+        Ok(value)
+    }
+    
+    /// Write the body as pretty-printed JSON to `writer` (native only)
+    ///
+    /// JSON bodies are written directly; text and binary bodies are parsed as
+    /// JSON first.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if the body is not JSON or the writer
+    /// returns an I/O error.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_json_pretty<W>(&self, writer: W) -> Result<(), crate::error::Error>
+    where
+        W: std::io::Write,
+    {
+        serde_json::to_writer_pretty(writer, &*self.json_value()?)
+            .map_err(|e| crate::error::Error::parse("Failed to write JSON", e))
+    }
+    
+    /// Deserialize the body into `T` if its `tag_field` is `expected_tag`
+    ///
+    /// A building block for tagged-union dispatch: returns `Ok(None)` when
+    /// the field is missing, not a string, or holds another tag, so the
+    /// caller can try the next type. Text and binary bodies are parsed as
+    /// JSON first.
+    ///
+    /// ```rust,ignore
+    /// if let Some(card) = response.json_tagged::<Card>("type", "card")? {
+    ///     // ...
+    /// } else if let Some(transfer) = response.json_tagged::<Transfer>("type", "transfer")? {
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if the body is not JSON, or if the tag
+    /// matches but the body doesn't deserialize into `T`.
+    pub fn json_tagged<T: serde::de::DeserializeOwned>(
+        &self,
+        tag_field: &str,
+        expected_tag: &str,
+    ) -> Result<Option<T>, crate::error::Error> {
+        let value = self.json_value()?;
+        if value.get(tag_field).and_then(serde_json::Value::as_str) != Some(expected_tag) {
+            return Ok(None);
+        }
+        Ok(Some(T::deserialize(&*value)?))
+    }
+    
+    /// The body as a JSON value, parsing text and binary bodies
+    fn json_value(&self) -> Result<std::borrow::Cow<'_, serde_json::Value>, crate::error::Error> {
+        use std::borrow::Cow;
+        
+        match &self.body {
+            ResponseBody::Json(value) => Ok(Cow::Borrowed(value)),
+            ResponseBody::Text(text) => Ok(Cow::Owned(serde_json::from_str(text)?)),
+            ResponseBody::Binary(bytes) => Ok(Cow::Owned(serde_json::from_slice(bytes)?)),
+            ResponseBody::Empty => Err(crate::error::Error::Parse {
+                message: "Response body is empty, not JSON".to_string(),
+                source: None,
+            }),
+        }
+    }
+    
+    /// Get the server's own request ID for log correlation
+    ///
+    /// Returns the first value present among `request_id_headers`
+    /// (`X-Request-Id`, `X-Amzn-RequestId`, ... by default).
+    pub fn server_request_id(&self) -> Option<String> {
+        self.request_id_headers
+            .iter()
+            .find_map(|name| self.headers.get_first(name))
+            .map(str::to_string)
+    }
+    
+    /// Check that the response `Content-Type` is `expected`
+    ///
+    /// Only the media type is compared, case-insensitively, so parameters
+    /// such as `charset` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// A mismatch or a missing header fails with `Error::Parse` naming both
+    /// types, which catches servers that answer `200` with an HTML error
+    /// page.
+    pub fn ensure_content_type(&self, expected: &str) -> Result<(), crate::error::Error> {
+        let media_type =
+            |value: &str| value.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        let actual = self.headers.get_first("content-type").map(media_type);
+        if actual.as_deref() == Some(media_type(expected).as_str()) {
+            return Ok(());
+        }
+        Err(crate::error::Error::Parse {
+            message: format!(
+                "Unexpected Content-Type: expected {}, got {}",
+                expected,
+                actual.as_deref().unwrap_or("none")
+            ),
+            source: None,
+        })
+    }
+    
+    /// Parse the `Authorization` header, e.g. from a request echo endpoint
+    pub fn authorization(&self) -> Option<crate::auth::AuthScheme> {
+        self.headers
+            .get_first("authorization")
+            .and_then(crate::auth::parse_authorization)
+    }
+    
+    /// Parse the `ETag` header; `None` if it is missing or malformed
+    pub fn etag(&self) -> Option<ETag> {
+        self.headers.get_first("etag").and_then(ETag::parse)
+    }
+    
+    /// Detect the body's real media type from its leading magic bytes
+    ///
+    /// Ignores the declared `Content-Type`, so it can tell that an
+    /// `application/octet-stream` download is actually a PNG or PDF, e.g.
+    /// to pick a file extension. Returns `None` for empty or unrecognized
+    /// bodies. See [`sniff_content_type`].
+    pub fn sniff_content_type(&self) -> Option<&'static str> {
+        sniff_content_type(self.bytes()?)
+    }
+    
+    /// Get the raw `Location` header value
+    pub fn location(&self) -> Option<&str> {
+        self.headers.get_first("location")
+    }
+    
+    /// Get the URL of a newly created resource from a `201 Created`
+    ///
+    /// Resolves the `Location` header against the response URL, so relative
+    /// locations such as `/items/7` become absolute. Returns `None` for any
+    /// other status, or when the header is missing or not a valid URL.
+    pub fn created_location(&self) -> Option<String> {
+        if self.status != 201 {
+            return None;
+        }
+        let location = self.location()?;
+        reqwest::Url::parse(&self.url)
+            .and_then(|base| base.join(location))
+            .or_else(|_| reqwest::Url::parse(location))
+            .ok()
+            .map(String::from)
+    }
+    
+    /// Turn a non-2xx response into `Error::Http`
+    ///
+    /// For clients built with
+    /// [`ClientBuilder::error_on_status(false)`](crate::client::ClientBuilder::error_on_status),
+    /// which return every status as a response. The error carries the
+    /// status, a text body and the `Retry-After` wait, as the automatic
+    /// check does.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Http` if the status is not `2xx`.
+    pub fn error_for_status(self) -> Result<Self, crate::error::Error> {
+        self.error_for_status_at(crate::time::now())
+    }
+    
+    /// [`Response::error_for_status`] with `Retry-After` measured from `now`
+    pub(crate) fn error_for_status_at(self, now: std::time::SystemTime) -> Result<Self, crate::error::Error> {
+        if self.is_success() {
+            return Ok(self);
+        }
+        Err(crate::error::Error::Http {
+            status: self.status,
+            retry_after: self.retry_after_at(now),
+            body: self.text().map(str::to_string),
+            status_text: self.status_text,
+        })
+    }
+    
+    /// Whether the request created a new resource (`201 Created`)
+    pub fn was_created(&self) -> bool {
+        self.status == 201
+    }
+    
+    /// Parse `Server-Timing` metrics from all header instances
+    ///
+    /// Each metric carries its name plus the optional `dur` (milliseconds)
+    /// and `desc` parameters; unknown parameters are ignored. Returns an
+    /// empty vec when the header is absent.
+    pub fn server_timing(&self) -> Vec<ServerTiming> {
+        let Some(values) = self.headers.get("server-timing") else {
+            return Vec::new();
+        };
+        
+        values
+            .iter()
+            .flat_map(|value| split_unquoted(value, ','))
+            .filter_map(ServerTiming::parse)
+            .collect()
+    }
+    
+    /// Parse every relation in the `Link` headers (RFC 8288), keyed by `rel`
+    ///
+    /// Handles several `Link` headers and several links per header. Target
+    /// URLs are resolved against the response URL. A link whose `rel`
+    /// lists several relations (`rel="next last"`) appears under each; if a
+    /// relation occurs more than once, the first link wins. Returns an empty
+    /// map when the header is absent.
+    pub fn links(&self) -> HashMap<String, LinkRel> {
+        let mut links = HashMap::new();
+        let Some(values) = self.headers.get("link") else {
+            return links;
+        };
+        
+        let base = reqwest::Url::parse(&self.url).ok();
+        for value in values {
+            let mut rest = value.as_str();
+            while let Some(start) = rest.find('<') {
+                let Some(end) = rest[start..].find('>') else {
+                    break;
+                };
+                let target = &rest[start + 1..start + end];
+                rest = &rest[start + end + 1..];
+                // Parameters run up to the next comma outside quotes
+                let params = split_unquoted(rest, ',')[0];
+                rest = &rest[params.len()..];
+                
+                let url = base
+                    .as_ref()
+                    .and_then(|base| base.join(target).ok())
+                    .map_or_else(|| target.to_string(), String::from);
+                let mut link = LinkRel {
+                    url,
+                    params: HashMap::new(),
+                };
+                let mut rels = String::new();
+                for param in split_unquoted(params, ';').into_iter().skip(1) {
+                    let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                    let key = key.trim().to_lowercase();
+                    let value = unquote(value.trim());
+                    if key == "rel" {
+                        rels = value;
+                    } else if !key.is_empty() {
+                        link.params.entry(key).or_insert(value);
+                    }
+                }
+                for rel in rels.split_whitespace() {
+                    links.entry(rel.to_lowercase()).or_insert_with(|| link.clone());
+                }
+            }
+        }
+        links
+    }
+    
+    /// Split a `multipart/*` body into its parts
+    ///
+    /// Uses the `boundary` parameter of the `Content-Type` header. The
+    /// preamble before the first boundary and the epilogue after the closing
+    /// boundary are discarded.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::Parse` if the response is not multipart or the body
+    /// is malformed.
+    pub fn multipart_parts(&self) -> Result<Vec<Part>, crate::error::Error> {
+        let malformed = |message: &str| crate::error::Error::Parse {
+            message: message.to_string(),
+            source: None,
+        };
+        
+        let content_type = self.headers.get_first("content-type").unwrap_or("");
+        if !content_type.trim_start().to_lowercase().starts_with("multipart/") {
+            return Err(malformed("Response is not multipart"));
+        }
+        let boundary = content_type
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+            .map(|(_, value)| value.trim().trim_matches('"'))
+            .filter(|boundary| !boundary.is_empty())
+            .ok_or_else(|| malformed("Multipart Content-Type has no boundary"))?;
+        let body = self.bytes().unwrap_or_default();
+        
+        let delimiter = format!("--{boundary}").into_bytes();
+        let next_delimiter = [b"\r\n".as_slice(), &delimiter].concat();
+        
+        // The first delimiter may start the body or follow the preamble
+        let mut pos = if body.starts_with(&delimiter) {
+            delimiter.len()
+        } else {
+            find_bytes(body, &next_delimiter, 0)
+                .ok_or_else(|| malformed("Multipart body has no opening boundary"))?
+                + next_delimiter.len()
+        };
+        
+        let mut parts = Vec::new();
+        loop {
+            if body[pos..].starts_with(b"--") {
+                return Ok(parts);
+            }
+            // Skip transport padding after the delimiter
+            while matches!(body.get(pos), Some(b' ' | b'\t')) {
+                pos += 1;
+            }
+            if !body[pos..].starts_with(b"\r\n") {
+                return Err(malformed("Multipart boundary is not followed by CRLF"));
+            }
+            pos += 2;
+            
+            let end = find_bytes(body, &next_delimiter, pos)
+                .ok_or_else(|| malformed("Multipart body has no closing boundary"))?;
+            parts.push(Part::parse(&body[pos..end])?);
+            pos = end + next_delimiter.len();
+        }
+    }
+}
+
+/// A single part of a multipart response body
+#[derive(Debug, Clone)]
+pub struct Part {
+    /// Part headers
+    pub headers: Headers,
+    /// Raw part content
+    pub body: Vec<u8>,
+}
+
+impl Part {
+    /// Parse a part's headers and content
+    fn parse(raw: &[u8]) -> Result<Self, crate::error::Error> {
+        let (head, body) = if raw.starts_with(b"\r\n") {
+            (&raw[..0], &raw[2..])
+        } else {
+            let split = find_bytes(raw, b"\r\n\r\n", 0).ok_or_else(|| crate::error::Error::Parse {
+                message: "Multipart part has no header terminator".to_string(),
+                source: None,
+            })?;
+            (&raw[..split], &raw[split + 4..])
+        };
+        
+        let mut headers = Headers::new();
+        for line in String::from_utf8_lossy(head).split("\r\n") {
+            let (name, value) = line.split_once(':').ok_or_else(|| crate::error::Error::Parse {
+                message: format!("Invalid multipart header line: {line}"),
+                source: None,
+            })?;
+            headers.insert(name.trim(), value.trim());
+        }
+        
+        Ok(Self {
+            headers,
+            body: body.to_vec(),
+        })
+    }
+    
+    /// Get the part's `Content-Type` header
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get_first("content-type")
+    }
+}
+
+/// An entity tag from an `ETag` header (RFC 7232 section 2.3)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// Parse an entity tag such as `"abc"` or `W/"abc"`
+    ///
+    /// Returns `None` unless the value is a quoted opaque tag, optionally
+    /// prefixed with `W/`, whose characters are allowed by RFC 7232.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        let tag = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        let valid = tag.bytes().all(|b| b == 0x21 || (0x23..=0x7E).contains(&b) || b >= 0x80);
+        valid.then(|| Self {
+            tag: tag.to_string(),
+            weak,
+        })
+    }
+    
+    /// Create a strong entity tag from its opaque value (without quotes)
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self {
+            tag: tag.into(),
+            weak: false,
+        }
+    }
+    
+    /// Create a weak entity tag from its opaque value (without quotes)
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self {
+            tag: tag.into(),
+            weak: true,
+        }
+    }
+    
+    /// The opaque tag, without quotes or weakness prefix
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    
+    /// Whether this is a weak validator (`W/` prefix)
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+    
+    /// Strong comparison: both tags are strong and their values are equal
+    ///
+    /// Required for `If-Match` and range requests.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+    
+    /// Weak comparison: the values are equal, whether or not either is weak
+    ///
+    /// Used for `If-None-Match` cache validation.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl std::fmt::Display for ETag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+/// A single metric from a `Server-Timing` header
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerTiming {
+    /// Metric name
+    pub name: String,
+    /// Duration in milliseconds (`dur` parameter)
+    pub duration: Option<f64>,
+    /// Human-readable description (`desc` parameter)
+    pub description: Option<String>,
+}
+
+impl ServerTiming {
+    /// Parse one metric such as `db;dur=53.2;desc="Database"`
+    fn parse(metric: &str) -> Option<Self> {
+        let mut params = split_unquoted(metric, ';').into_iter();
+        let name = params.next()?.trim();
+        if name.is_empty() {
+            return None;
+        }
+        
+        let mut timing = Self {
+            name: name.to_string(),
+            duration: None,
+            description: None,
+        };
+        for param in params {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = unquote(value.trim());
+            match key.trim().to_lowercase().as_str() {
+                "dur" if timing.duration.is_none() => timing.duration = value.parse().ok(),
+                "desc" if timing.description.is_none() => timing.description = Some(value),
+                _ => {}
+            }
+        }
+        Some(timing)
+    }
+}
+
+/// One link from a `Link` header, as returned by [`Response::links`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRel {
+    /// Target URL, resolved against the response URL
+    pub url: String,
+    /// Target attributes other than `rel`, such as `title` or `type`,
+    /// keyed by lowercase name with quotes removed
+    pub params: HashMap<String, String>,
+}
+
+/// Known signatures: byte offset, magic bytes, media type
+const MAGIC_NUMBERS: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"PK\x05\x06", "application/zip"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"BZh", "application/x-bzip2"),
+    (0, b"\0asm", "application/wasm"),
+    (4, b"ftypisom", "video/mp4"),
+    (0, b"OggS", "audio/ogg"),
+];
+
+/// Detect a media type from the leading magic bytes of `bytes`
+///
+/// Recognizes common image, document, archive and media formats (PNG,
+/// JPEG, GIF, WebP, PDF, gzip, zip, 7z, bzip2, WebAssembly, MP4, Ogg).
+/// Returns `None` when nothing matches.
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    MAGIC_NUMBERS
+        .iter()
+        .find(|(offset, magic, _)| {
+            // WebP is the only offset-8 signature and sits in a RIFF container
+            bytes.get(*offset..*offset + magic.len()) == Some(*magic)
+                && (*offset != 8 || bytes.starts_with(b"RIFF"))
+        })
+        .map(|(_, _, media_type)| *media_type)
+}
+
+/// Split on `delimiter` outside of double-quoted strings
+fn split_unquoted(input: &str, delimiter: char) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => {
+                pieces.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    pieces.push(&input[start..]);
+    pieces
+}
+
+/// Strip surrounding quotes and backslash escapes from a header parameter value
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                out.push(if c == '\\' { chars.next().unwrap_or(c) } else { c });
+            }
+            out
+        }
+        None => value.to_string(),
+    }
+}
+
+/// Find the first occurrence of `needle` in `haystack` at or after `from`
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| i + from)
+}
+
+/// Result of a create-or-update request; see [`crate::client::Client::put_upsert`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upserted<T> {
+    /// The response body, or `None` when the server sent none (e.g. `204`)
+    pub value: Option<T>,
+    /// `true` if the resource was created (`201`), `false` if it already
+    /// existed and was updated
+    pub created: bool,
+}
+
+/// Outcome of a single attempt within a retry sequence
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct AttemptRecord {
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// Request method
+    pub method: Method,
+    /// URL the attempt was sent to, before query parameters added with
+    /// `RequestBuilder::query` are appended
+    pub url: String,
+    /// HTTP status received, if the server answered
+    pub status: Option<u16>,
+    /// Error kind (see [`crate::error::Error::kind`]) if the attempt failed
+    pub error: Option<&'static str>,
+    /// Time spent on this attempt, excluding backoff delays
+    pub elapsed: Duration,
+    /// Tag set with `RequestBuilder::tag`, for grouping metrics by widget or
+    /// feature
+    pub tag: Option<String>,
+}
+
+/// Browser fetch priority hint (the `priority` option of `RequestInit`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchPriority {
+    /// Schedule ahead of other requests of the same type
+    High,
+    /// Schedule behind other requests of the same type
+    Low,
+    /// Let the browser decide
+    #[default]
+    Auto,
+}
+
+impl FetchPriority {
+    /// The value used by the Fetch API (`"high"`, `"low"`, `"auto"`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FetchPriority::High => "high",
+            FetchPriority::Low => "low",
+            FetchPriority::Auto => "auto",
+        }
+    }
+    
+    /// Set this hint as the `priority` option of a fetch `RequestInit`
+    ///
+    /// Set through `Reflect` because `web-sys` only exposes the setter
+    /// behind its unstable APIs.
+    #[cfg(target_arch = "wasm32")]
+    pub fn apply(&self, init: &web_sys::RequestInit) -> Result<(), JsValue> {
+        js_sys::Reflect::set(init, &"priority".into(), &self.as_str().into()).map(|_| ())
+    }
+}
+
+/// Which end of an error body to keep when it is truncated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorBodyEnd {
+    /// Keep the first bytes
+    Head,
+    /// Keep the last bytes, where stack traces and error summaries tend to be
+    #[default]
+    Tail,
+}
+
+/// How a client with several base URLs picks one per request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadBalancePolicy {
+    /// Cycle through the base URLs in order
+    #[default]
+    RoundRobin,
+    /// Pick a base URL uniformly at random
+    Random,
 }
 
 /// Retry configuration
@@ -314,6 +2050,17 @@ pub struct RetryConfig {
     pub retry_on_network_error: bool,
     /// Retry on specific status codes
     pub retry_on_status: Vec<u16>,
+    /// Retry when the response body contains any of these substrings, even
+    /// on a `2xx`
+    ///
+    /// For gateways that report transient failures in the body. Success
+    /// bodies are checked as text (JSON is re-serialized first) and error
+    /// bodies as captured in `Error::Http`; a matching success is returned
+    /// as-is when no retry follows it, on the last allowed attempt or when
+    /// the deadline leaves no room. Every body is scanned once per
+    /// substring, so keep the list short; when empty (the default) bodies
+    /// aren't inspected at all.
+    pub retry_on_body_contains: Vec<String>,
 }
 
 impl Default for RetryConfig {
@@ -326,10 +2073,41 @@ impl Default for RetryConfig {
             retry_on_timeout: true,
             retry_on_network_error: true,
             retry_on_status: vec![408, 429, 500, 502, 503, 504],
+            retry_on_body_contains: Vec::new(),
         }
     }
 }
 
+impl RetryConfig {
+    /// Whether this policy allows retrying after `error`
+    ///
+    /// Timeouts and network errors are retried when `retry_on_timeout` and
+    /// `retry_on_network_error` are set, HTTP errors when their status is in
+    /// `retry_on_status` or their body contains one of
+    /// `retry_on_body_contains`. Everything else is final. Unlike
+    /// [`Error::is_retryable`](crate::error::Error::is_retryable), which
+    /// applies fixed defaults, this honors the configured fields.
+    pub fn should_retry(&self, error: &crate::error::Error) -> bool {
+        use crate::error::Error;
+        match error {
+            Error::Timeout { .. } => self.retry_on_timeout,
+            Error::Network { .. } => self.retry_on_network_error,
+            Error::Http { status, body, .. } => {
+                self.retry_on_status.contains(status)
+                    || body.as_deref().is_some_and(|body| self.body_requests_retry(body))
+            }
+            _ => false,
+        }
+    }
+    
+    /// Whether `body` contains one of `retry_on_body_contains`
+    pub(crate) fn body_requests_retry(&self, body: &str) -> bool {
+        self.retry_on_body_contains
+            .iter()
+            .any(|needle| body.contains(needle.as_str()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -341,6 +2119,61 @@ mod tests {
         assert_eq!(Method::from_str("INVALID"), None);
     }
     
+    #[test]
+    fn test_custom_method() {
+        let purge = Method::custom("PURGE").unwrap();
+        assert!(matches!(purge, Method::Custom(custom) if custom.as_str() == "PURGE"));
+        assert_eq!(purge.to_string(), "PURGE");
+        assert_eq!(purge.to_reqwest().as_str(), "PURGE");
+        assert_eq!(Method::custom("GET").unwrap(), Method::Get);
+        assert!(Method::custom("").is_err());
+        assert!(Method::custom("BAD METHOD").is_err());
+        assert!(Method::custom("PROP\nFIND").is_err());
+        assert!(Method::custom("X".repeat(CustomMethod::MAX_LEN)).is_ok());
+        assert!(Method::custom("X".repeat(CustomMethod::MAX_LEN + 1)).is_err());
+        
+        assert_eq!(serde_json::to_string(&purge).unwrap(), "\"PURGE\"");
+        let parsed: Method = serde_json::from_str("\"PROPFIND\"").unwrap();
+        assert_eq!(parsed, Method::custom("PROPFIND").unwrap());
+        assert_eq!(serde_json::from_str::<Method>("\"delete\"").unwrap(), Method::Delete);
+        assert!(serde_json::from_str::<Method>("\"A B\"").is_err());
+    }
+    
+    #[test]
+    fn test_response_format_round_trip() {
+        for (name, format) in [
+            ("auto", ResponseFormat::Auto),
+            ("json", ResponseFormat::Json),
+            ("text", ResponseFormat::Text),
+            ("binary", ResponseFormat::Binary),
+        ] {
+            assert_eq!(name.parse::<ResponseFormat>().unwrap(), format);
+            assert_eq!(format.to_string(), name);
+            assert_eq!(serde_json::to_value(format).unwrap(), serde_json::json!(name));
+            let parsed: ResponseFormat = serde_json::from_value(serde_json::json!(name)).unwrap();
+            assert_eq!(parsed, format);
+        }
+        
+        assert_eq!("JSON".parse::<ResponseFormat>().unwrap(), ResponseFormat::Json);
+        assert!(matches!(
+            "xml".parse::<ResponseFormat>(),
+            Err(crate::error::Error::InvalidInput { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_headers_with_capacity() {
+        let mut headers = Headers::with_capacity(64);
+        for i in 0..64 {
+            headers.insert(format!("X-Header-{i}"), i.to_string());
+        }
+        headers.reserve(16);
+        
+        assert_eq!(headers.iter().count(), 64);
+        assert_eq!(headers.get_first("x-header-0"), Some("0"));
+        assert_eq!(headers.get_first("x-header-63"), Some("63"));
+    }
+    
     #[test]
     fn test_headers_operations() {
         let mut headers = Headers::new();
@@ -358,11 +2191,8 @@ mod tests {
     #[test]
     fn test_response_status_checks() {
         let response = Response {
-            status: 200,
-            status_text: "OK".to_string(),
-            headers: Headers::new(),
-            body: ResponseBody::Empty,
             url: "https://example.com".to_string(),
+            ..Response::default()
         };
         
         assert!(response.is_success());
@@ -370,4 +2200,531 @@ mod tests {
         assert!(!response.is_client_error());
         assert!(!response.is_server_error());
     }
+    
+    #[test]
+    fn test_patch_op_serialization() {
+        let ops = vec![
+            PatchOp::Test { path: "/version".to_string(), value: serde_json::json!(3) },
+            PatchOp::Replace { path: "/name".to_string(), value: serde_json::json!("new") },
+            PatchOp::Move { from: "/old".to_string(), path: "/new".to_string() },
+            PatchOp::Remove { path: "/tmp".to_string() },
+        ];
+        
+        assert_eq!(
+            serde_json::to_value(&ops).unwrap(),
+            serde_json::json!([
+                {"op": "test", "path": "/version", "value": 3},
+                {"op": "replace", "path": "/name", "value": "new"},
+                {"op": "move", "from": "/old", "path": "/new"},
+                {"op": "remove", "path": "/tmp"}
+            ])
+        );
+    }
+    
+    fn multipart_response(body: &str) -> Response {
+        let mut headers = Headers::new();
+        headers.insert("Content-Type", "multipart/mixed; boundary=\"frontier\"");
+        Response {
+            headers,
+            body: ResponseBody::Binary(body.as_bytes().to_vec()),
+            url: "https://example.com".to_string(),
+            ..Response::default()
+        }
+    }
+    
+    #[test]
+    fn test_multipart_parts() {
+        let body = "This preamble is ignored\r\n\
+            --frontier\r\n\
+            Content-Type: application/json\r\n\
+            \r\n\
+            {\"name\": \"blob.bin\"}\r\n\
+            --frontier\r\n\
+            Content-Type: application/octet-stream\r\n\
+            Content-Id: <blob>\r\n\
+            \r\n\
+            \x01\x02\x03\r\n\
+            --frontier--\r\n\
+            epilogue";
+        
+        let parts = multipart_response(body).multipart_parts().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].content_type(), Some("application/json"));
+        assert_eq!(parts[0].body, b"{\"name\": \"blob.bin\"}");
+        assert_eq!(parts[1].content_type(), Some("application/octet-stream"));
+        assert_eq!(parts[1].headers.get_first("content-id"), Some("<blob>"));
+        assert_eq!(parts[1].body, [1, 2, 3]);
+    }
+    
+    #[test]
+    fn test_multipart_missing_closing_boundary() {
+        let body = "--frontier\r\nContent-Type: text/plain\r\n\r\ntruncated";
+        let result = multipart_response(body).multipart_parts();
+        assert!(matches!(result, Err(crate::error::Error::Parse { .. })));
+    }
+    
+    #[test]
+    fn test_server_timing() {
+        let mut response = multipart_response("");
+        response.headers.insert(
+            "Server-Timing",
+            r#"db;dur=53.2, app;dur=47.2;desc="Render, then \"flush\"""#,
+        );
+        response.headers.insert("Server-Timing", "cache;desc=hit, miss");
+        
+        let timings = response.server_timing();
+        assert_eq!(timings.len(), 4);
+        assert_eq!(timings[0].name, "db");
+        assert_eq!(timings[0].duration, Some(53.2));
+        assert_eq!(timings[0].description, None);
+        assert_eq!(timings[1].name, "app");
+        assert_eq!(timings[1].duration, Some(47.2));
+        assert_eq!(timings[1].description.as_deref(), Some("Render, then \"flush\""));
+        assert_eq!(timings[2].description.as_deref(), Some("hit"));
+        assert_eq!(
+            timings[3],
+            ServerTiming {
+                name: "miss".to_string(),
+                duration: None,
+                description: None,
+            }
+        );
+        
+        response.headers.remove("server-timing");
+        assert!(response.server_timing().is_empty());
+    }
+    
+    #[test]
+    fn test_form_encoding_modes() {
+        let mut fields = serde_json::Map::new();
+        fields.insert("q".to_string(), serde_json::json!("rust"));
+        fields.insert("tag".to_string(), serde_json::json!(["a", "b"]));
+        let body = Body::FormFields(fields);
+        
+        let encode = |encoding| String::from_utf8(body.encode(encoding).unwrap()).unwrap();
+        assert_eq!(encode(FormEncoding::Repeated), "q=rust&tag=a&tag=b");
+        assert_eq!(encode(FormEncoding::Brackets), "q=rust&tag%5B%5D=a&tag%5B%5D=b");
+        assert_eq!(
+            encode(FormEncoding::IndexedBrackets),
+            "q=rust&tag%5B0%5D=a&tag%5B1%5D=b"
+        );
+        assert_eq!(body.to_bytes().unwrap(), body.encode(FormEncoding::Repeated).unwrap());
+    }
+    
+    #[test]
+    fn test_server_request_id_fallback() {
+        let mut response = Response::default();
+        assert_eq!(response.server_request_id(), None);
+        
+        response.headers.insert("X-Amzn-RequestId", "amzn-123");
+        assert_eq!(response.server_request_id().as_deref(), Some("amzn-123"));
+        
+        response.headers.insert("X-Request-Id", "req-456");
+        assert_eq!(response.server_request_id().as_deref(), Some("req-456"));
+    }
+    
+    #[test]
+    fn test_response_authorization() {
+        let mut response = Response::default();
+        assert_eq!(response.authorization(), None);
+        
+        response.headers.insert("Authorization", "Bearer token-123");
+        assert_eq!(
+            response.authorization(),
+            Some(crate::auth::AuthScheme::Bearer {
+                token: "token-123".to_string(),
+            })
+        );
+    }
+    
+    #[test]
+    fn test_request_config_serde_round_trip() {
+        let mut config = RequestConfig {
+            method: Method::Post,
+            body: Some(Body::Json(serde_json::json!({"job": 7}))),
+            success_statuses: vec![304..=304],
+            body_factory: Some(BodyFactory::new(|| Body::Text("x".to_string()))),
+            ..RequestConfig::default()
+        };
+        config.headers.insert("X-Job", "7");
+        
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: RequestConfig = serde_json::from_str(&json).unwrap();
+        
+        assert_eq!(restored.method, Method::Post);
+        assert_eq!(restored.headers.get_first("x-job"), Some("7"));
+        assert!(matches!(restored.body, Some(Body::Json(ref v)) if v["job"] == 7));
+        assert_eq!(restored.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(restored.success_statuses, vec![304..=304]);
+        assert!(restored.body_factory.is_none());
+        
+        let sparse: RequestConfig =
+            serde_json::from_str(r#"{"method":"DELETE","headers":{"Accept":["text/plain"]}}"#)
+                .unwrap();
+        assert_eq!(sparse.method, Method::Delete);
+        assert_eq!(sparse.headers.get_first("accept"), Some("text/plain"));
+        assert_eq!(sparse.max_redirects, 10);
+    }
+    
+    #[test]
+    fn test_ensure_content_type() {
+        let mut response = Response::default();
+        response.headers.insert("Content-Type", "Application/JSON; charset=utf-8");
+        assert!(response.ensure_content_type("application/json").is_ok());
+        
+        let error = response.ensure_content_type("text/csv").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parse error: Unexpected Content-Type: expected text/csv, got application/json"
+        );
+        
+        let error = Response::default().ensure_content_type("text/csv").unwrap_err();
+        assert!(error.to_string().contains("got none"));
+    }
+    
+    #[test]
+    fn test_write_json_pretty() {
+        let response = Response {
+            body: ResponseBody::Text(r#"{"name":"rust","tags":["a"]}"#.to_string()),
+            ..Response::default()
+        };
+        let mut out = Vec::new();
+        response.write_json_pretty(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\n  \"name\": \"rust\",\n  \"tags\": [\n    \"a\"\n  ]\n}"
+        );
+        
+        let html = Response {
+            body: ResponseBody::Text("<html></html>".to_string()),
+            ..Response::default()
+        };
+        let error = html.write_json_pretty(Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), "ParseError");
+    }
+    
+    #[test]
+    fn test_json_tagged() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Card {
+            last4: String,
+        }
+        
+        let response = Response {
+            body: ResponseBody::Json(serde_json::json!({"type": "card", "last4": "4242"})),
+            ..Response::default()
+        };
+        let card = response.json_tagged::<Card>("type", "card").unwrap();
+        assert_eq!(card, Some(Card { last4: "4242".to_string() }));
+        assert_eq!(response.json_tagged::<Card>("type", "transfer").unwrap(), None);
+        assert_eq!(response.json_tagged::<Card>("kind", "card").unwrap(), None);
+        
+        let text = Response {
+            body: ResponseBody::Text(r#"{"type":"card"}"#.to_string()),
+            ..Response::default()
+        };
+        let error = text.json_tagged::<Card>("type", "card").unwrap_err();
+        assert_eq!(error.kind(), "ParseError");
+    }
+    
+    #[test]
+    fn test_request_config_merge() {
+        let mut base = RequestConfig {
+            method: Method::Post,
+            body: Some(Body::Text("base".to_string())),
+            timeout: Some(Duration::from_secs(60)),
+            response_format: ResponseFormat::Json,
+            query: vec![("page".to_string(), "1".to_string()), ("q".to_string(), "a".to_string())],
+            ..RequestConfig::default()
+        };
+        base.headers.insert("Accept", "application/json");
+        base.headers.insert("X-Trace", "base");
+        
+        let mut overrides = RequestConfig {
+            timeout: Some(Duration::from_secs(5)),
+            query: vec![("page".to_string(), "2".to_string())],
+            query_replaced: vec!["page".to_string()],
+            tag: Some("search".to_string()),
+            follow_redirects: false,
+            ..RequestConfig::default()
+        };
+        overrides.headers.insert("X-Trace", "override");
+        overrides.headers.insert("X-Trace", "second");
+        
+        let merged = base.merge(&overrides);
+        assert_eq!(merged.method, Method::Post);
+        assert!(matches!(merged.body, Some(Body::Text(ref text)) if text == "base"));
+        assert_eq!(merged.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(merged.response_format, ResponseFormat::Json);
+        assert_eq!(merged.headers.get("accept"), Some(&vec!["application/json".to_string()]));
+        assert_eq!(
+            merged.headers.get("x-trace"),
+            Some(&vec!["override".to_string(), "second".to_string()])
+        );
+        assert_eq!(
+            merged.query,
+            vec![("q".to_string(), "a".to_string()), ("page".to_string(), "2".to_string())]
+        );
+        assert_eq!(merged.tag.as_deref(), Some("search"));
+        assert!(!merged.follow_redirects);
+        
+        let replaced = base.merge(&RequestConfig {
+            method: Method::Put,
+            body: Some(Body::Json(serde_json::json!({"v": 2}))),
+            ..RequestConfig::default()
+        });
+        assert_eq!(replaced.method, Method::Put);
+        assert!(matches!(replaced.body, Some(Body::Json(_))));
+        assert_eq!(replaced.timeout, Some(Duration::from_secs(60)));
+        
+        let factory = RequestConfig {
+            body_factory: Some(BodyFactory::new(|| Body::Text("factory".to_string()))),
+            ..RequestConfig::default()
+        };
+        let body_over_factory = factory.merge(&RequestConfig {
+            body: Some(Body::Text("override".to_string())),
+            ..RequestConfig::default()
+        });
+        assert!(body_over_factory.body_factory.is_none());
+        assert!(matches!(body_over_factory.body, Some(Body::Text(ref text)) if text == "override"));
+        
+        let factory_over_body = base.merge(&factory);
+        assert!(factory_over_body.body.is_none());
+        assert!(matches!(
+            factory_over_body.body_factory.map(|factory| factory.make()),
+            Some(Body::Text(ref text)) if text == "factory"
+        ));
+    }
+    
+    #[test]
+    fn test_fetch_priority_values() {
+        assert_eq!(FetchPriority::High.as_str(), "high");
+        assert_eq!(FetchPriority::default(), FetchPriority::Auto);
+        let parsed: FetchPriority = serde_json::from_str("\"low\"").unwrap();
+        assert_eq!(parsed, FetchPriority::Low);
+    }
+    
+    #[test]
+    fn test_created_location() {
+        let mut response = Response {
+            status: 201,
+            url: "https://api.example.com/v1/items".to_string(),
+            ..Response::default()
+        };
+        assert_eq!(response.created_location(), None);
+        
+        response.headers.insert("Location", "/v1/items/7");
+        assert_eq!(
+            response.created_location().as_deref(),
+            Some("https://api.example.com/v1/items/7")
+        );
+        
+        response.status = 200;
+        assert_eq!(response.created_location(), None);
+    }
+    
+    #[test]
+    fn test_etag_comparison() {
+        let strong = ETag::parse("\"abc\"").unwrap();
+        let weak = ETag::parse("W/\"abc\"").unwrap();
+        
+        assert!(!strong.is_weak());
+        assert!(weak.is_weak());
+        assert_eq!(weak.tag(), "abc");
+        assert_eq!(weak.to_string(), "W/\"abc\"");
+        
+        assert!(strong.strong_eq(&strong));
+        assert!(!strong.strong_eq(&weak));
+        assert!(!weak.strong_eq(&weak));
+        assert!(strong.weak_eq(&weak));
+        assert!(weak.weak_eq(&weak));
+        assert!(!strong.weak_eq(&ETag::strong("abd")));
+        
+        assert_eq!(ETag::parse("abc"), None);
+        assert_eq!(ETag::parse("\"a\"b\""), None);
+        
+        let mut response = Response::default();
+        response.headers.insert("ETag", "W/\"v2\"");
+        assert_eq!(response.etag(), Some(ETag::weak("v2")));
+    }
+    
+    #[test]
+    fn test_request_context_keyed_by_type() {
+        let mut context = RequestContext::default();
+        assert!(context.is_empty());
+        
+        context.insert(7u32);
+        context.insert("span-1".to_string());
+        let snapshot = context.clone();
+        context.insert(8u32);
+        
+        assert_eq!(context.get::<u32>(), Some(&8));
+        assert_eq!(context.get::<String>().map(String::as_str), Some("span-1"));
+        assert_eq!(context.get::<u64>(), None);
+        assert_eq!(snapshot.get::<u32>(), Some(&7));
+    }
+    
+    #[test]
+    fn test_sniff_content_type() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let pdf = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3";
+        assert_eq!(sniff_content_type(png), Some("image/png"));
+        assert_eq!(sniff_content_type(pdf), Some("application/pdf"));
+        assert_eq!(sniff_content_type(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_content_type(b"\0\0\0\0\0\0\0\0WEBP"), None);
+        assert_eq!(sniff_content_type(b"\x89PN"), None);
+        assert_eq!(sniff_content_type(b""), None);
+        
+        let mut response = Response {
+            body: ResponseBody::Binary(png.to_vec()),
+            ..Response::default()
+        };
+        response.headers.insert("Content-Type", "application/octet-stream");
+        assert_eq!(response.sniff_content_type(), Some("image/png"));
+        assert_eq!(Response::default().sniff_content_type(), None);
+    }
+
+    #[test]
+    fn test_response_retry_after() {
+        let mut response = Response::default();
+        assert_eq!(response.retry_after(), None);
+        response.headers.insert("Retry-After", "30");
+        assert_eq!(response.retry_after(), Some(Duration::from_secs(30)));
+        response.headers.set("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(response.retry_after(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_links() {
+        let mut response = Response {
+            url: "https://api.example.com/items?page=2".to_string(),
+            ..Response::default()
+        };
+        assert!(response.links().is_empty());
+        response.headers.insert(
+            "Link",
+            r#"</items?page=3>; rel="next", <https://api.example.com/items?page=1>; rel=prev; title="Page 1, the start""#,
+        );
+        response
+            .headers
+            .insert("Link", r#"<?page=9&tags=a,b>; rel="last end"; type="application/json""#);
+        
+        let links = response.links();
+        assert_eq!(links.len(), 4);
+        assert_eq!(links["next"].url, "https://api.example.com/items?page=3");
+        assert!(links["next"].params.is_empty());
+        assert_eq!(links["prev"].url, "https://api.example.com/items?page=1");
+        assert_eq!(links["prev"].params["title"], "Page 1, the start");
+        assert_eq!(links["last"].url, "https://api.example.com/items?page=9&tags=a,b");
+        assert_eq!(links["last"].params["type"], "application/json");
+        assert_eq!(links["end"], links["last"]);
+    }
+
+    #[test]
+    fn test_retry_config_should_retry() {
+        use crate::error::Error;
+        let http = |status| Error::Http {
+            status,
+            status_text: String::new(),
+            body: None,
+            retry_after: None,
+        };
+        let timeout = Error::Timeout { duration_ms: 10 };
+        let network = Error::Network {
+            message: "reset".to_string(),
+            source: None,
+        };
+        
+        let defaults = RetryConfig::default();
+        assert!(defaults.should_retry(&http(503)));
+        assert!(!defaults.should_retry(&http(501)));
+        assert!(defaults.should_retry(&timeout));
+        assert!(defaults.should_retry(&network));
+        assert!(!defaults.should_retry(&Error::Cancelled));
+        
+        let strict = RetryConfig {
+            retry_on_timeout: false,
+            retry_on_network_error: false,
+            retry_on_status: vec![502],
+            ..RetryConfig::default()
+        };
+        assert!(strict.should_retry(&http(502)));
+        assert!(!strict.should_retry(&http(500)));
+        assert!(!strict.should_retry(&timeout));
+        assert!(!strict.should_retry(&network));
+        
+        let by_body = RetryConfig {
+            retry_on_body_contains: vec!["please retry".to_string()],
+            ..RetryConfig::default()
+        };
+        let mut flagged = Error::http(400, Some("busy, please retry".to_string()));
+        assert!(by_body.should_retry(&flagged));
+        flagged = Error::http(400, Some("bad request".to_string()));
+        assert!(!by_body.should_retry(&flagged));
+    }
+
+    #[test]
+    fn test_headers_count() {
+        let mut headers = Headers::new();
+        assert_eq!(headers.count("content-length"), 0);
+        headers.insert("Content-Length", "5");
+        assert_eq!(headers.count("content-length"), 1);
+        headers.insert("content-length", "5");
+        assert_eq!(headers.count("CONTENT-LENGTH"), 2);
+        headers.set("Content-Length", "7");
+        assert_eq!(headers.count("content-length"), 1);
+    }
+
+    #[test]
+    fn test_response_cache_bytes_round_trip() {
+        let mut response = Response {
+            status: 201,
+            status_text: "Created".to_string(),
+            body: ResponseBody::Json(serde_json::json!({"id": 7, "tags": ["a", "b"]})),
+            url: "https://api.example.com/items/7".to_string(),
+            redirects: 1,
+            ..Response::default()
+        };
+        response.headers.insert("Content-Type", "application/json");
+        response.headers.insert("Set-Cookie", "a=1");
+        response.headers.insert("Set-Cookie", "b=2");
+        
+        let restored = Response::from_cache_bytes(&response.to_cache_bytes().unwrap()).unwrap();
+        assert_eq!(restored, response);
+        
+        let binary = Response {
+            body: ResponseBody::Binary(vec![0, 159, 255]),
+            ..Response::default()
+        };
+        let bytes = binary.to_cache_bytes().unwrap();
+        assert!(String::from_utf8_lossy(&bytes).contains(r#""body":{"binary":"AJ//"}"#));
+        assert_eq!(Response::from_cache_bytes(&bytes).unwrap(), binary);
+        
+        assert!(Response::from_cache_bytes(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_keep_alive_hint() {
+        let response = |value: &str| {
+            let mut response = Response::default();
+            response.headers.insert("Keep-Alive", value);
+            response
+        };
+        assert_eq!(
+            response("timeout=5, max=100").keep_alive(),
+            Some(KeepAlive {
+                timeout: Some(Duration::from_secs(5)),
+                max: Some(100),
+            })
+        );
+        assert_eq!(
+            response("Timeout=30").keep_alive(),
+            Some(KeepAlive {
+                timeout: Some(Duration::from_secs(30)),
+                max: None,
+            })
+        );
+        assert_eq!(response("timeout=soon").keep_alive(), None);
+        assert_eq!(Response::default().keep_alive(), None);
+    }
 }
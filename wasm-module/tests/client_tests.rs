@@ -1,5 +1,8 @@
 //! Comprehensive tests for the HTTP client module
 
+#[cfg(not(target_arch = "wasm32"))]
+mod support;
+
 #[cfg(test)]
 mod tests {
     use rust_fetch::client::{Client, ClientBuilder};
@@ -31,6 +34,18 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[test]
+    fn test_client_with_connect_and_read_timeouts() {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(2))
+            .timeout(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        let _request = client
+            .request(Method::Get, "https://example.com/stream")
+            .read_timeout(Duration::from_secs(10));
+    }
+
     #[test]
     fn test_client_with_base_url() {
         let client = Client::builder()
@@ -118,6 +133,7 @@ mod tests {
             status: 503,
             status_text: "Service Unavailable".to_string(),
             body: None,
+            retry_after: None,
         };
         assert_eq!(http_error_503.kind(), "HttpError");
         assert!(http_error_503.is_retryable());
@@ -127,6 +143,7 @@ mod tests {
             status: 400,
             status_text: "Bad Request".to_string(),
             body: Some("Invalid parameters".to_string()),
+            retry_after: None,
         };
         assert!(!http_error_400.is_retryable());
 
@@ -187,12 +204,60 @@ mod tests {
 
 #[cfg(all(test, target_arch = "wasm32"))]
 mod wasm_tests {
+    use wasm_bindgen::JsValue;
     use wasm_bindgen_test::*;
     use rust_fetch::client::WasmClient;
     use rust_fetch::http::{fetch_json, fetch_text, fetch_with_options};
 
     wasm_bindgen_test_configure!(run_in_browser);
 
+    #[wasm_bindgen_test]
+    async fn test_rejection_is_js_error() {
+        let client = WasmClient::new().unwrap();
+        let promise = client.request(
+            "NOT A METHOD".to_string(),
+            "https://example.com".to_string(),
+            JsValue::NULL,
+            JsValue::NULL,
+        );
+
+        let rejected = wasm_bindgen_futures::JsFuture::from(promise).await.unwrap_err();
+        assert!(rejected.is_instance_of::<js_sys::Error>());
+        let error: js_sys::Error = rejected.into();
+        assert_eq!(String::from(error.name()), "InvalidInputError");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_priority_applied_to_request_init() {
+        use rust_fetch::types::FetchPriority;
+
+        let init = web_sys::RequestInit::new();
+        FetchPriority::High.apply(&init).unwrap();
+
+        let priority = js_sys::Reflect::get(&init, &"priority".into()).unwrap();
+        assert_eq!(priority.as_string().as_deref(), Some("high"));
+    }
+
+    #[cfg(feature = "native-fetch")]
+    #[wasm_bindgen_test]
+    async fn test_native_fetch_get() {
+        use rust_fetch::client::Client;
+        use rust_fetch::types::ResponseBody;
+
+        // A data: URL needs no server but still goes through fetch
+        let client = Client::new().unwrap();
+        let response = client
+            .get(r#"data:application/json,{"ok":true}"#)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.headers.get_first("content-type"), Some("application/json"));
+        match response.body {
+            ResponseBody::Json(json) => assert_eq!(json["ok"], true),
+            other => panic!("expected a JSON body, got {:?}", other),
+        }
+    }
+
     #[wasm_bindgen_test]
     fn test_wasm_client_creation() {
         let client = WasmClient::new();
@@ -218,3 +283,2994 @@ mod wasm_tests {
         assert!(result.is_ok());
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod server_tests {
+    use super::support::{Reply, TestServer};
+    use rust_fetch::client::Client;
+    use rust_fetch::error::Error;
+    use rust_fetch::types::{
+        Headers, Method, PatchOp, Response, ResponseBody, ResponseFormat, RetryConfig,
+    };
+    use std::time::Duration;
+
+    fn fast_retries(max_retries: u32) -> RetryConfig {
+        RetryConfig {
+            max_retries,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..Default::default()
+        }
+    }
+
+    fn html_as_json_server() -> TestServer {
+        TestServer::start(|_| {
+            Reply::new(200)
+                .header("Content-Type", "application/json")
+                .body("<html>Internal error</html>")
+        })
+    }
+
+    #[tokio::test]
+    async fn test_strict_auto_rejects_invalid_json() {
+        let server = html_as_json_server();
+        let client = Client::builder().strict_auto(true).build().unwrap();
+
+        let result = client.get(server.url("/data")).await;
+        assert!(matches!(result, Err(Error::Parse { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_lenient_auto_degrades_to_text() {
+        let server = html_as_json_server();
+        let client = Client::new().unwrap();
+
+        let response = client.get(server.url("/data")).await.unwrap();
+        assert!(matches!(response.body, ResponseBody::Text(ref t) if t.contains("<html>")));
+    }
+
+    #[tokio::test]
+    async fn test_retries_exhausted_reports_attempts() {
+        let server = TestServer::start(|_| Reply::new(503));
+        let client = Client::builder()
+            .retry_config(fast_retries(2))
+            .build()
+            .unwrap();
+
+        let error = client.get(server.url("/flaky")).await.unwrap_err();
+        match error {
+            Error::RetriesExhausted { attempts, last } => {
+                assert_eq!(attempts, 3);
+                assert!(matches!(*last, Error::Http { status: 503, .. }));
+            }
+            other => panic!("expected RetriesExhausted, got {other:?}"),
+        }
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_after_retries_is_not_exhausted() {
+        let server = TestServer::sequence(vec![Reply::new(503), Reply::new(503), Reply::new(404)]);
+        let client = Client::builder()
+            .retry_config(fast_retries(3))
+            .build()
+            .unwrap();
+
+        let error = client.get(server.url("/gone")).await.unwrap_err();
+
+        assert!(matches!(error, Error::Http { status: 404, .. }), "{error:?}");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_stopping_retries_reports_exhausted() {
+        let server = TestServer::start(|_| Reply::new(503));
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                max_retries: 5,
+                initial_delay: Duration::from_millis(100),
+                max_delay: Duration::from_secs(1),
+                multiplier: 2.0,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        // Room for the 100ms wait before the first retry, not the 200ms one
+        // before the second
+        let error = client
+            .request(Method::Get, server.url("/flaky"))
+            .deadline(Duration::from_millis(250))
+            .send()
+            .await
+            .unwrap_err();
+
+        match error {
+            Error::RetriesExhausted { attempts, last } => {
+                assert_eq!(attempts, 2);
+                assert!(matches!(*last, Error::Http { status: 503, .. }));
+            }
+            other => panic!("expected RetriesExhausted, got {other:?}"),
+        }
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_retries_returns_plain_error() {
+        let server = TestServer::start(|_| Reply::new(503));
+        let client = Client::new().unwrap();
+
+        let error = client.get(server.url("/flaky")).await.unwrap_err();
+        assert!(matches!(error, Error::Http { status: 503, .. }));
+    }
+
+    fn redirect_response(server: &TestServer, status: u16) -> Response {
+        let mut headers = Headers::new();
+        headers.insert("Location", "/target");
+        Response {
+            status,
+            status_text: "Redirect".to_string(),
+            headers,
+            url: server.url("/start"),
+            ..Response::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follow_302_switches_to_get() {
+        let server = TestServer::start(|_| Reply::text("done"));
+        let client = Client::new().unwrap();
+        let original = client.post(server.url("/start")).text("payload");
+
+        let response = client
+            .follow(&redirect_response(&server, 302), &original)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/target");
+        assert!(requests[0].body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_follow_307_preserves_method_and_body() {
+        let server = TestServer::start(|_| Reply::text("done"));
+        let client = Client::new().unwrap();
+        let original = client.post(server.url("/start")).text("payload");
+
+        client
+            .follow(&redirect_response(&server, 307), &original)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/target");
+        assert_eq!(requests[0].body_text(), "payload");
+    }
+
+    #[tokio::test]
+    async fn test_preserve_header_case_on_the_wire() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let url = server.url("/");
+
+        let client = Client::builder().preserve_header_case(true).build().unwrap();
+        client.request(Method::Get, &url).header("X-Custom-Header", "1").send().await.unwrap();
+
+        let client = Client::new().unwrap();
+        client.request(Method::Get, &url).header("X-Custom-Header", "2").send().await.unwrap();
+
+        let requests = server.requests();
+        let has_name = |i: usize, name: &str| requests[i].headers.iter().any(|(k, _)| k == name);
+        assert!(has_name(0, "X-Custom-Header"));
+        assert!(has_name(1, "x-custom-header"));
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Account {
+        id: i64,
+    }
+
+    fn positive_id(account: &Account) -> Result<(), String> {
+        if account.id > 0 {
+            Ok(())
+        } else {
+            Err(format!("invalid account id {}", account.id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_validated_failure_is_parse_error() {
+        let server = TestServer::start(|_| Reply::json(r#"{"id": -1}"#));
+        let client = Client::new().unwrap();
+
+        let error = client
+            .request(Method::Get, server.url("/account"))
+            .send_validated(positive_id)
+            .await
+            .unwrap_err();
+
+        match error {
+            Error::Parse { message, .. } => assert_eq!(message, "invalid account id -1"),
+            other => panic!("expected Parse, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_validated_retries_when_enabled() {
+        let server = TestServer::sequence(vec![
+            Reply::json(r#"{"id": 0}"#),
+            Reply::json(r#"{"id": 7}"#),
+        ]);
+        let client = Client::builder()
+            .retry_config(fast_retries(2))
+            .build()
+            .unwrap();
+
+        let account: Account = client
+            .request(Method::Get, server.url("/account"))
+            .retry_on_invalid(true)
+            .send_validated(positive_id)
+            .await
+            .unwrap();
+
+        assert_eq!(account.id, 7);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_fires_on_slow_body() {
+        let server = TestServer::start(|_| {
+            Reply::new(200)
+                .header("Content-Type", "text/plain")
+                .chunked(vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()])
+                .chunk_delay(Duration::from_millis(300))
+        });
+        let client = Client::new().unwrap();
+
+        let error = client
+            .request(Method::Get, server.url("/poll"))
+            .read_timeout(Duration::from_millis(100))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Timeout { duration_ms: 100 }));
+    }
+
+    fn content_types(request: &super::support::RecordedRequest) -> Vec<&str> {
+        request
+            .headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_merge_patch_content_type() {
+        let server = TestServer::start(|_| Reply::json("{}"));
+        let client = Client::new().unwrap();
+
+        client
+            .patch(server.url("/users/1"))
+            .merge_patch(&serde_json::json!({"email": null, "name": "Ada"}))
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(content_types(request), ["application/merge-patch+json"]);
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body, serde_json::json!({"email": null, "name": "Ada"}));
+    }
+
+    #[tokio::test]
+    async fn test_json_patch_document() {
+        let server = TestServer::start(|_| Reply::json("{}"));
+        let client = Client::new().unwrap();
+
+        client
+            .patch(server.url("/users/1"))
+            .json_patch(&[
+                PatchOp::Add { path: "/tags/-".to_string(), value: serde_json::json!("admin") },
+                PatchOp::Copy { from: "/name".to_string(), path: "/display_name".to_string() },
+            ])
+            .send()
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(content_types(request), ["application/json-patch+json"]);
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!([
+                {"op": "add", "path": "/tags/-", "value": "admin"},
+                {"op": "copy", "from": "/name", "path": "/display_name"}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefer_parses_json_and_sends_weighted_accept() {
+        let server = TestServer::start(|_| Reply::json(r#"{"ok": true}"#));
+        let client = Client::new().unwrap();
+
+        let response = client
+            .request(Method::Get, server.url("/"))
+            .prefer(&[ResponseFormat::Json, ResponseFormat::Text])
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.json(), Some(&serde_json::json!({"ok": true})));
+        assert_eq!(
+            server.requests()[0].header("accept"),
+            Some("application/json, text/*;q=0.9")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefer_falls_back_for_unlisted_type() {
+        let server = TestServer::start(|_| {
+            Reply::new(200)
+                .header("Content-Type", "application/xml")
+                .body("<ok/>")
+        });
+        let client = Client::new().unwrap();
+
+        let response = client
+            .request(Method::Get, server.url("/"))
+            .prefer(&[ResponseFormat::Json])
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.text(), Some("<ok/>"));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_chaos_full_failure_rate() {
+        use rust_fetch::chaos::ChaosConfig;
+
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .chaos(ChaosConfig::new().fail(1.0, || Error::Network {
+                message: "injected".to_string(),
+                source: None,
+            }))
+            .build()
+            .unwrap();
+
+        for _ in 0..5 {
+            let error = client.get(server.url("/")).await.unwrap_err();
+            assert!(matches!(error, Error::Network { .. }));
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_mock_client_retries_without_network() {
+        use rust_fetch::mock::{self, MockClient};
+
+        let mock = MockClient::new();
+        mock.on(Method::Get, "/status", mock::response(503, ResponseBody::Empty))
+            .on(
+                Method::Get,
+                "/status",
+                mock::response(200, ResponseBody::Json(serde_json::json!({"up": true}))),
+            );
+        let client = Client::builder()
+            .base_url("https://api.example.com")
+            .retry_config(fast_retries(2))
+            .mock(&mock)
+            .build()
+            .unwrap();
+
+        let response = client.get("/status").await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.json(), Some(&serde_json::json!({"up": true})));
+        assert_eq!(response.url, "https://api.example.com/status");
+        let requests = mock.requests();
+        assert_eq!(
+            requests.iter().map(|r| (r.method, r.url.as_str())).collect::<Vec<_>>(),
+            vec![
+                (Method::Get, "https://api.example.com/status"),
+                (Method::Get, "https://api.example.com/status"),
+            ]
+        );
+
+        let error = client.post("/missing").send().await.unwrap_err();
+        assert!(error.to_string().contains("No mock response for POST https://api.example.com/missing"));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_mock_client_answers_streaming_requests() {
+        use futures_util::StreamExt;
+        use rust_fetch::mock::{self, MockClient};
+
+        let mock = MockClient::new();
+        mock.on(Method::Get, "/export", mock::response(200, ResponseBody::Text("a\nb\n".to_string())))
+            .on(Method::Head, "/", mock::response(204, ResponseBody::Empty));
+        // Nothing listens here, so any request that escapes the mock fails
+        let client = Client::builder()
+            .base_url("http://127.0.0.1:9")
+            .mock(&mock)
+            .build()
+            .unwrap();
+
+        let stream = client.request(Method::Get, "/export").send_stream().await.unwrap();
+        assert_eq!(stream.status, 200);
+        assert_eq!(stream.url, "http://127.0.0.1:9/export");
+        let chunks: Vec<_> = stream.map(|chunk| chunk.unwrap()).collect().await;
+        assert_eq!(chunks.concat(), b"a\nb\n");
+
+        let lines = client.request(Method::Get, "/export").send_lines().await.unwrap();
+        let lines: Vec<_> = lines.map(|line| line.unwrap()).collect().await;
+        assert_eq!(lines, vec!["a", "b"]);
+
+        client.warm_up("/").await.unwrap();
+        assert_eq!(mock.requests().len(), 3);
+        assert_eq!(mock.requests()[2].method, Method::Head);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_request_metrics_count_retries() {
+        use rust_fetch::mock::{self, MockClient};
+
+        let mock = MockClient::new();
+        mock.on(Method::Get, "/flaky", mock::response(503, ResponseBody::Empty))
+            .on(Method::Get, "/flaky", mock::response(200, ResponseBody::Empty));
+        let client = Client::builder()
+            .base_url("https://api.example.com")
+            .retry_config(fast_retries(3))
+            .request_metrics(true)
+            .mock(&mock)
+            .build()
+            .unwrap();
+
+        let metrics = client.get("/flaky").await.unwrap().metrics.expect("metrics enabled");
+
+        assert_eq!(metrics.attempts, 2);
+        assert!(metrics.total > Duration::ZERO);
+        assert!(metrics.total >= metrics.last_attempt);
+
+        let client = Client::builder().mock(&mock).build().unwrap();
+        assert!(client.get("https://api.example.com/flaky").await.unwrap().metrics.is_none());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_chaos_latency_injection() {
+        use rust_fetch::chaos::ChaosConfig;
+
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .chaos(ChaosConfig::new().latency(Duration::from_millis(150)))
+            .build()
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        client.get(server.url("/")).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_send_with_attempts_records_retries() {
+        let server = TestServer::sequence(vec![
+            Reply::new(503),
+            Reply::new(500),
+            Reply::json(r#"{"ok": true}"#),
+        ]);
+        let client = Client::builder()
+            .retry_config(fast_retries(3))
+            .build()
+            .unwrap();
+
+        let (response, attempts) = client
+            .request(Method::Get, server.url("/flaky"))
+            .send_with_attempts()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(
+            attempts.iter().map(|a| a.attempt).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(attempts[0].status, Some(503));
+        assert_eq!(attempts[0].error, Some("HttpError"));
+        assert_eq!(attempts[1].status, Some(500));
+        assert_eq!(attempts[2].status, Some(200));
+        assert_eq!(attempts[2].error, None);
+    }
+
+    #[tokio::test]
+    async fn test_send_with_attempts_keeps_records_on_failure() {
+        let server = TestServer::start(|_| Reply::new(503));
+        let client = Client::builder()
+            .retry_config(fast_retries(2))
+            .build()
+            .unwrap();
+
+        let failed = client
+            .request(Method::Get, server.url("/down"))
+            .send_with_attempts()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(failed.error, Error::RetriesExhausted { attempts: 3, .. }));
+        assert_eq!(failed.attempts.len(), 3);
+        assert!(failed
+            .attempts
+            .iter()
+            .all(|a| a.status == Some(503) && a.error == Some("HttpError")));
+        let error: Error = failed.into();
+        assert_eq!(error.kind(), "RetriesExhaustedError");
+    }
+
+    #[tokio::test]
+    async fn test_form_serde_with_brackets() {
+        #[derive(serde::Serialize)]
+        struct Search {
+            q: &'static str,
+            tag: Vec<&'static str>,
+            page: Option<u32>,
+        }
+
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::new().unwrap();
+        let search = Search {
+            q: "rust",
+            tag: vec!["a", "b"],
+            page: None,
+        };
+
+        client
+            .post(server.url("/search"))
+            .form_serde(&search)
+            .unwrap()
+            .form_encoding(rust_fetch::types::FormEncoding::Brackets)
+            .send()
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(request.body_text(), "q=rust&tag%5B%5D=a&tag%5B%5D=b");
+    }
+
+    #[tokio::test]
+    async fn test_default_base_url_for_convenience_functions() {
+        use rust_fetch::http::{fetch_text, set_default_base_url};
+
+        let server = TestServer::start(|request| Reply::text(&request.path));
+        set_default_base_url(server.url("/api/"));
+        let relative = fetch_text("/posts/1".to_string()).await;
+        let absolute = fetch_text(server.url("/direct")).await;
+        // Joined like `ClientBuilder::base_url`
+        set_default_base_url(server.url("/api"));
+        let query = fetch_text("posts?page=2".to_string()).await;
+        let colon = fetch_text("users:42".to_string()).await;
+        set_default_base_url(String::new());
+
+        assert_eq!(relative.unwrap(), "/api/posts/1");
+        assert_eq!(absolute.unwrap(), "/direct");
+        assert_eq!(query.unwrap(), "/api/posts?page=2");
+        assert_eq!(colon.unwrap(), "/api/users:42");
+    }
+
+    #[tokio::test]
+    async fn test_custom_server_request_id_headers() {
+        let server = TestServer::start(|_| {
+            Reply::text("ok")
+                .header("X-Request-Id", "generic")
+                .header("X-Trace", "trace-789")
+        });
+        let client = Client::builder()
+            .server_request_id_headers(vec!["X-Trace".to_string()])
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url("/")).await.unwrap();
+        assert_eq!(response.server_request_id().as_deref(), Some("trace-789"));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_quota_exceeded() {
+        let server = TestServer::start(|_| Reply::text(&"x".repeat(60)));
+        let client = Client::builder().transfer_quota(100).build().unwrap();
+
+        client
+            .post(server.url("/upload"))
+            .text("y".repeat(40))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(client.bytes_transferred(), 100);
+
+        let error = client.get(server.url("/download")).await.unwrap_err();
+        assert!(matches!(error, Error::QuotaExceeded { limit: 100, used: 100 }));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_lines_across_chunks() {
+        use futures_util::StreamExt;
+
+        let server = TestServer::start(|_| {
+            Reply::new(200)
+                .header("Content-Type", "text/plain; charset=utf-8")
+                .chunked(vec![
+                    b"first li".to_vec(),
+                    b"ne\r\nsecond\nthi".to_vec(),
+                    b"rd \xc3".to_vec(),
+                    b"\xa9\n\nlast".to_vec(),
+                ])
+                .chunk_delay(Duration::from_millis(10))
+        });
+        let client = Client::new().unwrap();
+
+        let lines: Vec<String> = client
+            .request(Method::Get, server.url("/logs"))
+            .send_lines()
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(lines, vec!["first line", "second", "third \u{e9}", "", "last"]);
+    }
+
+    #[tokio::test]
+    async fn test_send_stream_matches_buffered_body() {
+        use futures_util::StreamExt;
+
+        let chunks: Vec<Vec<u8>> = (0..4u8).map(|i| vec![b'a' + i; 1500]).collect();
+        let server = TestServer::start(move |_| {
+            Reply::new(200)
+                .header("Content-Type", "application/octet-stream")
+                .chunked(chunks.clone())
+                .chunk_delay(Duration::from_millis(5))
+        });
+        let client = Client::new().unwrap();
+
+        let mut stream = client
+            .request(Method::Get, server.url("/download"))
+            .send_stream()
+            .await
+            .unwrap();
+        assert_eq!(stream.status, 200);
+        assert_eq!(
+            stream.headers.get_first("content-type"),
+            Some("application/octet-stream")
+        );
+        let mut streamed = Vec::new();
+        let mut pieces = 0;
+        while let Some(chunk) = stream.next().await {
+            streamed.extend_from_slice(&chunk.unwrap());
+            pieces += 1;
+        }
+
+        let buffered = client.get(server.url("/download")).await.unwrap();
+        let rust_fetch::ResponseBody::Binary(buffered) = buffered.body else {
+            panic!("expected a binary body");
+        };
+        assert_eq!(streamed.len(), 6000);
+        assert_eq!(streamed, buffered);
+        assert!(pieces > 1, "body arrived in {} chunk(s)", pieces);
+    }
+
+    #[tokio::test]
+    async fn test_cross_host_redirect_strips_authorization() {
+        let other = TestServer::start(|_| Reply::text("elsewhere"));
+        let other_url = other.url("/landing");
+        let server = TestServer::start(move |request| match request.path.as_str() {
+            "/same" => Reply::new(302).header("Location", "/landing"),
+            "/away" => Reply::new(302).header("Location", &other_url),
+            _ => Reply::text("home"),
+        });
+        let client = Client::builder()
+            .default_header("Authorization", "Bearer secret")
+            .default_header("X-Trace", "abc")
+            .build()
+            .unwrap();
+
+        client.get(server.url("/same")).await.unwrap();
+        let same_host = &server.requests()[1];
+        assert_eq!(same_host.path, "/landing");
+        assert_eq!(same_host.header("authorization"), Some("Bearer secret"));
+
+        let response = client.get(server.url("/away")).await.unwrap();
+        assert_eq!(response.text(), Some("elsewhere"));
+        let cross_host = &other.requests()[0];
+        assert_eq!(cross_host.header("authorization"), None);
+        assert_eq!(cross_host.header("x-trace"), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_sensitive_headers() {
+        let other = TestServer::start(|_| Reply::text("elsewhere"));
+        let other_url = other.url("/landing");
+        let server =
+            TestServer::start(move |_| Reply::new(307).header("Location", &other_url));
+        let client = Client::builder()
+            .sensitive_headers(vec!["x-api-key".to_string()])
+            .default_header("X-Api-Key", "k")
+            .default_header("Authorization", "Bearer secret")
+            .build()
+            .unwrap();
+
+        client.get(server.url("/")).await.unwrap();
+        let request = &other.requests()[0];
+        assert_eq!(request.header("x-api-key"), None);
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+    }
+
+    #[tokio::test]
+    async fn test_module_post_json_round_trip() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Note {
+            id: u32,
+            title: String,
+        }
+
+        let server = TestServer::start(|request| Reply::json(&request.body_text()));
+        let note = Note {
+            id: 7,
+            title: "hello".to_string(),
+        };
+
+        let echoed: Note = rust_fetch::prelude::post_json(server.url("/echo"), &note)
+            .await
+            .unwrap();
+        assert_eq!(echoed, note);
+        assert_eq!(
+            server.requests()[0].header("content-type"),
+            Some("application/json")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_before_send_hook_runs_per_attempt() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let server = TestServer::sequence(vec![Reply::new(503), Reply::text("ok")]);
+        let calls = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let client = Client::builder()
+            .default_header("X-Default", "yes")
+            .retry_config(fast_retries(2))
+            .before_send(move |method, url, headers| {
+                assert_eq!(*method, Method::Post);
+                assert!(url.ends_with("/hooked"));
+                assert!(headers.contains("x-default"));
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                headers.set("X-Attempt", n.to_string());
+            })
+            .build()
+            .unwrap();
+
+        client
+            .post(server.url("/hooked"))
+            .text("body")
+            .send()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(requests[0].header("x-attempt"), Some("0"));
+        assert_eq!(requests[1].header("x-attempt"), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn test_expect_continue_accepted() {
+        let server = TestServer::start(|request| Reply::text(&request.body_text()));
+        let client = Client::new().unwrap();
+
+        let response = client
+            .put(server.url("/upload"))
+            .expect_continue(true)
+            .bytes(vec![b'x'; 1024])
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.text().map(str::len), Some(1024));
+        assert_eq!(server.requests()[0].header("expect"), Some("100-continue"));
+    }
+
+    #[tokio::test]
+    async fn test_expect_continue_rejected_early() {
+        let server = TestServer::rejecting_expect(401, |_| Reply::text("unreachable"));
+        let client = Client::new().unwrap();
+
+        let error = client
+            .put(server.url("/upload"))
+            .expect_continue(true)
+            .bytes(vec![b'x'; 1024])
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Http { status: 401, .. }));
+        assert!(server.requests()[0].body.is_empty());
+    }
+
+    /// Phases of a breakdown, which must add up to its total
+    fn sum_of_phases(timing: &rust_fetch::types::TimingBreakdown) -> Duration {
+        [timing.dns, timing.connect, timing.tls].into_iter().flatten().sum::<Duration>() + timing.wait + timing.download
+    }
+
+    #[tokio::test]
+    async fn test_timing_breakdown_phases_sum_to_total() {
+        let server = TestServer::start(|_| {
+            Reply::new(200)
+                .header("Content-Type", "text/plain")
+                .chunked(vec![b"slow".to_vec(), b" body".to_vec()])
+                .delay(Duration::from_millis(50))
+                .chunk_delay(Duration::from_millis(150))
+        });
+        let client = Client::builder().timing_breakdown(true).build().unwrap();
+        // A host name, so the lookup is timed
+        let url = server.url("/").replace("127.0.0.1", "localhost");
+
+        let started = std::time::Instant::now();
+        let response = client.get(&url).await.unwrap();
+        let elapsed = started.elapsed();
+        let timing = response.timing.expect("timing enabled");
+
+        // The server holds the head back 50ms, then each chunk 150ms
+        assert!(timing.dns.is_some(), "{:?}", timing);
+        assert_eq!((timing.connect, timing.tls), (None, None));
+        assert!(timing.wait >= Duration::from_millis(50), "wait {:?}", timing.wait);
+        assert!(timing.download >= Duration::from_millis(150), "download {:?}", timing.download);
+        assert!(timing.wait < timing.download, "{:?}", timing);
+        assert_eq!(sum_of_phases(&timing), timing.total, "{:?}", timing);
+        assert!(timing.total <= elapsed);
+
+        // The pooled connection is reused without a lookup
+        let reused = client.get(&url).await.unwrap().timing.unwrap();
+        assert_eq!((reused.dns, reused.connect, reused.tls), (None, None, None));
+        assert_eq!(sum_of_phases(&reused), reused.total, "{:?}", reused);
+
+        let plain = Client::new().unwrap().get(server.url("/")).await.unwrap();
+        assert!(plain.timing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_body_factory_called_per_attempt() {
+        use rust_fetch::types::Body;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let server = TestServer::sequence(vec![Reply::new(503), Reply::text("stored")]);
+        let client = Client::builder()
+            .retry_config(fast_retries(2))
+            .build()
+            .unwrap();
+        let calls = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        client
+            .put(server.url("/upload"))
+            .text("ignored")
+            .body_factory(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                Body::Text(format!("attempt {}", n))
+            })
+            .send()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(requests[0].body_text(), "attempt 0");
+        assert_eq!(requests[1].body_text(), "attempt 1");
+    }
+
+    #[tokio::test]
+    async fn test_success_statuses_accepts_304() {
+        let server = TestServer::start(|_| Reply::new(304).header("ETag", "\"v1\""));
+        let client = Client::new().unwrap();
+
+        let response = client
+            .request(Method::Get, server.url("/cached"))
+            .success_statuses(304..=304)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, 304);
+        assert_eq!(response.headers.get_first("etag"), Some("\"v1\""));
+
+        let error = client.get(server.url("/cached")).await.unwrap_err();
+        assert!(matches!(error, Error::Http { status: 304, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_replaces_final_error() {
+        use std::sync::{Arc, Mutex};
+
+        let server = TestServer::start(|_| Reply::new(503));
+        let client = Client::builder()
+            .retry_config(fast_retries(1))
+            .build()
+            .unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+
+        let response = client
+            .request(Method::Get, server.url("/feed"))
+            .fallback(move |error| {
+                log.lock().unwrap().push(error.kind());
+                Some(Response {
+                    body: ResponseBody::Text("stale".to_string()),
+                    ..Response::default()
+                })
+            })
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.text().unwrap(), "stale");
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(*seen.lock().unwrap(), vec!["RetriesExhaustedError"]);
+
+        let error = client
+            .request(Method::Get, server.url("/feed"))
+            .fallback(|_| None)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::RetriesExhausted { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_expect_json_reports_body_snippet() {
+        let page = format!("<html><body>Bad gateway{}</body></html>", " ".repeat(500));
+        let server = TestServer::start(move |_| {
+            Reply::new(200).header("Content-Type", "text/html").body(page.clone())
+        });
+        let client = Client::new().unwrap();
+
+        let error = client
+            .request(Method::Get, server.url("/api"))
+            .expect_json()
+            .send()
+            .await
+            .unwrap_err();
+
+        let message = error.to_string();
+        assert!(matches!(error, Error::Parse { .. }));
+        assert!(message.contains("text/html"), "{}", message);
+        assert!(message.contains("<html><body>Bad gateway"), "{}", message);
+        assert!(!message.contains("</html>"), "{}", message);
+
+        let lenient = client.get(server.url("/api")).await.unwrap();
+        assert!(lenient.text().unwrap().starts_with("<html>"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_config_replays_stored_spec() {
+        use rust_fetch::types::{Body, RequestConfig};
+
+        let server = TestServer::start(|request| Reply::text(&request.body_text()));
+        let client = Client::builder()
+            .base_url(server.url(""))
+            .default_header("X-Client", "queue")
+            .build()
+            .unwrap();
+
+        let mut config = RequestConfig {
+            method: Method::Put,
+            body: Some(Body::Text("payload".to_string())),
+            ..RequestConfig::default()
+        };
+        config.headers.insert("X-Job", "42");
+        let stored = serde_json::to_string(&config).unwrap();
+
+        let replayed: RequestConfig = serde_json::from_str(&stored).unwrap();
+        let response = client.execute_config("/jobs/42", replayed).await.unwrap();
+
+        assert_eq!(response.text(), Some("payload"));
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/jobs/42");
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.header("x-job"), Some("42"));
+        assert_eq!(request.header("x-client"), Some("queue"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_token_cached_until_expiry() {
+        use rust_fetch::auth::{parse_authorization, AuthScheme, OAuthConfig};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let issued = AtomicUsize::new(0);
+        let server = TestServer::start(move |request| {
+            if request.path == "/token" {
+                let n = issued.fetch_add(1, Ordering::SeqCst);
+                Reply::json(&format!(r#"{{"access_token":"tok-{}","expires_in":1}}"#, n))
+            } else {
+                Reply::text(request.header("authorization").unwrap_or(""))
+            }
+        });
+        let client = Client::builder()
+            .oauth2_client_credentials(
+                OAuthConfig::new(server.url("/token"), "app", "s3cret")
+                    .scopes(vec!["read".to_string(), "write".to_string()])
+                    .refresh_margin(Duration::from_millis(700)),
+            )
+            .build()
+            .unwrap();
+
+        let first = client.get(server.url("/api")).await.unwrap();
+        let second = client.get(server.url("/api")).await.unwrap();
+        assert_eq!(first.text(), Some("Bearer tok-0"));
+        assert_eq!(second.text(), Some("Bearer tok-0"));
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let refreshed = client.get(server.url("/api")).await.unwrap();
+        assert_eq!(refreshed.text(), Some("Bearer tok-1"));
+
+        let token_requests: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.path == "/token")
+            .collect();
+        assert_eq!(token_requests.len(), 2);
+        let token_request = &token_requests[0];
+        assert_eq!(token_request.method, "POST");
+        assert!(token_request.body_text().contains("grant_type=client_credentials"));
+        assert!(token_request.body_text().contains("scope=read+write"));
+        assert_eq!(
+            token_request.header("authorization").and_then(parse_authorization),
+            Some(AuthScheme::Basic {
+                username: "app".to_string(),
+                password: "s3cret".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_token_endpoint_failure() {
+        use rust_fetch::auth::OAuthConfig;
+
+        let server = TestServer::start(|request| {
+            if request.path == "/token" {
+                Reply::new(401).body(r#"{"error":"invalid_client"}"#)
+            } else {
+                Reply::text("unreachable")
+            }
+        });
+        let client = Client::builder()
+            .oauth2_client_credentials(OAuthConfig::new(server.url("/token"), "app", "wrong"))
+            .build()
+            .unwrap();
+
+        let error = client.get(server.url("/api")).await.unwrap_err();
+        assert_eq!(error.kind(), "AuthError");
+        assert!(matches!(error, Error::Auth { status: Some(401), .. }));
+        assert!(error.to_string().contains("invalid_client"));
+        assert!(server.requests().iter().all(|request| request.path == "/token"));
+    }
+
+    #[tokio::test]
+    async fn test_json_stream_posts_valid_array() {
+        #[derive(serde::Serialize)]
+        struct Record {
+            id: u32,
+            name: String,
+        }
+
+        let server = TestServer::start(|_| Reply::new(204));
+        let client = Client::new().unwrap();
+        let records = (0..1000).map(|id| Record {
+            id,
+            name: format!("record-{}", id),
+        });
+
+        let response = client
+            .post(server.url("/bulk"))
+            .json_stream(records)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, 204);
+
+        let request = &server.requests()[0];
+        assert_eq!(request.header("content-type"), Some("application/json"));
+        let body: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body.len(), 1000);
+        assert_eq!(body[999]["name"], "record-999");
+
+        client
+            .post(server.url("/bulk"))
+            .json_stream(Vec::<u32>::new())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(server.requests()[1].body_text(), "[]");
+    }
+
+    #[tokio::test]
+    async fn test_json_stream_is_not_replayed_on_retry() {
+        let server = TestServer::start(|_| Reply::new(503));
+        let client = Client::builder()
+            .retry_config(fast_retries(1))
+            .build()
+            .unwrap();
+
+        let error = client
+            .post(server.url("/bulk"))
+            .json_stream(vec![1, 2, 3])
+            .send()
+            .await
+            .unwrap_err();
+
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(server.requests()[0].body_text(), "[1,2,3]");
+        assert!(matches!(error, Error::InvalidInput { .. }), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn test_require_content_type() {
+        let server = TestServer::start(|request| {
+            if request.path == "/data" {
+                Reply::json(r#"{"ok":true}"#)
+            } else {
+                Reply::new(200)
+                    .header("Content-Type", "text/html")
+                    .body("<html>Sign in</html>")
+            }
+        });
+        let client = Client::new().unwrap();
+
+        let response = client
+            .request(Method::Get, server.url("/data"))
+            .require_content_type("application/json")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.json().is_some());
+
+        let error = client
+            .request(Method::Get, server.url("/login"))
+            .require_content_type("application/json")
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Parse { .. }));
+        assert!(error.to_string().contains("expected application/json, got text/html"));
+    }
+
+    #[tokio::test]
+    async fn test_base_urls_round_robin() {
+        let first = TestServer::start(|_| Reply::text("first"));
+        let second = TestServer::start(|_| Reply::text("second"));
+        let client = Client::builder()
+            .base_urls(vec![first.url(""), second.url("")])
+            .build()
+            .unwrap();
+
+        let mut bodies = Vec::new();
+        for _ in 0..4 {
+            let response = client.get("/items").await.unwrap();
+            bodies.push(response.text().unwrap().to_string());
+        }
+
+        assert_eq!(bodies, vec!["first", "second", "first", "second"]);
+        assert_eq!(first.requests().len(), 2);
+        assert_eq!(second.requests().len(), 2);
+        assert!(first.requests().iter().all(|request| request.path == "/items"));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_base_on_retry() {
+        use rust_fetch::types::LoadBalancePolicy;
+
+        let down = TestServer::start(|_| Reply::new(503));
+        let up = TestServer::start(|request| Reply::text(&request.path));
+        let client = Client::builder()
+            .base_urls(vec![down.url(""), up.url("")])
+            .load_balance(LoadBalancePolicy::RoundRobin)
+            .rotate_base_on_retry(true)
+            .retry_config(fast_retries(1))
+            .build()
+            .unwrap();
+
+        let response = client.get("/status?full=1").await.unwrap();
+
+        assert_eq!(response.text(), Some("/status?full=1"));
+        assert_eq!(down.requests().len(), 1);
+        assert_eq!(up.requests().len(), 1);
+
+        // A per-request base is kept for every retry
+        let pinned = TestServer::start(|_| Reply::new(503));
+        let error = client
+            .request(Method::Get, "/status")
+            .base_url(pinned.url(""))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::RetriesExhausted { attempts: 2, .. }));
+        assert_eq!(pinned.requests().len(), 2);
+        assert_eq!(down.requests().len() + up.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_post_and_follow_fetches_created_resource() {
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "POST" => Reply::new(201).header("Location", "/items/7"),
+            _ => Reply::json(r#"{"id":7,"name":"widget"}"#),
+        });
+        let client = Client::new().unwrap();
+
+        let response = client
+            .post_and_follow(server.url("/items"), &serde_json::json!({"name": "widget"}))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.json().unwrap()["id"], 7);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body_text(), r#"{"name":"widget"}"#);
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[1].path, "/items/7");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_tag_aborts_only_that_group() {
+        let server = TestServer::start(|_| Reply::text("done").delay(Duration::from_millis(300)));
+        let client = Client::new().unwrap();
+
+        let spawn = |tag: &str| {
+            let request = client.request(Method::Get, server.url("/widget")).tag(tag);
+            tokio::spawn(request.send())
+        };
+        let chart = [spawn("chart"), spawn("chart")];
+        let table = spawn("table");
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(client.in_flight("chart"), 2);
+        assert_eq!(client.cancel_tag("chart"), 2);
+        assert_eq!(client.in_flight("chart"), 0);
+
+        for handle in chart {
+            let error = handle.await.unwrap().unwrap_err();
+            assert!(matches!(error, Error::Cancelled));
+        }
+        let response = table.await.unwrap().unwrap();
+        assert_eq!(response.text(), Some("done"));
+        assert_eq!(client.in_flight("table"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tag_reaches_on_complete() {
+        use rust_fetch::types::AttemptRecord;
+        use std::sync::{Arc, Mutex};
+
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let tags = Arc::new(Mutex::new(Vec::new()));
+        let completions = tags.clone();
+        let client = Client::builder()
+            .on_complete(move |record: &AttemptRecord, _| {
+                completions.lock().unwrap().push(record.tag.clone());
+            })
+            .build()
+            .unwrap();
+
+        client
+            .request(Method::Get, server.url("/widget"))
+            .tag("chart")
+            .send()
+            .await
+            .unwrap();
+        client.get(server.url("/widget")).await.unwrap();
+
+        assert_eq!(*tags.lock().unwrap(), vec![Some("chart".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn test_paginate_offset_reads_all_pages() {
+        use futures_util::StreamExt;
+
+        #[derive(serde::Deserialize)]
+        struct User {
+            id: usize,
+        }
+
+        let server = TestServer::start(|request| {
+            let query = request.path.split_once('?').map(|(_, q)| q).unwrap_or("");
+            let param = |name: &str| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix(&format!("{}=", name)))
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .unwrap()
+            };
+            let (offset, limit) = (param("offset"), param("limit"));
+            let users: Vec<_> = (offset..(offset + limit).min(7))
+                .map(|id| serde_json::json!({ "id": id }))
+                .collect();
+            Reply::json(&serde_json::json!({ "total": 7, "data": users }).to_string())
+        });
+        let client = Client::new().unwrap();
+
+        let users: Vec<User> = client
+            .paginate_offset(server.url("/users?active=true"), 3, |page| {
+                page["data"].as_array().cloned().unwrap_or_default()
+            })
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let ids: Vec<usize> = users.iter().map(|user| user.id).collect();
+        assert_eq!(ids, (0..7).collect::<Vec<_>>());
+        let paths: Vec<String> = server.requests().iter().map(|r| r.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/users?active=true&offset=0&limit=3",
+                "/users?active=true&offset=3&limit=3",
+                "/users?active=true&offset=6&limit=3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paginate_cursor_follows_cursors() {
+        use futures_util::StreamExt;
+
+        #[derive(serde::Deserialize)]
+        struct Event {
+            id: u32,
+        }
+
+        let server = TestServer::sequence(vec![
+            Reply::json(r#"{"items": [{"id": 1}, {"id": 2}], "next_cursor": "abc"}"#),
+            Reply::json(r#"{"items": [{"id": 3}], "next_cursor": null}"#),
+        ]);
+        let client = Client::new().unwrap();
+
+        let events: Vec<Event> = client
+            .paginate_cursor(
+                server.url("/events?kind=push"),
+                "cursor",
+                |page| page["items"].as_array().cloned().unwrap_or_default(),
+                |page| page["next_cursor"].as_str().map(str::to_string),
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let ids: Vec<u32> = events.iter().map(|event| event.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        let paths: Vec<String> = server.requests().iter().map(|r| r.path.clone()).collect();
+        assert_eq!(paths, vec!["/events?kind=push", "/events?kind=push&cursor=abc"]);
+    }
+
+    #[tokio::test]
+    async fn test_paginate_cursor_stops_on_repeated_cursor_or_empty_page() {
+        use futures_util::StreamExt;
+
+        let server = TestServer::start(|request| {
+            if request.path.starts_with("/empty") {
+                Reply::json(r#"{"items": [], "next_cursor": "more"}"#)
+            } else {
+                Reply::json(r#"{"items": [1], "next_cursor": "same"}"#)
+            }
+        });
+        let client = Client::new().unwrap();
+        let collect = |path: &str| {
+            client
+                .paginate_cursor::<u32, _, _>(
+                    server.url(path),
+                    "cursor",
+                    |page| page["items"].as_array().cloned().unwrap_or_default(),
+                    |page| page["next_cursor"].as_str().map(str::to_string),
+                )
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(collect("/repeat").await, vec![1, 1]);
+        assert_eq!(server.requests().len(), 2);
+
+        assert!(collect("/empty").await.is_empty());
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_error_body_limit_truncates_http_error_body() {
+        use rust_fetch::types::ErrorBodyEnd;
+
+        let page = format!("BEGIN{}END: database unavailable", "x".repeat(1 << 20));
+        let server = TestServer::start(move |_| Reply::new(500).body(page.clone()));
+
+        let body_of = |error: Error| match error {
+            Error::Http { body, .. } => body.unwrap(),
+            other => panic!("unexpected error: {:?}", other),
+        };
+
+        let tail = Client::builder()
+            .error_body_limit(1024, ErrorBodyEnd::Tail)
+            .build()
+            .unwrap();
+        let body = body_of(tail.get(server.url("/")).await.unwrap_err());
+        assert_eq!(body.len(), 1024);
+        assert!(body.ends_with("END: database unavailable"));
+
+        let head = Client::builder()
+            .error_body_limit(16, ErrorBodyEnd::Head)
+            .build()
+            .unwrap();
+        let body = body_of(head.get(server.url("/")).await.unwrap_err());
+        assert_eq!(body, "BEGINxxxxxxxxxxx");
+
+        // A limit above any body size doesn't allocate it up front
+        let unlimited = Client::builder()
+            .error_body_limit(usize::MAX, ErrorBodyEnd::Tail)
+            .build()
+            .unwrap();
+        let body = body_of(unlimited.get(server.url("/")).await.unwrap_err());
+        assert!(body.starts_with("BEGIN") && body.ends_with("END: database unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_query_merge_append_duplicate_and_replace() {
+        let server = TestServer::start(|_| Reply::new(204));
+        let client = Client::builder()
+            .base_url(server.url("/search?api_key=k1&page=1"))
+            .build()
+            .unwrap();
+
+        client
+            .request(Method::Get, "")
+            .query("q", "rust fetch")
+            .send()
+            .await
+            .unwrap();
+        client
+            .request(Method::Get, "")
+            .query("page", "2")
+            .query("page", "3")
+            .send()
+            .await
+            .unwrap();
+        client
+            .request(Method::Get, "")
+            .query("page", "2")
+            .query_replace("page", "9")
+            .query("sort", "asc")
+            .send()
+            .await
+            .unwrap();
+
+        let paths: Vec<String> = server.requests().iter().map(|r| r.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/search?api_key=k1&page=1&q=rust+fetch",
+                "/search?api_key=k1&page=1&page=2&page=3",
+                "/search?api_key=k1&page=9&sort=asc",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_on_unauthorized_refreshes_token_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let server = TestServer::start(|request| match request.header("authorization") {
+            Some("Bearer fresh-1") => Reply::text("secret"),
+            _ => Reply::new(401),
+        });
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let counter = refreshes.clone();
+        let client = Client::builder()
+            .default_header("Authorization", "Bearer expired")
+            .on_unauthorized(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                Box::pin(async move { Ok(format!("fresh-{}", n)) })
+            })
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url("/private")).await.unwrap();
+        assert_eq!(response.text(), Some("secret"));
+        let again = client.get(server.url("/private")).await.unwrap();
+        assert_eq!(again.text(), Some("secret"));
+
+        let headers: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|r| r.header("authorization").unwrap().to_string())
+            .collect();
+        assert_eq!(headers, vec!["Bearer expired", "Bearer fresh-1", "Bearer fresh-1"]);
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+        // A refreshed token that is rejected as well is tried once, not in a loop
+        let stubborn = TestServer::start(|_| Reply::new(401));
+        let error = client.get(stubborn.url("/")).await.unwrap_err();
+        assert!(matches!(error, Error::Http { status: 401, .. }));
+        assert_eq!(stubborn.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_on_unauthorized_runs_once_across_retries() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // 401, refreshed 503, retried 401
+        let server = TestServer::sequence(vec![Reply::new(401), Reply::new(503), Reply::new(401)]);
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let counter = refreshes.clone();
+        let client = Client::builder()
+            .default_header("Authorization", "Bearer expired")
+            .retry_config(fast_retries(3))
+            .on_unauthorized(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok("fresh".to_string()) })
+            })
+            .build()
+            .unwrap();
+
+        let error = client.get(server.url("/private")).await.unwrap_err();
+
+        assert!(matches!(error, Error::Http { status: 401, .. }), "{:?}", error);
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_handle_runs_request_on_that_runtime() {
+        use std::sync::{mpsc, Arc, Mutex};
+
+        let (handle_tx, handle_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let io_thread = std::thread::Builder::new()
+            .name("io-runtime".to_string())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                handle_tx.send(runtime.handle().clone()).unwrap();
+                runtime.block_on(async {
+                    let _ = stop_rx.await;
+                });
+            })
+            .unwrap();
+        let handle = handle_rx.recv().unwrap();
+
+        let server = TestServer::start(|_| Reply::text("pinned"));
+        let threads = Arc::new(Mutex::new(Vec::new()));
+        let seen = threads.clone();
+        let client = Client::builder()
+            .runtime_handle(handle)
+            .before_send(move |_, _, _| {
+                let name = std::thread::current().name().map(str::to_string);
+                seen.lock().unwrap().push(name);
+            })
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url("/")).await.unwrap();
+        assert_eq!(response.text(), Some("pinned"));
+        client.warm_up(server.url("/")).await.unwrap();
+        let stream = client.request(Method::Get, server.url("/")).send_stream().await.unwrap();
+        assert_eq!(stream.status, 200);
+        drop(stream);
+        let sha256 = "3fab5c181bd28a09b64397df76ae2bfaf1eac182979b5fdb7a342858004f36af";
+        client.download_verified(server.url("/"), sha256).await.unwrap();
+        let threads = threads.lock().unwrap().clone();
+        assert_eq!(threads, vec![Some("io-runtime".to_string()); 4]);
+
+        stop_tx.send(()).unwrap();
+        io_thread.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_as_custom_deserializer() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Point {
+            x: i32,
+            label: String,
+        }
+
+        let packed = rmp_serde::to_vec(&Point { x: 7, label: "seven".into() }).unwrap();
+        let server = TestServer::start(move |request| match request.header("accept") {
+            Some("application/msgpack") => Reply::new(200)
+                .header("Content-Type", "application/msgpack")
+                .body(packed.clone()),
+            _ => Reply::new(406),
+        });
+        let client = Client::new().unwrap();
+        let from_msgpack = |bytes: &[u8]| {
+            rmp_serde::from_slice::<Point>(bytes).map_err(|e| Error::Parse {
+                message: e.to_string(),
+                source: None,
+            })
+        };
+
+        let point = client
+            .request(Method::Get, server.url("/point"))
+            .header("Accept", "application/msgpack")
+            .send_as(from_msgpack)
+            .await
+            .unwrap();
+        assert_eq!(point, Point { x: 7, label: "seven".into() });
+
+        let error = client
+            .request(Method::Get, server.url("/point"))
+            .send_as(from_msgpack)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Http { status: 406, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_context_reaches_callbacks() {
+        use rust_fetch::types::AttemptRecord;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+
+        struct Caller {
+            user_id: u32,
+        }
+
+        let hits = AtomicUsize::new(0);
+        let server = TestServer::start(move |_| match hits.fetch_add(1, Ordering::SeqCst) {
+            0 => Reply::new(503),
+            _ => Reply::text("ok"),
+        });
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let retries = events.clone();
+        let completions = events.clone();
+        let client = Client::builder()
+            .retry_config(fast_retries(2))
+            .on_retry(move |record: &AttemptRecord, context| {
+                let user = context.get::<Caller>().map(|caller| caller.user_id);
+                retries.lock().unwrap().push(("retry", user, record.attempt, record.status));
+            })
+            .on_complete(move |record: &AttemptRecord, context| {
+                let user = context.get::<Caller>().map(|caller| caller.user_id);
+                completions.lock().unwrap().push(("complete", user, record.attempt, record.status));
+            })
+            .build()
+            .unwrap();
+
+        client
+            .request(Method::Get, server.url("/"))
+            .context(Caller { user_id: 42 })
+            .send()
+            .await
+            .unwrap();
+        client.get(server.url("/")).await.unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ("retry", Some(42), 1, Some(503)),
+                ("complete", Some(42), 2, Some(200)),
+                ("complete", None, 1, Some(200)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_accept_charset() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder().accept_charset("utf-8").build().unwrap();
+
+        client.get(server.url("/")).await.unwrap();
+        client
+            .request(Method::Get, server.url("/"))
+            .header("Accept-Charset", "iso-8859-1")
+            .send()
+            .await
+            .unwrap();
+        let sent: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|r| r.header("accept-charset").unwrap().to_string())
+            .collect();
+        assert_eq!(sent, vec!["utf-8", "iso-8859-1"]);
+
+        let error = Client::builder().accept_charset("utf-9").build().err().unwrap();
+        assert!(matches!(error, Error::InvalidInput { parameter, .. } if parameter == "accept_charset"));
+    }
+
+    #[tokio::test]
+    async fn test_get_typed_map() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct User {
+            name: String,
+        }
+
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/users/1" => Reply::json(r#"{"name":"ada"}"#),
+            "/users/2" => Reply::json(r#"{"name":"grace"}"#),
+            _ => Reply::new(404),
+        });
+        let client = Client::new().unwrap();
+        let items = (1..=3u32)
+            .map(|id| (id, server.url(&format!("/users/{}", id))))
+            .collect();
+
+        let users = client.get_typed_map::<u32, User>(items).await;
+        assert_eq!(users.len(), 3);
+        assert_eq!(users[&1].as_ref().unwrap(), &User { name: "ada".into() });
+        assert_eq!(users[&2].as_ref().unwrap(), &User { name: "grace".into() });
+        assert!(matches!(users[&3], Err(Error::Http { status: 404, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_trace_strips_sensitive_headers() {
+        let server = TestServer::start(|request| {
+            let echo = request
+                .headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Reply::new(200)
+                .header("Content-Type", "message/http")
+                .body(format!("{} {}\n{}", request.method, request.path, echo))
+        });
+        let client = Client::builder()
+            .default_header("Authorization", "Bearer secret")
+            .build()
+            .unwrap();
+
+        let response = client
+            .trace(server.url("/diag"))
+            .header("X-Probe", "1")
+            .send()
+            .await
+            .unwrap();
+        let echo = String::from_utf8_lossy(response.bytes().unwrap()).to_lowercase();
+        assert!(echo.starts_with("trace /diag"));
+        assert!(echo.contains("x-probe: 1"));
+        assert!(!echo.contains("secret"));
+
+        client
+            .trace(server.url("/diag"))
+            .allow_trace_sensitive(true)
+            .send()
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[0].header("authorization"), None);
+        assert_eq!(requests[1].header("authorization"), Some("Bearer secret"));
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[tokio::test]
+    async fn test_preserve_number_precision() {
+        let server = TestServer::start(|_| {
+            Reply::json(r#"{"id":98765432109876543210,"ratio":0.10000000000000000555}"#)
+        });
+        let client = Client::builder()
+            .preserve_number_precision(true)
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url("/")).await.unwrap();
+        let json = response.json().unwrap();
+        assert_eq!(json["id"].to_string(), "98765432109876543210");
+        assert_eq!(json["ratio"].to_string(), "0.10000000000000000555");
+    }
+
+    #[cfg(not(feature = "arbitrary-precision"))]
+    #[test]
+    fn test_preserve_number_precision_requires_feature() {
+        let error = Client::builder()
+            .preserve_number_precision(true)
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::InvalidInput { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_download_verified() {
+        // SHA-256 of "hello world"
+        const DIGEST: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+        let server = TestServer::start(|_| Reply::new(200).body("hello world"));
+        let client = Client::new().unwrap();
+
+        let body = client.download_verified(server.url("/a"), DIGEST).await.unwrap();
+        assert_eq!(body, b"hello world");
+        let upper = client
+            .download_verified(server.url("/a"), &DIGEST.to_uppercase())
+            .await
+            .unwrap();
+        assert_eq!(upper, b"hello world");
+
+        let wrong = DIGEST.replace('b', "c");
+        let error = client.download_verified(server.url("/a"), &wrong).await.unwrap_err();
+        match error {
+            Error::ChecksumMismatch { expected, actual } => {
+                assert_eq!(expected, wrong);
+                assert_eq!(actual, DIGEST);
+            }
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+
+        let error = client.download_verified(server.url("/a"), "abc").await.unwrap_err();
+        assert!(matches!(error, Error::InvalidInput { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_custom_method() {
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "PURGE" => Reply::text("purged"),
+            _ => Reply::new(405),
+        });
+        let client = Client::new().unwrap();
+
+        let response = client
+            .request(Method::custom("PURGE").unwrap(), server.url("/cache/item"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text(), Some("purged"));
+        assert_eq!(server.requests()[0].method, "PURGE");
+
+        let error = Method::custom("NO GOOD").unwrap_err();
+        assert!(matches!(error, Error::InvalidInput { parameter, .. } if parameter == "method"));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_loop_fails_early() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/a" => Reply::new(302).header("Location", "/b"),
+            _ => Reply::new(302).header("Location", "/a"),
+        });
+        let client = Client::new().unwrap();
+
+        let err = client.get(server.url("/a")).await.unwrap_err();
+
+        assert!(err.to_string().contains("Redirect loop detected"), "unexpected error: {}", err);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_count_and_limit() {
+        use rust_fetch::types::RequestConfig;
+
+        let server = TestServer::start(|request| {
+            match request.path.strip_prefix("/hop/").and_then(|n| n.parse::<u32>().ok()) {
+                Some(0) | None => Reply::text("landed"),
+                Some(n) => Reply::new(302).header("Location", &format!("/hop/{}", n - 1)),
+            }
+        });
+        let client = Client::new().unwrap();
+
+        let response = client.get(server.url("/hop/3")).await.unwrap();
+        assert_eq!(response.text(), Some("landed"));
+        assert_eq!(response.redirect_count(), 3);
+        let direct = client.get(server.url("/hop/0")).await.unwrap();
+        assert_eq!(direct.redirect_count(), 0);
+
+        let config = RequestConfig {
+            max_redirects: 2,
+            ..RequestConfig::default()
+        };
+        let error = client
+            .execute_config(server.url("/hop/3"), config)
+            .await
+            .unwrap_err();
+        match error {
+            Error::Network { message, .. } => assert_eq!(message, "Too many redirects (limit 2)"),
+            other => panic!("expected a redirect limit error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_stream_uploads_file_and_field() {
+        use rust_fetch::types::StreamPart;
+
+        let path = std::env::temp_dir().join(format!("rust-fetch-upload-{}.txt", std::process::id()));
+        std::fs::write(&path, "file contents\nline two").unwrap();
+
+        let server = TestServer::start(|_| Reply::text("stored"));
+        let client = Client::new().unwrap();
+        let response = client
+            .post(server.url("/upload"))
+            .multipart_stream(vec![
+                StreamPart::text("title", "Quarterly report"),
+                StreamPart::file("attachment", &path).content_type("text/plain"),
+            ])
+            .send()
+            .await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(response.unwrap().text(), Some("stored"));
+
+        let request = &server.requests()[0];
+        let content_type = request.header("content-type").unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .expect("multipart content type with boundary");
+        let body = request.body_text();
+        assert!(body.contains(&format!("--{}\r\n", boundary)));
+        assert!(body.contains("name=\"title\"\r\n\r\nQuarterly report\r\n"));
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        let file_part = body.split(&format!("--{}", boundary)).nth(2).unwrap();
+        assert!(file_part.contains(&format!("name=\"attachment\"; filename=\"{}\"", file_name)));
+        assert!(file_part.to_lowercase().contains("content-type: text/plain\r\n"));
+        assert!(file_part.ends_with("\r\n\r\nfile contents\nline two\r\n"));
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+    }
+
+    #[tokio::test]
+    async fn test_expected_total_unknown_for_chunked_body() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/chunked" => Reply::new(200)
+                .header("Content-Type", "application/octet-stream")
+                .chunked(vec![b"abc".to_vec(), b"defg".to_vec()]),
+            _ => Reply::new(200)
+                .header("Content-Type", "application/octet-stream")
+                .body("abcdefg"),
+        });
+        let client = Client::new().unwrap();
+
+        let chunked = client.get(server.url("/chunked")).await.unwrap();
+        assert_eq!(chunked.expected_total(), None);
+        assert_eq!(chunked.bytes().unwrap(), b"abcdefg");
+
+        let sized = client.get(server.url("/sized")).await.unwrap();
+        assert_eq!(sized.expected_total(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_connection_close_is_not_reused() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::new().unwrap();
+
+        client.get(server.url("/a")).await.unwrap();
+        client.get(server.url("/b")).await.unwrap();
+        client
+            .request(Method::Get, server.url("/c"))
+            .connection_close(true)
+            .send()
+            .await
+            .unwrap();
+        client.get(server.url("/d")).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[2].header("connection"), Some("close"));
+        let connections: Vec<_> = requests.iter().map(|r| r.connection).collect();
+        assert_eq!(connections[0], connections[1]);
+        assert_eq!(connections[1], connections[2]);
+        assert_ne!(connections[2], connections[3]);
+    }
+
+    #[tokio::test]
+    async fn test_max_connection_age_replaces_pool() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .max_connection_age(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        client.get(server.url("/a")).await.unwrap();
+        client.get(server.url("/b")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        client.get(server.url("/c")).await.unwrap();
+
+        let connections: Vec<_> = server.requests().iter().map(|r| r.connection).collect();
+        assert_eq!(connections[0], connections[1]);
+        assert_ne!(connections[1], connections[2]);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_set_once_and_overridable() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .x_api_key("first")
+            .x_api_key("client-key")
+            .build()
+            .unwrap();
+
+        client.get(server.url("/default")).await.unwrap();
+        client
+            .request(Method::Get, server.url("/override"))
+            .x_api_key("request-key")
+            .send()
+            .await
+            .unwrap();
+        client
+            .request(Method::Get, server.url("/custom"))
+            .api_key("Authorization-Token", "custom-key")
+            .send()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        let keys = |index: usize| -> Vec<&str> {
+            requests[index]
+                .headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("x-api-key"))
+                .map(|(_, value)| value.as_str())
+                .collect()
+        };
+        assert_eq!(keys(0), ["client-key"]);
+        assert_eq!(keys(1), ["request-key"]);
+        assert_eq!(keys(2), ["client-key"]);
+        assert_eq!(requests[2].header("authorization-token"), Some("custom-key"));
+    }
+
+    #[tokio::test]
+    async fn test_discard_body_keeps_status_and_headers() {
+        let server = TestServer::start(|request| {
+            if request.path == "/missing" {
+                Reply::new(404).header("Content-Type", "text/plain").body("gone")
+            } else {
+                Reply::json(r#"{"ignored":true}"#).header("X-Trace", "abc")
+            }
+        });
+        let client = Client::new().unwrap();
+
+        let response = client
+            .request(Method::Post, server.url("/beacon"))
+            .discard_body()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.headers.get_first("x-trace"), Some("abc"));
+        assert!(matches!(response.body, ResponseBody::Empty));
+
+        // A drained body leaves the connection reusable
+        client.get(server.url("/next")).await.unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[0].connection, requests[1].connection);
+
+        let err = client
+            .request(Method::Get, server.url("/missing"))
+            .discard_body()
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Http { status: 404, body: Some(ref body), .. } if body == "gone"));
+    }
+
+    #[tokio::test]
+    async fn test_follow_redirects_disabled_returns_redirect() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/old" => Reply::new(301).header("Location", "/new"),
+            "/hop" => Reply::new(302).header("Location", "/old"),
+            _ => Reply::text("moved here"),
+        });
+        let client = Client::new().unwrap();
+
+        let error = client
+            .request(Method::Get, server.url("/old"))
+            .follow_redirects(false)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Http { status: 301, .. }));
+        assert_eq!(server.requests().len(), 1);
+
+        let error = client
+            .request(Method::Get, server.url("/hop"))
+            .max_redirects(1)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Network { ref message, .. } if message == "Too many redirects (limit 1)"));
+
+        let response = client
+            .request(Method::Get, server.url("/hop"))
+            .max_redirects(2)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text(), Some("moved here"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_status_is_honored() {
+        let server = TestServer::start(|_| Reply::new(500));
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                retry_on_status: vec![502, 503],
+                ..fast_retries(3)
+            })
+            .build()
+            .unwrap();
+
+        let error = client.get(server.url("/")).await.unwrap_err();
+        assert!(matches!(error, Error::Http { status: 500, .. }));
+        assert_eq!(server.requests().len(), 1);
+
+        let server = TestServer::start(|_| Reply::new(502));
+        let error = client.get(server.url("/")).await.unwrap_err();
+        assert!(matches!(error, Error::RetriesExhausted { attempts: 4, .. }));
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_strict_headers_accepts_single_content_length() {
+        // hyper itself refuses conflicting lengths on native, so the
+        // rejection path is covered by the unit test in client.rs
+        let server = TestServer::start(|_| Reply::text("hello"));
+        let client = Client::builder().strict_headers(true).build().unwrap();
+
+        let response = client.get(server.url("/")).await.unwrap();
+        assert_eq!(response.headers.count("content-length"), 1);
+        assert_eq!(response.text(), Some("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_overrides_backoff() {
+        let server = TestServer::sequence(vec![
+            Reply::new(429).header("Retry-After", "1"),
+            Reply::text("ok"),
+        ]);
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                max_delay: Duration::from_secs(5),
+                ..fast_retries(1)
+            })
+            .build()
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let response = client.get(server.url("/")).await.unwrap();
+        assert_eq!(response.text(), Some("ok"));
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_capped_and_date_form() {
+        // A huge delay is capped by max_delay; a past HTTP-date means no
+        // wait; a malformed value falls back to normal backoff
+        let server = TestServer::sequence(vec![
+            Reply::new(503).header("Retry-After", "3600"),
+            Reply::new(503).header("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            Reply::new(503).header("Retry-After", "later"),
+            Reply::text("ok"),
+        ]);
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                max_delay: Duration::from_millis(50),
+                ..fast_retries(3)
+            })
+            .build()
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let response = client.get(server.url("/")).await.unwrap();
+        assert_eq!(response.text(), Some("ok"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(server.requests().len(), 4);
+
+        // Only 429 and 503 make the wait binding; a 500's is ignored
+        let server = TestServer::sequence(vec![
+            Reply::new(500).header("Retry-After", "5"),
+            Reply::text("ok"),
+        ]);
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                max_delay: Duration::from_secs(5),
+                ..fast_retries(1)
+            })
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+        client.get(server.url("/")).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let server = TestServer::start(|_| Reply::new(503).header("Retry-After", "120"));
+        let error = Client::new().unwrap().get(server.url("/")).await.unwrap_err();
+        assert!(matches!(error, Error::Http { status: 503, retry_after: Some(wait), .. } if wait == Duration::from_secs(120)));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_leaves_connection_pooled() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::new(404),
+            _ => Reply::text("ok"),
+        });
+        let client = Client::builder().base_url(server.url("")).build().unwrap();
+
+        client.warm_up("/").await.unwrap();
+        client.get("/data").await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "HEAD");
+        assert_eq!(requests[0].connection, requests[1].connection);
+    }
+
+    #[tokio::test]
+    async fn test_bearer_and_basic_auth_headers() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .basic_auth("Aladdin", Some("open sesame".to_string()))
+            .bearer_auth("client-token")
+            .build()
+            .unwrap();
+
+        client.get(server.url("/default")).await.unwrap();
+        client
+            .request(Method::Get, server.url("/bearer"))
+            .bearer_auth("request-token")
+            .send()
+            .await
+            .unwrap();
+        client
+            .request(Method::Get, server.url("/basic"))
+            .basic_auth("Aladdin", Some("open sesame".to_string()))
+            .send()
+            .await
+            .unwrap();
+        client
+            .request(Method::Get, server.url("/user-only"))
+            .basic_auth("user", None)
+            .send()
+            .await
+            .unwrap();
+
+        let authorization: Vec<Vec<String>> = server
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .headers
+                    .iter()
+                    .filter(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+                    .map(|(_, value)| value.clone())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(authorization[0], ["Bearer client-token"]);
+        assert_eq!(authorization[1], ["Bearer request-token"]);
+        assert_eq!(authorization[2], ["Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="]);
+        assert_eq!(authorization[3], ["Basic dXNlcjo="]);
+    }
+
+    #[tokio::test]
+    async fn test_query_serde_with_null_modes() {
+        use rust_fetch::types::SerializeNulls;
+
+        #[derive(serde::Serialize)]
+        struct Filter {
+            q: &'static str,
+            cursor: Option<String>,
+        }
+
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::new().unwrap();
+        let filter = Filter {
+            q: "rust",
+            cursor: None,
+        };
+
+        client
+            .request(Method::Get, server.url("/items"))
+            .query_serde_with(&filter, SerializeNulls::Omit)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        client
+            .request(Method::Get, server.url("/items"))
+            .query_serde_with(&filter, SerializeNulls::EmptyString)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        client
+            .post(server.url("/items"))
+            .form_serde_with(&filter, SerializeNulls::EmptyString)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/items?q=rust");
+        assert_eq!(requests[1].path, "/items?cursor=&q=rust");
+        assert_eq!(requests[2].body_text(), "cursor=&q=rust");
+    }
+
+    #[tokio::test]
+    async fn test_deadline_at_in_the_past_fails_without_sending() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder().retry_config(fast_retries(3)).build().unwrap();
+        let started = std::time::Instant::now();
+
+        let err = client
+            .request(Method::Get, server.url("/"))
+            .deadline_at(started - Duration::from_secs(1))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Timeout { .. }), "unexpected error: {:?}", err);
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_caps_attempt_timeout() {
+        let server = TestServer::start(|_| Reply::text("late").delay(Duration::from_millis(500)));
+        let client = Client::new().unwrap();
+        let started = std::time::Instant::now();
+
+        let err = client
+            .request(Method::Get, server.url("/"))
+            .timeout(Duration::from_secs(10))
+            .deadline(Duration::from_secs(5))
+            .deadline_at(started + Duration::from_millis(100))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Timeout { .. }), "unexpected error: {:?}", err);
+        assert!(started.elapsed() < Duration::from_millis(400));
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_pin_certificate_rejects_unverifiable_connection() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let pin = "96:10:CD:3B:F0:44:72:56:64:2B:BC:2B:E5:3D:A5:1D:09:AC:08:7A:16:D3:14:F4:F7:8C:E1:F6:78:18:04:AD";
+
+        let unpinned = Client::new().unwrap().get(server.url("/")).await.unwrap();
+        assert!(unpinned.peer_certificates().is_none());
+
+        let client = Client::builder().pin_certificate(pin).build().unwrap();
+        let err = client.get(server.url("/")).await.unwrap_err();
+        assert!(
+            matches!(err, Error::Network { ref message, .. } if message == "certificate pin mismatch"),
+            "unexpected error: {:?}",
+            err
+        );
+
+        let invalid = Client::builder().pin_certificate("not-a-fingerprint").build();
+        assert!(matches!(invalid, Err(Error::InvalidInput { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_request_base_url_override() {
+        let primary = TestServer::start(|request| Reply::text(&format!("primary {}", request.path)));
+        let other = TestServer::start(|request| Reply::text(&format!("other {}", request.path)));
+        let client = Client::builder().base_url(primary.url("/v1")).build().unwrap();
+
+        let overridden = client
+            .request(Method::Get, "/status")
+            .base_url(other.url("/v2/"))
+            .send()
+            .await
+            .unwrap();
+        let default = client.request(Method::Get, "/status").send().await.unwrap();
+
+        assert_eq!(overridden.text(), Some("other /v2/status"));
+        assert_eq!(default.text(), Some("primary /v1/status"));
+        assert_eq!(primary.requests().len(), 1);
+        assert_eq!(other.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_on_progress_reports_every_chunk() {
+        use futures_util::StreamExt;
+        use std::sync::{Arc, Mutex};
+
+        let server = TestServer::start(|request| {
+            if request.path == "/sized" {
+                return Reply::text(&"x".repeat(3000));
+            }
+            Reply::new(200)
+                .header("Content-Type", "application/octet-stream")
+                .chunked(vec![vec![1; 1000], vec![2; 1000], vec![3; 500]])
+                .chunk_delay(Duration::from_millis(5))
+        });
+        let client = Client::new().unwrap();
+        let recorder = || {
+            let updates = Arc::new(Mutex::new(Vec::new()));
+            let sink = updates.clone();
+            (updates, move |done, total| sink.lock().unwrap().push((done, total)))
+        };
+
+        let (updates, callback) = recorder();
+        client
+            .request(Method::Get, server.url("/chunked"))
+            .on_progress(callback)
+            .send()
+            .await
+            .unwrap();
+        let updates = updates.lock().unwrap().clone();
+        assert!(updates.len() > 1, "only {} update(s)", updates.len());
+        assert!(updates.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(updates.last(), Some(&(2500, None)));
+
+        let (updates, callback) = recorder();
+        let response = client
+            .request(Method::Get, server.url("/sized"))
+            .on_progress(callback)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().map(str::len), Some(3000));
+        assert_eq!(updates.lock().unwrap().last(), Some(&(3000, Some(3000))));
+
+        let (updates, callback) = recorder();
+        let stream = client
+            .request(Method::Get, server.url("/chunked"))
+            .on_progress(callback)
+            .send_stream()
+            .await
+            .unwrap();
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(updates.lock().unwrap().len(), chunks.len());
+        assert_eq!(updates.lock().unwrap().last(), Some(&(2500, None)));
+    }
+
+    #[tokio::test]
+    async fn test_cookie_store_sends_cookies_back() {
+        let server = TestServer::start(|request| {
+            if request.path == "/login" {
+                return Reply::text("ok")
+                    .header("Set-Cookie", "session=abc123; Path=/")
+                    .header("Set-Cookie", "theme=dark; Path=/");
+            }
+            Reply::text(request.header("cookie").unwrap_or("none"))
+        });
+
+        let client = Client::builder().cookie_store(true).build().unwrap();
+        client.get(server.url("/login")).await.unwrap();
+        let echoed = client.get(server.url("/me")).await.unwrap();
+        let mut sent: Vec<&str> = echoed.text().unwrap().split("; ").collect();
+        sent.sort_unstable();
+        assert_eq!(sent, vec!["session=abc123", "theme=dark"]);
+        let mut cookies = client.cookies(server.url("/"));
+        cookies.sort();
+        assert_eq!(
+            cookies,
+            vec![
+                ("session".to_string(), "abc123".to_string()),
+                ("theme".to_string(), "dark".to_string()),
+            ]
+        );
+
+        let plain = Client::new().unwrap();
+        plain.get(server.url("/login")).await.unwrap();
+        assert_eq!(plain.get(server.url("/me")).await.unwrap().text(), Some("none"));
+        assert!(plain.cookies(server.url("/")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_honor_keep_alive_hints_replaces_pool_once() {
+        let server = TestServer::start(|_| Reply::text("ok").header("Keep-Alive", "timeout=5, max=100"));
+        let client = Client::builder().honor_keep_alive_hints(true).build().unwrap();
+
+        let first = client.get(server.url("/")).await.unwrap();
+        client.get(server.url("/")).await.unwrap();
+        client.get(server.url("/")).await.unwrap();
+
+        assert_eq!(first.keep_alive().and_then(|hint| hint.max), Some(100));
+        let connections: Vec<usize> = server.requests().iter().map(|r| r.connection).collect();
+        // The first hint shortens the idle timeout, replacing the pool;
+        // the same hint again leaves it alone
+        assert_ne!(connections[0], connections[1]);
+        assert_eq!(connections[1], connections[2]);
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_hints_are_per_host() {
+        let short = TestServer::start(|_| Reply::text("ok").header("Keep-Alive", "timeout=5"));
+        let long = TestServer::start(|_| Reply::text("ok").header("Keep-Alive", "timeout=120"));
+        let plain = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder().honor_keep_alive_hints(true).build().unwrap();
+
+        client.get(plain.url("/")).await.unwrap();
+        client.get(long.url("/")).await.unwrap();
+        client.get(short.url("/")).await.unwrap();
+        client.get(short.url("/")).await.unwrap();
+        client.get(long.url("/")).await.unwrap();
+        client.get(plain.url("/")).await.unwrap();
+
+        let connections = |server: &TestServer| server.requests().iter().map(|r| r.connection).collect::<Vec<_>>();
+        // Only the host with the shorter hint moves to a new pool
+        let short = connections(&short);
+        assert_ne!(short[0], short[1]);
+        let long = connections(&long);
+        assert_eq!(long[0], long[1]);
+        let plain = connections(&plain);
+        assert_eq!(plain[0], plain[1]);
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_fails_fast() {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .connect_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+
+        // Non-routable: the SYN goes unanswered, or the sandbox rejects it
+        let err = client.get("http://10.255.255.1:81/").await.unwrap_err();
+
+        assert!(
+            matches!(err, Error::Timeout { .. } | Error::Network { .. }),
+            "unexpected error: {:?}",
+            err
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_reports_applied_duration() {
+        let server = TestServer::start(|_| Reply::text("late").delay(Duration::from_millis(500)));
+        let client = Client::new().unwrap();
+
+        let err = client
+            .request(Method::Get, server.url("/slow"))
+            .timeout(Duration::from_millis(50))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Timeout { duration_ms: 50 }), "unexpected error: {:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_request_metrics_sum_backoff() {
+        let server = TestServer::sequence(vec![
+            Reply::new(503),
+            Reply::new(503),
+            Reply::text("ok"),
+        ]);
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                max_retries: 2,
+                initial_delay: Duration::from_millis(20),
+                multiplier: 2.0,
+                max_delay: Duration::from_secs(1),
+                ..Default::default()
+            })
+            .request_metrics(true)
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url("/flaky")).await.unwrap();
+
+        let metrics = response.metrics.unwrap();
+        assert_eq!(metrics.attempts, 3);
+        assert_eq!(metrics.backoff_total, Duration::from_millis(60));
+        assert!(metrics.total >= metrics.backoff_total);
+    }
+
+    #[tokio::test]
+    async fn test_put_upsert_reports_creation() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Item {
+            id: u32,
+        }
+
+        let server = TestServer::sequence(vec![
+            Reply::new(201)
+                .header("Content-Type", "application/json")
+                .body(r#"{"id": 7}"#),
+            Reply::new(204),
+        ]);
+        let client = Client::new().unwrap();
+        let body = serde_json::json!({"name": "widget"});
+
+        let created = client.put_upsert::<_, Item>(server.url("/items/7"), &body).await.unwrap();
+        assert!(created.created);
+        assert_eq!(created.value, Some(Item { id: 7 }));
+
+        let updated = client.put_upsert::<_, Item>(server.url("/items/7"), &body).await.unwrap();
+        assert!(!updated.created);
+        assert_eq!(updated.value, None);
+
+        let requests = server.requests();
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].body_text(), r#"{"name":"widget"}"#);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_test_clock_triggers_deadline_without_waiting() {
+        use rust_fetch::time::TestClock;
+
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let clock = TestClock::new();
+        let client = Client::builder().clock(clock.clone()).build().unwrap();
+        let started = std::time::Instant::now();
+
+        let on_time = client
+            .request(Method::Get, server.url("/"))
+            .deadline(Duration::from_secs(60));
+        let late = client
+            .request(Method::Get, server.url("/"))
+            .deadline(Duration::from_secs(60));
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(on_time.send().await.unwrap().text(), Some("ok"));
+
+        clock.advance(Duration::from_secs(31));
+        let err = late.send().await.unwrap_err();
+
+        assert!(matches!(err, Error::Timeout { duration_ms: 0 }), "unexpected error: {:?}", err);
+        assert_eq!(server.requests().len(), 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_client_get_and_post() {
+        use rust_fetch::blocking::BlockingClient;
+
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "POST" => Reply::json(&request.body_text()),
+            _ => Reply::json(r#"{"id": 1, "name": "rust"}"#),
+        });
+        let client = BlockingClient::from_builder(Client::builder().base_url(server.url("/"))).unwrap();
+
+        let response = client.get("/users/1").unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.json(), Some(&serde_json::json!({"id": 1, "name": "rust"})));
+
+        let echoed = client
+            .post("/users")
+            .header("X-Trace", "abc")
+            .json(&serde_json::json!({"name": "new"}))
+            .unwrap()
+            .send()
+            .unwrap();
+        assert_eq!(echoed.json(), Some(&serde_json::json!({"name": "new"})));
+        assert_eq!(server.requests()[1].header("x-trace"), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_body_contains() {
+        let server = TestServer::sequence(vec![
+            Reply::text("upstream busy, please retry"),
+            Reply::text("done"),
+            Reply::text("done"),
+        ]);
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                retry_on_body_contains: vec!["please retry".to_string()],
+                ..fast_retries(3)
+            })
+            .build()
+            .unwrap();
+
+        let (response, attempts) = client
+            .request(Method::Get, server.url("/flaky"))
+            .send_with_attempts()
+            .await
+            .unwrap();
+        assert_eq!(response.text(), Some("done"));
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].status, Some(200));
+        assert_eq!(attempts[0].error, Some("HttpError"));
+
+        // A clean 200 is not retried
+        let response = client.get(server.url("/clean")).await.unwrap();
+        assert_eq!(response.text(), Some("done"));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_body_returns_response_when_no_retry_follows() {
+        let server = TestServer::start(|_| Reply::text("upstream busy, please retry"));
+        let client = Client::builder()
+            .retry_config(RetryConfig {
+                retry_on_body_contains: vec!["please retry".to_string()],
+                initial_delay: Duration::from_secs(5),
+                max_delay: Duration::from_secs(5),
+                ..fast_retries(3)
+            })
+            .build()
+            .unwrap();
+
+        // The backoff would outlast the deadline, so no retry is made
+        let response = client
+            .request(Method::Get, server.url("/flaky"))
+            .deadline(Duration::from_secs(1))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.text(), Some("upstream busy, please retry"));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_error_on_status_opt_out_returns_response() {
+        let server = TestServer::start(|_| {
+            Reply::new(404)
+                .header("Content-Type", "application/json")
+                .body(r#"{"error": "no such user"}"#)
+        });
+
+        let strict = Client::new().unwrap();
+        let err = strict.get(server.url("/users/9")).await.unwrap_err();
+        assert!(matches!(err, Error::Http { status: 404, .. }));
+
+        let lenient = Client::builder().error_on_status(false).build().unwrap();
+        let response = lenient.get(server.url("/users/9")).await.unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.json(), Some(&serde_json::json!({"error": "no such user"})));
+
+        let err = response.error_for_status().unwrap_err();
+        assert!(matches!(err, Error::Http { status: 404, ref status_text, .. } if status_text == "Not Found"));
+    }
+
+    #[tokio::test]
+    async fn test_accept_header_follows_response_format() {
+        let server = TestServer::start(|_| Reply::json("{}"));
+        let client = Client::new().unwrap();
+
+        client
+            .request(Method::Get, server.url("/"))
+            .response_format(ResponseFormat::Json)
+            .send()
+            .await
+            .unwrap();
+        client.get(server.url("/")).await.unwrap();
+        client
+            .request(Method::Get, server.url("/"))
+            .response_format(ResponseFormat::Json)
+            .header("Accept", "application/vnd.api+json")
+            .send()
+            .await
+            .unwrap();
+
+        let accepts: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|r| r.header("accept").unwrap().to_string())
+            .collect();
+        assert_eq!(accepts, vec!["application/json", "*/*", "application/vnd.api+json"]);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_prometheus_recorder_counts_requests() {
+        use rust_fetch::metrics::PrometheusRecorder;
+
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/missing" => Reply::new(404),
+            _ => Reply::text("ok"),
+        });
+        let recorder = PrometheusRecorder::new();
+        let client = Client::builder().on_complete(recorder.on_complete()).build().unwrap();
+
+        client.get(server.url("/a")).await.unwrap();
+        client.get(server.url("/b")).await.unwrap();
+        client.get(server.url("/missing")).await.unwrap_err();
+
+        let text = recorder.gather();
+        let host = server.url("").trim_start_matches("http://").trim_end_matches('/').to_string();
+        assert!(text.contains("# TYPE http_client_requests_total counter"));
+        assert!(text.contains("# TYPE http_client_request_duration_seconds histogram"));
+        assert!(text.contains(&format!(
+            "http_client_requests_total{{method=\"GET\",host=\"{}\",status=\"2xx\"}} 2",
+            host
+        )));
+        assert!(text.contains(&format!(
+            "http_client_request_duration_seconds_count{{method=\"GET\",host=\"{}\",status=\"4xx\"}} 1",
+            host
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_send_duplex_echoes_while_uploading() {
+        use futures_util::StreamExt;
+
+        let server = TestServer::duplex_echo();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+        let upload = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (Ok(chunk), rx))
+        });
+
+        let mut echoes = Client::new()
+            .unwrap()
+            .post(server.url("/rpc"))
+            .send_duplex(upload)
+            .await
+            .unwrap()
+            .boxed();
+
+        // Each chunk is only sent after the previous echo came back
+        for message in ["ping", "pong"] {
+            tx.send(message.as_bytes().to_vec()).unwrap();
+            assert_eq!(echoes.next().await.unwrap().unwrap(), message.as_bytes());
+        }
+        drop(tx);
+        assert!(echoes.next().await.is_none());
+        assert_eq!(server.requests()[0].body_text(), "pingpong");
+    }
+
+    #[tokio::test]
+    async fn test_see_other_drops_body_factory() {
+        use rust_fetch::types::Body;
+
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/upload" => Reply::new(303).header("Location", "/result"),
+            _ => Reply::text("done"),
+        });
+        let client = Client::new().unwrap();
+
+        client
+            .post(server.url("/upload"))
+            .body_factory(|| Body::Text("payload".to_string()))
+            .header("Content-Type", "text/plain")
+            .send()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].body_text(), "payload");
+        assert_eq!((requests[1].method.as_str(), requests[1].path.as_str()), ("GET", "/result"));
+        assert!(requests[1].body.is_empty());
+        assert_eq!(requests[1].header("content-type"), None);
+    }
+
+    #[tokio::test]
+    async fn test_refreshed_token_stays_with_origin() {
+        let private = TestServer::start(|request| match request.header("authorization") {
+            Some("Bearer fresh") => Reply::text("secret"),
+            _ => Reply::new(401),
+        });
+        let other = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .default_header("Authorization", "Bearer expired")
+            .on_unauthorized(|| Box::pin(async { Ok("fresh".to_string()) }))
+            .build()
+            .unwrap();
+
+        client.get(private.url("/")).await.unwrap();
+        client.get(other.url("/")).await.unwrap();
+        let error = client
+            .request(Method::Get, private.url("/"))
+            .bearer_auth("mine")
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Http { status: 401, .. }));
+        let sent = |server: &TestServer| -> Vec<String> {
+            server
+                .requests()
+                .iter()
+                .map(|r| r.header("authorization").unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(sent(&private), vec!["Bearer expired", "Bearer fresh", "Bearer mine"]);
+        assert_eq!(sent(&other), vec!["Bearer expired"]);
+    }
+
+    #[tokio::test]
+    async fn test_rotated_pool_keeps_transport_settings() {
+        let server = TestServer::start(|_| Reply::text("ok"));
+        let client = Client::builder()
+            .preserve_header_case(true)
+            .max_connection_age(Duration::from_millis(1))
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            client
+                .request(Method::Get, server.url("/"))
+                .header("x-trace-id", "1")
+                .send()
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let requests = server.requests();
+        assert_ne!(requests[0].connection, requests[1].connection);
+        for request in &requests {
+            assert!(request.headers.iter().any(|(name, _)| name == "X-Trace-Id"));
+        }
+    }
+
+    /// Self-signed P-256 certificate for `localhost` and `127.0.0.1`
+    #[cfg(feature = "tls")]
+    const TLS_CERT: &str = "\
+        MIIBwzCCAWigAwIBAgIUQ84mGr2KnJZvRChnA96NWPhs5oAwCgYIKoZIzj0EAwIwKTETMBEGA1UECgwKcnVzdC1mZXRjaDESMBAGA1UE\
+        AwwJbG9jYWxob3N0MCAXDTI2MTAxNjIwMTQwOVoYDzIxMjYwOTIyMjAxNDA5WjApMRMwEQYDVQQKDApydXN0LWZldGNoMRIwEAYDVQQD\
+        DAlsb2NhbGhvc3QwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAQK3J72yKUkfkSAT1tl1Qr4++9uJ7+isApfumZVhTFdUf78ts/6q0A+\
+        QE91/hHRu2Ib/70o2v3XgEwGH1VOHAwRo2wwajAdBgNVHQ4EFgQUtR6oEacB1UhYw/Ja4DvaFpzlKMkwHwYDVR0jBBgwFoAUtR6oEacB\
+        1UhYw/Ja4DvaFpzlKMkwGgYDVR0RBBMwEYIJbG9jYWxob3N0hwR/AAABMAwGA1UdEwEB/wQCMAAwCgYIKoZIzj0EAwIDSQAwRgIhAPYw\
+        ZlU6UWInz4iNVOoaK/td3zpF9/Bu2zQpaw8D+Sm1AiEA3JAy7AJPcqhCRoIygrIAQ9KyZ0tJFtxAGATouEzP92w=";
+
+    /// PKCS#8 private key of [`TLS_CERT`]
+    #[cfg(feature = "tls")]
+    const TLS_KEY: &str = "\
+        MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgf1jUZH95GD/eL3q2yx5s3DnOaRLZC7lIs9jDBI9a5NChRANCAAQK3J72\
+        yKUkfkSAT1tl1Qr4++9uJ7+isApfumZVhTFdUf78ts/6q0A+QE91/hHRu2Ib/70o2v3XgEwGH1VOHAwR";
+
+    #[cfg(feature = "tls")]
+    fn tls_server() -> TestServer {
+        use base64::Engine;
+        let decode = |b64: &str| base64::engine::general_purpose::STANDARD.decode(b64).unwrap();
+        TestServer::tls(decode(TLS_CERT), decode(TLS_KEY), "secure")
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_pin_certificate_accepts_matching_server() {
+        let server = tls_server();
+        let client = Client::builder()
+            .pin_certificate("69:91:B2:A6:AD:C4:A8:AC:ED:78:64:17:D3:A6:18:63:6C:45:58:8F:9E:31:77:90:13:6F:B9:15:C7:6B:79:AF")
+            .build()
+            .unwrap();
+
+        let response = client.get(format!("https://localhost:{}/", server.addr().port())).await.unwrap();
+
+        assert_eq!(response.text(), Some("secure"));
+        let certificates = response.peer_certificates().unwrap();
+        assert_eq!(certificates[0].subject, "O=rust-fetch, CN=localhost");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_pin_certificate_mismatch_fails_before_sending() {
+        let server = tls_server();
+        let client = Client::builder().pin_certificate("00".repeat(32)).build().unwrap();
+
+        let err = client.get(format!("https://localhost:{}/", server.addr().port())).await.unwrap_err();
+
+        assert!(
+            matches!(err, Error::Network { ref message, .. } if message == "certificate pin mismatch"),
+            "unexpected error: {:?}",
+            err
+        );
+        // The handshake failed, so the server never saw a request
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(server.requests().is_empty());
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_timeout_spans_redirect_chain() {
+        let server = TestServer::start(|request| {
+            let reply = match request.path.as_str() {
+                "/start" => Reply::new(302).header("Location", "/end"),
+                _ => Reply::text("done"),
+            };
+            reply.delay(Duration::from_millis(300))
+        });
+        let client = Client::builder().timeout(Duration::from_millis(450)).build().unwrap();
+
+        // Each hop fits in the timeout, but the chain doesn't
+        let err = client.get(server.url("/start")).await.unwrap_err();
+
+        assert!(matches!(err, Error::Timeout { duration_ms: 450 }), "unexpected error: {:?}", err);
+    }
+}
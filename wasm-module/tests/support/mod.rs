@@ -0,0 +1,460 @@
+//! Minimal in-process HTTP/1.1 server for exercising the client without network access
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A request as seen by the test server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Request method
+    pub method: String,
+    /// Request target (path and query)
+    pub path: String,
+    /// Headers with their original casing, in wire order
+    pub headers: Vec<(String, String)>,
+    /// Request body (de-chunked)
+    pub body: Vec<u8>,
+    /// Sequential id of the TCP connection the request arrived on
+    pub connection: usize,
+}
+
+impl RecordedRequest {
+    /// Get the first header value (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get the body as UTF-8 text
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// A canned reply returned by the test server
+#[derive(Debug, Clone)]
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    chunks: Option<Vec<Vec<u8>>>,
+    delay: Duration,
+    chunk_delay: Duration,
+}
+
+impl Reply {
+    /// Create a reply with the given status and an empty body
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            chunks: None,
+            delay: Duration::ZERO,
+            chunk_delay: Duration::ZERO,
+        }
+    }
+
+    /// Create a 200 reply with a JSON body
+    pub fn json(body: &str) -> Self {
+        Self::new(200)
+            .header("Content-Type", "application/json")
+            .body(body)
+    }
+
+    /// Create a 200 reply with a plain text body
+    pub fn text(body: &str) -> Self {
+        Self::new(200).header("Content-Type", "text/plain").body(body)
+    }
+
+    /// Add a response header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the response body
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Send the body using chunked transfer encoding
+    pub fn chunked(mut self, chunks: Vec<Vec<u8>>) -> Self {
+        self.chunks = Some(chunks);
+        self
+    }
+
+    /// Wait before sending the response head
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Wait between body chunks
+    pub fn chunk_delay(mut self, delay: Duration) -> Self {
+        self.chunk_delay = delay;
+        self
+    }
+}
+
+/// A test server bound to a random local port
+pub struct TestServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+/// Connection behaviour shared by a server's connection threads
+#[derive(Clone, Copy, Default)]
+struct Options {
+    /// Answer `Expect: 100-continue` with this final status instead of 100
+    reject_expect: Option<u16>,
+}
+
+impl TestServer {
+    /// Start a server answering every request with the handler's reply
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> Reply + Send + Sync + 'static,
+    {
+        Self::start_with(Options::default(), handler)
+    }
+
+    /// Start a server that rejects `Expect: 100-continue` requests with
+    /// `status` before reading their body
+    pub fn rejecting_expect<F>(status: u16, handler: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> Reply + Send + Sync + 'static,
+    {
+        let options = Options {
+            reject_expect: Some(status),
+        };
+        Self::start_with(options, handler)
+    }
+
+    fn start_with<F>(options: Options, handler: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler = Arc::new(handler);
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { break };
+                let id = connections.fetch_add(1, Ordering::SeqCst);
+                let handler = handler.clone();
+                let recorded = recorded.clone();
+                thread::spawn(move || {
+                    serve_connection(stream, id, options, handler.as_ref(), &recorded)
+                });
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    /// Start a server answering with `replies` in order, repeating the last one
+    pub fn sequence(replies: Vec<Reply>) -> Self {
+        let next = AtomicUsize::new(0);
+        Self::start(move |_| {
+            let i = next.fetch_add(1, Ordering::SeqCst).min(replies.len() - 1);
+            replies[i].clone()
+        })
+    }
+
+    /// Start a server that streams each chunk of a chunked request body
+    /// back as a response chunk as soon as it arrives
+    ///
+    /// The response head is sent before any of the body is read, so the
+    /// exchange only completes if the client reads while still uploading.
+    /// Serves one request per connection.
+    pub fn duplex_echo() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().enumerate() {
+                let Ok(stream) = stream else { break };
+                let recorded = recorded.clone();
+                thread::spawn(move || serve_echo(stream, id, &recorded));
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    /// Start an HTTPS server presenting the DER certificate `cert`, whose
+    /// PKCS#8 private key is `key`, answering every request with `body`
+    ///
+    /// Requests are recorded with their method and path only. Serves one
+    /// request per connection.
+    #[cfg(feature = "tls")]
+    pub fn tls(cert: Vec<u8>, key: Vec<u8>, body: &'static str) -> Self {
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(vec![rustls::Certificate(cert)], rustls::PrivateKey(key))
+            .expect("test certificate");
+        let config = Arc::new(config);
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().enumerate() {
+                let Ok(stream) = stream else { break };
+                let connection = rustls::ServerConnection::new(config.clone()).unwrap();
+                let recorded = recorded.clone();
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(rustls::StreamOwned::new(connection, stream));
+                    let mut line = String::new();
+                    // Fails when the client aborts the handshake
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let mut parts = line.split_whitespace();
+                    let request = RecordedRequest {
+                        method: parts.next().unwrap_or_default().to_string(),
+                        path: parts.next().unwrap_or_default().to_string(),
+                        headers: Vec::new(),
+                        body: Vec::new(),
+                        connection: id,
+                    };
+                    while !line.ends_with("\r\n\r\n") && reader.read_line(&mut line).unwrap_or(0) > 0 {}
+                    recorded.lock().unwrap().push(request);
+                    let reply = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let stream = reader.get_mut();
+                    let _ = stream.write_all(reply.as_bytes());
+                    let _ = stream.flush();
+                    stream.conn.send_close_notify();
+                    let _ = stream.flush();
+                });
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    /// Get the server address
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Build an absolute URL for a path on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// All requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn serve_connection(
+    stream: TcpStream,
+    connection: usize,
+    options: Options,
+    handler: &(dyn Fn(&RecordedRequest) -> Reply + Send + Sync),
+    recorded: &Mutex<Vec<RecordedRequest>>,
+) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let request = match read_request(&mut reader, &mut writer, connection, options) {
+            Some(Ok(request)) => request,
+            Some(Err(rejected)) => {
+                recorded.lock().unwrap().push(rejected);
+                let status = options.reject_expect.unwrap_or(417);
+                let head = format!(
+                    "HTTP/1.1 {status} Rejected\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                let _ = writer.write_all(head.as_bytes());
+                return;
+            }
+            None => return,
+        };
+        let close = request
+            .header("connection")
+            .is_some_and(|v| v.eq_ignore_ascii_case("close"));
+        recorded.lock().unwrap().push(request.clone());
+
+        let reply = handler(&request);
+        let head_only = request.method == "HEAD";
+        if write_reply(&mut writer, &reply, head_only).is_err() || close {
+            return;
+        }
+    }
+}
+
+fn serve_echo(stream: TcpStream, connection: usize, recorded: &Mutex<Vec<RecordedRequest>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let Some(mut request) = read_head(&mut reader, connection) else {
+        return;
+    };
+    let head = "HTTP/1.1 200 Test\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n";
+    if writer.write_all(head.as_bytes()).and_then(|()| writer.flush()).is_err() {
+        return;
+    }
+
+    loop {
+        let mut size = String::new();
+        if reader.read_line(&mut size).is_err() {
+            return;
+        }
+        let Ok(size) = usize::from_str_radix(size.trim(), 16) else {
+            return;
+        };
+        let mut chunk = vec![0; size + 2];
+        if reader.read_exact(&mut chunk).is_err() {
+            return;
+        }
+        request.body.extend_from_slice(&chunk[..size]);
+        // Record before the terminating chunk lets the client finish
+        if size == 0 {
+            recorded.lock().unwrap().push(request.clone());
+        }
+        // The chunk already ends in CRLF, so it is echoed as-is
+        let echoed = writer
+            .write_all(format!("{size:x}\r\n").as_bytes())
+            .and_then(|()| writer.write_all(&chunk))
+            .and_then(|()| writer.flush());
+        if echoed.is_err() || size == 0 {
+            return;
+        }
+    }
+}
+
+/// Read a request line and headers
+fn read_head(reader: &mut BufReader<TcpStream>, connection: usize) -> Option<RecordedRequest> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':')?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+        connection,
+    })
+}
+
+/// Read one request; `Err` carries a request rejected before its body
+fn read_request(
+    reader: &mut BufReader<TcpStream>,
+    writer: &mut TcpStream,
+    connection: usize,
+    options: Options,
+) -> Option<Result<RecordedRequest, RecordedRequest>> {
+    let mut request = read_head(reader, connection)?;
+
+    if request
+        .header("expect")
+        .is_some_and(|v| v.eq_ignore_ascii_case("100-continue"))
+    {
+        if options.reject_expect.is_some() {
+            return Some(Err(request));
+        }
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").ok()?;
+    }
+
+    if request
+        .header("transfer-encoding")
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+    {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size).ok()?;
+            let size = usize::from_str_radix(size.trim(), 16).ok()?;
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).ok()?;
+            if size == 0 {
+                break;
+            }
+            request.body.extend_from_slice(&chunk[..size]);
+        }
+    } else if let Some(len) = request.header("content-length") {
+        let mut body = vec![0; len.parse().ok()?];
+        reader.read_exact(&mut body).ok()?;
+        request.body = body;
+    }
+
+    Some(Ok(request))
+}
+
+fn write_reply(writer: &mut TcpStream, reply: &Reply, head_only: bool) -> std::io::Result<()> {
+    thread::sleep(reply.delay);
+
+    let mut head = format!("HTTP/1.1 {} Test\r\n", reply.status);
+    for (name, value) in &reply.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    let has_length = reply
+        .headers
+        .iter()
+        .any(|(k, _)| k.eq_ignore_ascii_case("content-length"));
+    if reply.chunks.is_some() {
+        head.push_str("Transfer-Encoding: chunked\r\n");
+    } else if !has_length {
+        head.push_str(&format!("Content-Length: {}\r\n", reply.body.len()));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    if head_only {
+        return writer.flush();
+    }
+
+    match &reply.chunks {
+        Some(chunks) => {
+            for chunk in chunks {
+                writer.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())?;
+                writer.write_all(chunk)?;
+                writer.write_all(b"\r\n")?;
+                writer.flush()?;
+                thread::sleep(reply.chunk_delay);
+            }
+            writer.write_all(b"0\r\n\r\n")?;
+        }
+        None => writer.write_all(&reply.body)?,
+    }
+    writer.flush()
+}
@@ -61,11 +61,16 @@ export interface RequestOptions {
   followRedirects?: boolean;
 }
 
-export interface HttpError {
+export interface HttpError extends Error {
+  /**
+   * Error name, identical to `kind`
+   */
+  name: HttpError['kind'];
+  
   /**
    * Error type/kind
    */
-  kind: 'NetworkError' | 'HttpError' | 'ParseError' | 'TimeoutError' | 'InvalidInputError' | 'JsInteropError' | 'CancelledError';
+  kind: 'NetworkError' | 'HttpError' | 'ParseError' | 'TimeoutError' | 'InvalidInputError' | 'JsInteropError' | 'CancelledError' | 'RetriesExhaustedError' | 'QuotaExceededError' | 'AuthError' | 'ChecksumMismatchError';
   
   /**
    * Human-readable error message
@@ -73,7 +78,7 @@ export interface HttpError {
   message: string;
   
   /**
-   * HTTP status code (for HttpError)
+   * HTTP status code (for HttpError, and AuthError when the token endpoint responded)
    */
   status?: number;
   
@@ -87,6 +92,11 @@ export interface HttpError {
    */
   body?: string;
   
+  /**
+   * Wait requested by the server's Retry-After header, in milliseconds (for HttpError)
+   */
+  retryAfterMs?: number;
+  
   /**
    * Timeout duration in milliseconds (for TimeoutError)
    */
@@ -96,6 +106,36 @@ export interface HttpError {
    * Parameter name (for InvalidInputError)
    */
   parameter?: string;
+  
+  /**
+   * Total attempts made (for RetriesExhaustedError)
+   */
+  attempts?: number;
+  
+  /**
+   * Kind of the final attempt's error (for RetriesExhaustedError)
+   */
+  lastKind?: string;
+  
+  /**
+   * Configured transfer quota in bytes (for QuotaExceededError)
+   */
+  limit?: number;
+  
+  /**
+   * Bytes transferred so far (for QuotaExceededError)
+   */
+  used?: number;
+  
+  /**
+   * Expected SHA-256 digest, lowercase hex (for ChecksumMismatchError)
+   */
+  expected?: string;
+  
+  /**
+   * Digest of the received body, lowercase hex (for ChecksumMismatchError)
+   */
+  actual?: string;
 }
 
 /**
@@ -193,6 +233,13 @@ export declare class WasmClient {
   request(method: string, url: string, headers?: Record<string, string>, body?: string | object): Promise<HttpResponse>;
 }
 
+/**
+ * Set a base URL that fetch_json, fetch_text and fetch_with_options resolve
+ * relative URLs against; absolute URLs are unaffected
+ * @param url - The base URL, or an empty string to clear it
+ */
+export declare function set_default_base_url(url: string): void;
+
 /**
  * Simple fetch function for JSON data
  * @param url - The URL to fetch from